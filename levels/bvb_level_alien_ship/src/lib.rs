@@ -0,0 +1,3 @@
+// The Alien Ship map, built from `alien_ship.toml` by `bvb_level_builder`.
+
+bvb_level_builder::level_module!("../alien_ship.toml");
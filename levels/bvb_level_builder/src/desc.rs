@@ -0,0 +1,661 @@
+use bvb_level_kit::{Hazard, HazardInfo, HazardState};
+use bvb_raster::Rgba;
+
+use crate::toml::{self, Table, Value};
+
+/// Largest image edge in pixels the host accepts, at any scale.
+pub const MAX_IMAGE_DIMENSION: u32 = 2048;
+
+/// A level map as data: what `LevelDesc::render` draws and marks, in level pixels.
+///
+/// Parsed from TOML; see `docs/levels.md` for the format.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LevelDesc {
+    pub name: String,
+    pub author: String,
+    pub tile_size: u32,
+    /// Grid size in tiles.
+    pub width: u32,
+    pub height: u32,
+    pub ground: Ground,
+    pub lanes: Vec<Lane>,
+    pub islands: Vec<Island>,
+    pub openings: Vec<Opening>,
+    pub bays: Vec<Bay>,
+    pub exits: Vec<Exit>,
+    pub spawns: Vec<Spawn>,
+    pub hazards: Vec<HazardZone>,
+    pub decorations: Vec<Decoration>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub w: i32,
+    pub h: i32,
+}
+
+/// Base fill and boundary wall.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Ground {
+    pub color: Rgba,
+    /// Second colour mixed in with the parking lot's light dither.
+    pub dither: Option<Rgba>,
+    /// Wall thickness in pixels; the whole outer ring of tiles it covers is blocked.
+    pub border: u32,
+    pub border_color: Rgba,
+}
+
+/// A walkable lane, numbered from 1 in declaration order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Lane {
+    pub center: i32,
+    pub width: i32,
+    pub top: i32,
+    pub bottom: i32,
+    pub color: Rgba,
+    pub edge: Option<Rgba>,
+    /// Centre line dashes.
+    pub dashes: Option<Rgba>,
+}
+
+/// A blocked area: median, building or wall.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Island {
+    pub rect: Rect,
+    pub fill: Rgba,
+    pub edge: Option<Rgba>,
+}
+
+/// A walkable gap carved through islands, drawn like the parking lot's crossovers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Opening {
+    pub rect: Rect,
+    pub color: Rgba,
+    pub edge: Option<Rgba>,
+}
+
+/// Parking bays: a surface with a stall line every `step` pixels. Purely visual.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Bay {
+    pub rect: Rect,
+    pub color: Rgba,
+    pub line: Rgba,
+    pub step: i32,
+}
+
+/// Where humans escape with the flag; walkable, and drawn over the boundary wall.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Exit {
+    pub rect: Rect,
+    pub color: Rgba,
+    pub edge: Option<Rgba>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpawnKind {
+    Alien,
+    Demon,
+    Human,
+    Flag,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Shape {
+    Circle { cx: i32, cy: i32, radius: i32 },
+    Rect(Rect),
+}
+
+/// A walkable area carrying a spawn or flag marker.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Spawn {
+    pub kind: SpawnKind,
+    pub shape: Shape,
+    pub color: Rgba,
+    /// Outline, 4 pixels wide on circles.
+    pub ring: Option<Rgba>,
+    /// Centre fill, a third of the size.
+    pub core: Option<Rgba>,
+}
+
+/// Whole tiles carrying a hazard in the semantic grid. Not drawn: hosts draw each hazard's state
+/// over the level image.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HazardZone {
+    pub kind: Hazard,
+    /// The state the hazard starts in.
+    pub state: HazardState,
+    pub rect: Rect,
+}
+
+/// Art without gameplay meaning, drawn last.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Decoration {
+    Rect {
+        rect: Rect,
+        color: Rgba,
+    },
+    Outline {
+        rect: Rect,
+        color: Rgba,
+    },
+    Circle {
+        cx: i32,
+        cy: i32,
+        radius: i32,
+        color: Rgba,
+    },
+    Ring {
+        cx: i32,
+        cy: i32,
+        outer: i32,
+        inner: i32,
+        color: Rgba,
+    },
+    Line {
+        from: (i32, i32),
+        to: (i32, i32),
+        color: Rgba,
+    },
+    Polygon {
+        points: Vec<(i32, i32)>,
+        color: Rgba,
+    },
+    Tree {
+        cx: i32,
+        cy: i32,
+        trunk: Rgba,
+        canopy: Rgba,
+        highlight: Rgba,
+    },
+}
+
+impl LevelDesc {
+    /// Parses and checks a TOML description. Errors name the offending entry, e.g.
+    /// ``lanes[2]: missing `width` ``.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let root = toml::parse(text)?;
+        let mut doc = Fields::new(&root, String::new());
+        let palette = doc.palette()?;
+        let desc = LevelDesc {
+            name: doc.string("name")?,
+            author: doc.string_or("author", "")?,
+            tile_size: doc.positive("tile_size")?,
+            width: doc.positive("width")?,
+            height: doc.positive("height")?,
+            ground: doc.ground(&palette)?,
+            lanes: doc.each("lanes", |f| {
+                Ok(Lane {
+                    center: f.int("center")?,
+                    width: f.int("width")?,
+                    top: f.int("top")?,
+                    bottom: f.int("bottom")?,
+                    color: f.color("color", &palette)?,
+                    edge: f.optional_color("edge", &palette)?,
+                    dashes: f.optional_color("dashes", &palette)?,
+                })
+            })?,
+            islands: doc.each("islands", |f| {
+                Ok(Island {
+                    rect: f.rect()?,
+                    fill: f.color("fill", &palette)?,
+                    edge: f.optional_color("edge", &palette)?,
+                })
+            })?,
+            openings: doc.each("openings", |f| {
+                Ok(Opening {
+                    rect: f.rect()?,
+                    color: f.color("color", &palette)?,
+                    edge: f.optional_color("edge", &palette)?,
+                })
+            })?,
+            bays: doc.each("bays", |f| {
+                Ok(Bay {
+                    rect: f.rect()?,
+                    color: f.color("color", &palette)?,
+                    line: f.color("line", &palette)?,
+                    step: f.positive("step")? as i32,
+                })
+            })?,
+            exits: doc.each("exits", |f| {
+                Ok(Exit {
+                    rect: f.rect()?,
+                    color: f.color("color", &palette)?,
+                    edge: f.optional_color("edge", &palette)?,
+                })
+            })?,
+            spawns: doc.each("spawns", |f| {
+                let kind = match f.string("kind")?.as_str() {
+                    "alien" => SpawnKind::Alien,
+                    "demon" => SpawnKind::Demon,
+                    "human" => SpawnKind::Human,
+                    "flag" => SpawnKind::Flag,
+                    other => {
+                        return Err(f.error(&format!(
+                            "unknown spawn kind `{other}` (alien, demon, human or flag)"
+                        )));
+                    }
+                };
+                let shape = if f.has("radius") {
+                    Shape::Circle {
+                        cx: f.int("x")?,
+                        cy: f.int("y")?,
+                        radius: f.positive("radius")? as i32,
+                    }
+                } else {
+                    Shape::Rect(f.rect()?)
+                };
+                Ok(Spawn {
+                    kind,
+                    shape,
+                    color: f.color("color", &palette)?,
+                    ring: f.optional_color("ring", &palette)?,
+                    core: f.optional_color("core", &palette)?,
+                })
+            })?,
+            hazards: doc.each("hazards", |f| {
+                let kind = match f.string("kind")?.as_str() {
+                    "lava" => Hazard::Lava,
+                    "turret" => Hazard::Turret,
+                    "collapse" => Hazard::Collapse,
+                    other => {
+                        return Err(f.error(&format!(
+                            "unknown hazard kind `{other}` (lava, turret or collapse)"
+                        )));
+                    }
+                };
+                let state = match f.string_or("state", "dormant")?.as_str() {
+                    "dormant" => HazardState::Dormant,
+                    "warning" => HazardState::Warning,
+                    "active" => HazardState::Active,
+                    "depleted" => HazardState::Depleted,
+                    other => {
+                        return Err(f.error(&format!(
+                            "unknown hazard state `{other}` (dormant, warning, active or depleted)"
+                        )));
+                    }
+                };
+                Ok(HazardZone {
+                    kind,
+                    state,
+                    rect: f.rect()?,
+                })
+            })?,
+            decorations: doc.each("decorations", |f| f.decoration(&palette))?,
+        };
+        doc.finish()?;
+        desc.check()?;
+        Ok(desc)
+    }
+
+    /// Level size in pixels.
+    pub fn pixel_size(&self) -> (u32, u32) {
+        (
+            self.width.saturating_mul(self.tile_size),
+            self.height.saturating_mul(self.tile_size),
+        )
+    }
+
+    /// The `bvb_level_hazards_*` records, in declaration order.
+    pub fn hazard_infos(&self) -> Vec<HazardInfo> {
+        self.hazards
+            .iter()
+            .map(|hazard| HazardInfo {
+                kind: hazard.kind,
+                state: hazard.state,
+                x: hazard.rect.x as u32,
+                y: hazard.rect.y as u32,
+                width: hazard.rect.w as u32,
+                height: hazard.rect.h as u32,
+            })
+            .collect()
+    }
+
+    fn check(&self) -> Result<(), String> {
+        let (width, height) = self.pixel_size();
+        if width > MAX_IMAGE_DIMENSION || height > MAX_IMAGE_DIMENSION {
+            return Err(format!(
+                "level is {width}x{height} pixels, at most {MAX_IMAGE_DIMENSION}x{MAX_IMAGE_DIMENSION} are allowed"
+            ));
+        }
+        if self.lanes.len() > usize::from(u8::MAX) {
+            return Err(format!(
+                "{} lanes, at most 255 are allowed",
+                self.lanes.len()
+            ));
+        }
+        for (i, lane) in self.lanes.iter().enumerate() {
+            if lane.width <= 0 || lane.bottom <= lane.top {
+                return Err(format!("lanes[{i}]: width and height must be positive"));
+            }
+        }
+        let required = [
+            (SpawnKind::Alien, "an alien spawn"),
+            (SpawnKind::Demon, "a demon spawn"),
+        ];
+        for (kind, what) in required {
+            if !self.spawns.iter().any(|spawn| spawn.kind == kind) {
+                return Err(format!("level needs {what}"));
+            }
+        }
+        if self.exits.is_empty() {
+            return Err("level needs an exit".to_string());
+        }
+        let tile = self.tile_size as i32;
+        for (i, hazard) in self.hazards.iter().enumerate() {
+            let Rect { x, y, w, h } = hazard.rect;
+            let aligned = [x, y, w, h].iter().all(|n| n % tile == 0);
+            let inside = x >= 0
+                && y >= 0
+                && i64::from(x) + i64::from(w) <= i64::from(width)
+                && i64::from(y) + i64::from(h) <= i64::from(height);
+            if !aligned || !inside {
+                return Err(format!(
+                    "hazards[{i}]: must cover whole {tile} px tiles inside the level"
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+// One TOML table being read into a description type. Tracks which keys were read so `finish` can
+// reject typos instead of silently ignoring them.
+struct Fields<'a> {
+    table: &'a Table,
+    context: String,
+    used: Vec<&'a str>,
+}
+
+impl<'a> Fields<'a> {
+    fn new(table: &'a Table, context: String) -> Self {
+        Self {
+            table,
+            context,
+            used: Vec::new(),
+        }
+    }
+
+    fn error(&self, message: &str) -> String {
+        if self.context.is_empty() {
+            message.to_string()
+        } else {
+            format!("{}: {message}", self.context)
+        }
+    }
+
+    fn has(&self, key: &str) -> bool {
+        self.table.iter().any(|(name, _)| name == key)
+    }
+
+    fn get(&mut self, key: &str) -> Option<&'a Value> {
+        let (name, value) = self.table.iter().find(|(name, _)| name == key)?;
+        self.used.push(name);
+        Some(value)
+    }
+
+    fn require(&mut self, key: &str) -> Result<&'a Value, String> {
+        self.get(key)
+            .ok_or_else(|| self.error(&format!("missing `{key}`")))
+    }
+
+    fn mismatch(&self, key: &str, wanted: &str, value: &Value) -> String {
+        self.error(&format!(
+            "`{key}` must be {wanted}, found a {}",
+            value.type_name()
+        ))
+    }
+
+    fn int(&mut self, key: &str) -> Result<i32, String> {
+        let value = self.require(key)?;
+        self.int_value(key, value)
+    }
+
+    fn int_value(&self, key: &str, value: &Value) -> Result<i32, String> {
+        match value {
+            Value::Integer(n) => {
+                i32::try_from(*n).map_err(|_| self.error(&format!("`{key}` is out of range")))
+            }
+            other => Err(self.mismatch(key, "an integer", other)),
+        }
+    }
+
+    fn positive(&mut self, key: &str) -> Result<u32, String> {
+        match self.int(key)? {
+            n if n > 0 => Ok(n as u32),
+            _ => Err(self.error(&format!("`{key}` must be positive"))),
+        }
+    }
+
+    fn string(&mut self, key: &str) -> Result<String, String> {
+        match self.require(key)? {
+            Value::String(s) => Ok(s.clone()),
+            other => Err(self.mismatch(key, "a string", other)),
+        }
+    }
+
+    fn string_or(&mut self, key: &str, default: &str) -> Result<String, String> {
+        if self.has(key) {
+            self.string(key)
+        } else {
+            Ok(default.to_string())
+        }
+    }
+
+    fn rect(&mut self) -> Result<Rect, String> {
+        let rect = Rect {
+            x: self.int("x")?,
+            y: self.int("y")?,
+            w: self.positive("w")? as i32,
+            h: self.positive("h")? as i32,
+        };
+        Ok(rect)
+    }
+
+    fn point(&self, key: &str, value: &Value) -> Result<(i32, i32), String> {
+        match value {
+            Value::Array(items) if items.len() == 2 => Ok((
+                self.int_value(key, &items[0])?,
+                self.int_value(key, &items[1])?,
+            )),
+            other => Err(self.mismatch(key, "an `[x, y]` pair", other)),
+        }
+    }
+
+    fn color(&mut self, key: &str, palette: &[(String, Rgba)]) -> Result<Rgba, String> {
+        let value = self.require(key)?;
+        self.color_value(key, value, palette)
+    }
+
+    fn optional_color(
+        &mut self,
+        key: &str,
+        palette: &[(String, Rgba)],
+    ) -> Result<Option<Rgba>, String> {
+        if self.has(key) {
+            self.color(key, palette).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    // A palette name, `#rrggbb`, `#rrggbbaa` or `[r, g, b]` / `[r, g, b, a]`.
+    fn color_value(
+        &self,
+        key: &str,
+        value: &Value,
+        palette: &[(String, Rgba)],
+    ) -> Result<Rgba, String> {
+        match value {
+            Value::String(s) if s.starts_with('#') => parse_hex(&s[1..])
+                .ok_or_else(|| self.error(&format!("`{key}`: `{s}` is not a #rrggbb colour"))),
+            Value::String(name) => palette
+                .iter()
+                .find(|(entry, _)| entry == name)
+                .map(|&(_, color)| color)
+                .ok_or_else(|| self.error(&format!("`{key}`: no palette colour `{name}`"))),
+            Value::Array(items) if matches!(items.len(), 3 | 4) => {
+                let mut color = [0, 0, 0, 255];
+                for (channel, item) in color.iter_mut().zip(items) {
+                    *channel = u8::try_from(self.int_value(key, item)?)
+                        .map_err(|_| self.error(&format!("`{key}`: channels must be 0 to 255")))?;
+                }
+                Ok(color)
+            }
+            other => Err(self.mismatch(key, "a colour", other)),
+        }
+    }
+
+    fn palette(&mut self) -> Result<Vec<(String, Rgba)>, String> {
+        let Some(value) = self.get("palette") else {
+            return Ok(Vec::new());
+        };
+        let Value::Table(table) = value else {
+            return Err(self.mismatch("palette", "a table", value));
+        };
+        let fields = Fields::new(table, "palette".to_string());
+        let mut palette = Vec::new();
+        for (name, value) in table {
+            // Entries may refer to earlier ones.
+            let color = fields.color_value(name, value, &palette)?;
+            palette.push((name.clone(), color));
+        }
+        Ok(palette)
+    }
+
+    fn ground(&mut self, palette: &[(String, Rgba)]) -> Result<Ground, String> {
+        let value = self.require("ground")?;
+        let Value::Table(table) = value else {
+            return Err(self.mismatch("ground", "a table", value));
+        };
+        let mut f = Fields::new(table, "ground".to_string());
+        let border = if f.has("border") {
+            f.int("border")?.max(0) as u32
+        } else {
+            0
+        };
+        let ground = Ground {
+            color: f.color("color", palette)?,
+            dither: f.optional_color("dither", palette)?,
+            border,
+            border_color: if border > 0 {
+                f.color("border_color", palette)?
+            } else {
+                [0; 4]
+            },
+        };
+        f.finish()?;
+        Ok(ground)
+    }
+
+    // Reads every table of the `[[key]]` array with `read`; a missing array is empty.
+    fn each<T>(
+        &mut self,
+        key: &str,
+        mut read: impl FnMut(&mut Fields<'a>) -> Result<T, String>,
+    ) -> Result<Vec<T>, String> {
+        let Some(value) = self.get(key) else {
+            return Ok(Vec::new());
+        };
+        let Value::Array(items) = value else {
+            return Err(self.mismatch(key, "an array of tables", value));
+        };
+        let mut out = Vec::with_capacity(items.len());
+        for (i, item) in items.iter().enumerate() {
+            let Value::Table(table) = item else {
+                return Err(self.mismatch(&format!("{key}[{i}]"), "a table", item));
+            };
+            let mut fields = Fields::new(table, format!("{key}[{i}]"));
+            out.push(read(&mut fields)?);
+            fields.finish()?;
+        }
+        Ok(out)
+    }
+
+    fn decoration(&mut self, palette: &[(String, Rgba)]) -> Result<Decoration, String> {
+        let kind = self.string("kind")?;
+        Ok(match kind.as_str() {
+            "rect" => Decoration::Rect {
+                rect: self.rect()?,
+                color: self.color("color", palette)?,
+            },
+            "outline" => Decoration::Outline {
+                rect: self.rect()?,
+                color: self.color("color", palette)?,
+            },
+            "circle" => Decoration::Circle {
+                cx: self.int("x")?,
+                cy: self.int("y")?,
+                radius: self.positive("radius")? as i32,
+                color: self.color("color", palette)?,
+            },
+            "ring" => Decoration::Ring {
+                cx: self.int("x")?,
+                cy: self.int("y")?,
+                outer: self.positive("outer")? as i32,
+                inner: self.int("inner")?,
+                color: self.color("color", palette)?,
+            },
+            "line" => {
+                let from = self.require("from")?;
+                let to = self.require("to")?;
+                Decoration::Line {
+                    from: self.point("from", from)?,
+                    to: self.point("to", to)?,
+                    color: self.color("color", palette)?,
+                }
+            }
+            "polygon" => {
+                let value = self.require("points")?;
+                let Value::Array(items) = value else {
+                    return Err(self.mismatch("points", "an array of points", value));
+                };
+                if items.len() < 3 {
+                    return Err(self.error("a polygon needs at least 3 points"));
+                }
+                Decoration::Polygon {
+                    points: items
+                        .iter()
+                        .map(|item| self.point("points", item))
+                        .collect::<Result<_, _>>()?,
+                    color: self.color("color", palette)?,
+                }
+            }
+            "tree" => Decoration::Tree {
+                cx: self.int("x")?,
+                cy: self.int("y")?,
+                trunk: self.color("trunk", palette)?,
+                canopy: self.color("canopy", palette)?,
+                highlight: self.color("highlight", palette)?,
+            },
+            other => {
+                return Err(self.error(&format!(
+                    "unknown decoration `{other}` (rect, outline, circle, ring, line, polygon or tree)"
+                )));
+            }
+        })
+    }
+
+    fn finish(self) -> Result<(), String> {
+        match self
+            .table
+            .iter()
+            .find(|(name, _)| !self.used.contains(&name.as_str()))
+        {
+            Some((name, _)) => Err(self.error(&format!("unknown key `{name}`"))),
+            None => Ok(()),
+        }
+    }
+}
+
+fn parse_hex(hex: &str) -> Option<Rgba> {
+    if !matches!(hex.len(), 6 | 8) || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    let mut color = [0, 0, 0, 255];
+    for (channel, i) in color.iter_mut().zip((0..hex.len()).step_by(2)) {
+        *channel = u8::from_str_radix(&hex[i..i + 2], 16).ok()?;
+    }
+    Some(color)
+}
@@ -0,0 +1,134 @@
+// Builds level modules from TOML descriptions instead of drawing code.
+//
+// A level crate holds a description file and one `level_module!` call, which generates the full ABI
+// from `docs/levels.md`. Descriptions are drawn with the same `bvb_raster` primitives and marked on
+// the same semantic grid as the hand-written parking lot.
+
+mod desc;
+mod module;
+mod render;
+pub mod toml;
+
+pub use bvb_level_kit::ABI_VERSION;
+pub use desc::{
+    Bay, Decoration, Exit, Ground, HazardZone, Island, Lane, LevelDesc, MAX_IMAGE_DIMENSION,
+    Opening, Rect, Shape, Spawn, SpawnKind,
+};
+pub use module::DescribedLevel;
+
+/// Exports a level module for the description at `$path`, relative to the calling file.
+///
+/// Also defines `pub const DESCRIPTION: &str` with the description source, for tests and tools.
+#[macro_export]
+macro_rules! level_module {
+    ($path:literal) => {
+        pub const DESCRIPTION: &str = include_str!($path);
+
+        static LEVEL: $crate::DescribedLevel = $crate::DescribedLevel::new(DESCRIPTION);
+
+        #[unsafe(no_mangle)]
+        pub extern "C" fn bvb_level_abi_version() -> u32 {
+            $crate::ABI_VERSION
+        }
+
+        #[unsafe(no_mangle)]
+        pub extern "C" fn bvb_level_init() {
+            LEVEL.init();
+        }
+
+        #[unsafe(no_mangle)]
+        pub extern "C" fn bvb_level_render(scale: u32) -> u32 {
+            LEVEL.render(scale)
+        }
+
+        #[unsafe(no_mangle)]
+        pub extern "C" fn bvb_level_release() {
+            LEVEL.release();
+        }
+
+        #[unsafe(no_mangle)]
+        pub extern "C" fn bvb_level_scale() -> u32 {
+            LEVEL.scale()
+        }
+
+        #[unsafe(no_mangle)]
+        pub extern "C" fn bvb_level_width() -> u32 {
+            LEVEL.image_size().0
+        }
+
+        #[unsafe(no_mangle)]
+        pub extern "C" fn bvb_level_height() -> u32 {
+            LEVEL.image_size().1
+        }
+
+        #[unsafe(no_mangle)]
+        pub extern "C" fn bvb_level_pixels_ptr() -> *const u8 {
+            LEVEL.pixels().0
+        }
+
+        #[unsafe(no_mangle)]
+        pub extern "C" fn bvb_level_pixels_len() -> u32 {
+            LEVEL.pixels().1
+        }
+
+        #[unsafe(no_mangle)]
+        pub extern "C" fn bvb_level_name_ptr() -> *const u8 {
+            LEVEL.name().0
+        }
+
+        #[unsafe(no_mangle)]
+        pub extern "C" fn bvb_level_name_len() -> u32 {
+            LEVEL.name().1
+        }
+
+        #[unsafe(no_mangle)]
+        pub extern "C" fn bvb_level_author_ptr() -> *const u8 {
+            LEVEL.author().0
+        }
+
+        #[unsafe(no_mangle)]
+        pub extern "C" fn bvb_level_author_len() -> u32 {
+            LEVEL.author().1
+        }
+
+        #[unsafe(no_mangle)]
+        pub extern "C" fn bvb_level_tile_size() -> u32 {
+            LEVEL.grid().0
+        }
+
+        #[unsafe(no_mangle)]
+        pub extern "C" fn bvb_level_grid_width() -> u32 {
+            LEVEL.grid().1
+        }
+
+        #[unsafe(no_mangle)]
+        pub extern "C" fn bvb_level_grid_height() -> u32 {
+            LEVEL.grid().2
+        }
+
+        #[unsafe(no_mangle)]
+        pub extern "C" fn bvb_level_content_hash() -> u64 {
+            LEVEL.content_hash()
+        }
+
+        #[unsafe(no_mangle)]
+        pub extern "C" fn bvb_level_semantics_ptr() -> *const u8 {
+            LEVEL.semantics().0
+        }
+
+        #[unsafe(no_mangle)]
+        pub extern "C" fn bvb_level_semantics_len() -> u32 {
+            LEVEL.semantics().1
+        }
+
+        #[unsafe(no_mangle)]
+        pub extern "C" fn bvb_level_hazards_ptr() -> *const u8 {
+            LEVEL.hazards().0
+        }
+
+        #[unsafe(no_mangle)]
+        pub extern "C" fn bvb_level_hazards_len() -> u32 {
+            LEVEL.hazards().1
+        }
+    };
+}
@@ -0,0 +1,145 @@
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use bvb_level_kit::{content_hash, hazards_to_bytes};
+
+use crate::desc::{LevelDesc, MAX_IMAGE_DIMENSION};
+
+// Same ceiling as the parking lot; larger levels get a lower one from `MAX_IMAGE_DIMENSION`.
+const MAX_SCALE: u32 = 4;
+
+/// State behind the exports `level_module!` generates: the description source and the level
+/// rendered from it. Pointers it hands out stay valid until the next `render` or `release`.
+pub struct DescribedLevel {
+    source: &'static str,
+    level: Mutex<Option<Built>>,
+}
+
+struct Built {
+    desc: LevelDesc,
+    scale: u32,
+    pixels: Box<[u8]>,
+    semantics: Box<[u8]>,
+    hazards: Box<[u8]>,
+    content_hash: u64,
+}
+
+impl Built {
+    fn new(desc: LevelDesc, scale: u32) -> Self {
+        let (pixels, grid) = desc.render(scale);
+        let (width, height) = desc.pixel_size();
+        Self {
+            content_hash: content_hash(width * scale, height * scale, &pixels),
+            pixels: pixels.into_boxed_slice(),
+            semantics: grid.to_bytes().into_boxed_slice(),
+            hazards: hazards_to_bytes(&desc.hazard_infos()).into_boxed_slice(),
+            desc,
+            scale,
+        }
+    }
+}
+
+impl DescribedLevel {
+    pub const fn new(source: &'static str) -> Self {
+        Self {
+            source,
+            level: Mutex::new(None),
+        }
+    }
+
+    /// Parses and renders the description at 1x. Idempotent while a level is loaded.
+    ///
+    /// # Panics
+    ///
+    /// If the description is invalid, which traps the module. The builder's tests parse every
+    /// shipped description, so this cannot happen in a shipped module.
+    pub fn init(&self) {
+        let mut level = self.current();
+        if level.is_none() {
+            let desc = LevelDesc::parse(self.source)
+                .unwrap_or_else(|err| panic!("invalid level description: {err}"));
+            *level = Some(Built::new(desc, 1));
+        }
+    }
+
+    /// Re-renders at `scale`. Returns 0 on success and 1 for a scale outside 1 to 4 or one that
+    /// would exceed `MAX_IMAGE_DIMENSION`.
+    pub fn render(&self, scale: u32) -> u32 {
+        self.init();
+        let mut level = self.current();
+        let Some(built) = level.take() else {
+            return 1;
+        };
+        let (width, height) = built.desc.pixel_size();
+        if !(1..=MAX_SCALE).contains(&scale) || width.max(height) * scale > MAX_IMAGE_DIMENSION {
+            *level = Some(built);
+            return 1;
+        }
+        *level = Some(Built::new(built.desc, scale));
+        0
+    }
+
+    pub fn release(&self) {
+        *self.current() = None;
+    }
+
+    pub fn scale(&self) -> u32 {
+        self.current().as_ref().map_or(1, |built| built.scale)
+    }
+
+    /// Image size in pixels, 0 x 0 until init.
+    pub fn image_size(&self) -> (u32, u32) {
+        self.current().as_ref().map_or((0, 0), |built| {
+            let (width, height) = built.desc.pixel_size();
+            (width * built.scale, height * built.scale)
+        })
+    }
+
+    /// Tile edge and grid size in tiles, zero until init.
+    pub fn grid(&self) -> (u32, u32, u32) {
+        self.current().as_ref().map_or((0, 0, 0), |built| {
+            (built.desc.tile_size, built.desc.width, built.desc.height)
+        })
+    }
+
+    /// Hash of the image as last rendered; 0 before init or after release.
+    pub fn content_hash(&self) -> u64 {
+        self.current()
+            .as_ref()
+            .map_or(0, |built| built.content_hash)
+    }
+
+    pub fn pixels(&self) -> (*const u8, u32) {
+        self.buffer(|built| &built.pixels)
+    }
+
+    pub fn semantics(&self) -> (*const u8, u32) {
+        self.buffer(|built| &built.semantics)
+    }
+
+    /// The hazard table; empty when the description has no hazards.
+    pub fn hazards(&self) -> (*const u8, u32) {
+        self.buffer(|built| &built.hazards)
+    }
+
+    pub fn name(&self) -> (*const u8, u32) {
+        self.buffer(|built| built.desc.name.as_bytes())
+    }
+
+    pub fn author(&self) -> (*const u8, u32) {
+        self.buffer(|built| built.desc.author.as_bytes())
+    }
+
+    // A panic while holding the lock cannot leave a half-written level, so poisoning is ignored.
+    fn current(&self) -> MutexGuard<'_, Option<Built>> {
+        self.level.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn buffer(&self, bytes: impl FnOnce(&Built) -> &[u8]) -> (*const u8, u32) {
+        self.current()
+            .as_ref()
+            .map_or((core::ptr::null(), 0), |built| {
+                let bytes = bytes(built);
+                (bytes.as_ptr(), bytes.len() as u32)
+            })
+    }
+}
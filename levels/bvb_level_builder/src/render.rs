@@ -0,0 +1,202 @@
+use bvb_level_kit::{Marker, SemanticGrid, SemanticTile, Terrain};
+use bvb_raster::{Canvas, Rgba};
+
+use crate::desc::{Decoration, LevelDesc, Rect, Shape, SpawnKind};
+
+// Matches the parking lot's lane markings.
+const DASH_LENGTH: i32 = 8;
+const DASH_SPACING: i32 = 20;
+const SPAWN_RING_WIDTH: i32 = 4;
+
+impl LevelDesc {
+    /// Draws the level at `scale` image pixels per level pixel and marks its semantic grid from the
+    /// same shapes, in level pixels whatever the scale.
+    ///
+    /// Layers go down in a fixed order, so later ones win where they overlap: ground, border, bays,
+    /// lanes, islands, openings, exits, spawns, then decorations, which never touch the grid.
+    /// Hazards only mark the grid.
+    pub fn render(&self, scale: u32) -> (Vec<u8>, SemanticGrid) {
+        let (width, height) = self.pixel_size();
+        let (width, height) = (width as i32, height as i32);
+        let mut canvas = Canvas::with_scale(width as u32, height as u32, scale);
+        let mut grid = SemanticGrid::new(
+            self.width,
+            self.height,
+            self.tile_size,
+            SemanticTile::WALKABLE,
+        );
+        let block = |tile: &mut SemanticTile| tile.terrain = Terrain::Blocked;
+        let open = |tile: &mut SemanticTile| tile.terrain = Terrain::Walkable;
+
+        let ground = &self.ground;
+        for y in 0..height {
+            for x in 0..width {
+                let c = match ground.dither {
+                    Some(dither) if (x * 3 + y * 5) & 7 >= 4 => dither,
+                    _ => ground.color,
+                };
+                canvas.put(x, y, c);
+            }
+        }
+
+        if ground.border > 0 {
+            let border = ground.border as i32;
+            canvas.fill_rect(0, 0, width, border, ground.border_color);
+            canvas.fill_rect(0, height - border, width, border, ground.border_color);
+            canvas.fill_rect(0, 0, border, height, ground.border_color);
+            canvas.fill_rect(width - border, 0, border, height, ground.border_color);
+            // Every tile the wall reaches into is blocked, as with the parking lot's curb.
+            let ring = ground.border.div_ceil(self.tile_size) as i32 * self.tile_size as i32;
+            grid.mark_rect(0, 0, width, ring, block);
+            grid.mark_rect(0, height - ring, width, ring, block);
+            grid.mark_rect(0, 0, ring, height, block);
+            grid.mark_rect(width - ring, 0, ring, height, block);
+        }
+
+        for bay in &self.bays {
+            let Rect { x, y, w, h } = bay.rect;
+            canvas.fill_rect(x, y, w, h, bay.color);
+            let mut line_y = y + bay.step;
+            while line_y < y + h {
+                canvas.fill_rect(x + 2, line_y, w - 4, 1, bay.line);
+                line_y += bay.step;
+            }
+        }
+
+        for (number, lane) in (1u8..).zip(&self.lanes) {
+            let x0 = lane.center - lane.width / 2;
+            let h = lane.bottom - lane.top;
+            canvas.fill_rect(x0, lane.top, lane.width, h, lane.color);
+            if let Some(edge) = lane.edge {
+                canvas.rect_outline(x0, lane.top, lane.width, h, edge);
+            }
+            if let Some(dashes) = lane.dashes {
+                let mut dash_y = lane.top + 12;
+                while dash_y < lane.bottom - 12 {
+                    canvas.fill_rect(lane.center - 1, dash_y, 2, DASH_LENGTH, dashes);
+                    dash_y += DASH_SPACING;
+                }
+            }
+            grid.mark_rect(x0, lane.top, lane.width, h, |tile| {
+                tile.terrain = Terrain::Walkable;
+                tile.lane = number;
+            });
+        }
+
+        for island in &self.islands {
+            let Rect { x, y, w, h } = island.rect;
+            canvas.fill_rect(x, y, w, h, island.fill);
+            if let Some(edge) = island.edge {
+                canvas.rect_outline(x, y, w, h, edge);
+            }
+            grid.mark_rect(x, y, w, h, block);
+        }
+
+        for opening in &self.openings {
+            let Rect { x, y, w, h } = opening.rect;
+            canvas.fill_rect(x, y, w, h, opening.color);
+            if let Some(edge) = opening.edge {
+                canvas.rect_outline(x, y, w, h, edge);
+                canvas.fill_rect(x + 2, y + h / 2 - 1, w - 4, 2, edge);
+            }
+            grid.mark_rect(x, y, w, h, open);
+        }
+
+        for exit in &self.exits {
+            let Rect { x, y, w, h } = exit.rect;
+            canvas.fill_rect(x, y, w, h, exit.color);
+            if let Some(edge) = exit.edge {
+                canvas.rect_outline(x, y, w, h, edge);
+            }
+            grid.mark_rect(x, y, w, h, |tile| {
+                tile.terrain = Terrain::Walkable;
+                tile.marker = Marker::HumanExit;
+            });
+        }
+
+        for spawn in &self.spawns {
+            let marker = match spawn.kind {
+                SpawnKind::Alien => Marker::AlienSpawnBase,
+                SpawnKind::Demon => Marker::DemonSpawnBase,
+                SpawnKind::Human => Marker::HumanSpawn,
+                SpawnKind::Flag => Marker::FlagBase,
+            };
+            let mark = |tile: &mut SemanticTile| {
+                tile.terrain = Terrain::Walkable;
+                tile.marker = marker;
+            };
+            match spawn.shape {
+                Shape::Circle { cx, cy, radius } => {
+                    canvas.fill_circle(cx, cy, radius, spawn.color);
+                    if let Some(ring) = spawn.ring {
+                        canvas.fill_ring(cx, cy, radius, radius - SPAWN_RING_WIDTH, ring);
+                    }
+                    if let Some(core) = spawn.core {
+                        canvas.fill_circle(cx, cy, radius / 3, core);
+                    }
+                    grid.mark_circle(cx, cy, radius, mark);
+                }
+                Shape::Rect(Rect { x, y, w, h }) => {
+                    canvas.fill_rect(x, y, w, h, spawn.color);
+                    if let Some(ring) = spawn.ring {
+                        canvas.rect_outline(x, y, w, h, ring);
+                    }
+                    if let Some(core) = spawn.core {
+                        canvas.fill_rect(x + w / 3, y + h / 3, w / 3, h / 3, core);
+                    }
+                    grid.mark_rect(x, y, w, h, mark);
+                }
+            }
+        }
+
+        for hazard in &self.hazards {
+            let Rect { x, y, w, h } = hazard.rect;
+            grid.mark_rect(x, y, w, h, |tile| tile.hazard = hazard.kind);
+        }
+
+        for decoration in &self.decorations {
+            draw_decoration(&mut canvas, decoration);
+        }
+
+        (canvas.pixels, grid)
+    }
+}
+
+fn draw_decoration(canvas: &mut Canvas, decoration: &Decoration) {
+    match *decoration {
+        Decoration::Rect { rect, color } => canvas.fill_rect(rect.x, rect.y, rect.w, rect.h, color),
+        Decoration::Outline { rect, color } => {
+            canvas.rect_outline(rect.x, rect.y, rect.w, rect.h, color)
+        }
+        Decoration::Circle {
+            cx,
+            cy,
+            radius,
+            color,
+        } => canvas.fill_circle(cx, cy, radius, color),
+        Decoration::Ring {
+            cx,
+            cy,
+            outer,
+            inner,
+            color,
+        } => canvas.fill_ring(cx, cy, outer, inner, color),
+        Decoration::Line { from, to, color } => canvas.line(from.0, from.1, to.0, to.1, color),
+        Decoration::Polygon { ref points, color } => canvas.fill_polygon(points, color),
+        Decoration::Tree {
+            cx,
+            cy,
+            trunk,
+            canopy,
+            highlight,
+        } => draw_tree(canvas, cx, cy, trunk, canopy, highlight),
+    }
+}
+
+// Same shape as the parking lot's median trees.
+fn draw_tree(canvas: &mut Canvas, cx: i32, cy: i32, trunk: Rgba, canopy: Rgba, highlight: Rgba) {
+    canvas.fill_rect(cx - 1, cy + 2, 2, 3, trunk);
+    canvas.fill_circle(cx, cy, 4, canopy);
+    canvas.fill_circle(cx - 2, cy + 1, 2, highlight);
+    canvas.fill_circle(cx + 2, cy + 1, 2, highlight);
+}
@@ -0,0 +1,338 @@
+// The subset of TOML level descriptions use: `key = value` pairs, `[table]` and `[[array]]` headers
+// with bare names, and integer, string, boolean, array and inline-table values. Floats, dates, dotted
+// keys and multi-line strings are rejected with an error rather than misread.
+
+use std::fmt::Write as _;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Value {
+    Integer(i64),
+    String(String),
+    Boolean(bool),
+    Array(Vec<Value>),
+    Table(Table),
+}
+
+impl Value {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Integer(_) => "integer",
+            Value::String(_) => "string",
+            Value::Boolean(_) => "boolean",
+            Value::Array(_) => "array",
+            Value::Table(_) => "table",
+        }
+    }
+}
+
+/// Key-value pairs in document order.
+pub type Table = Vec<(String, Value)>;
+
+pub fn parse(text: &str) -> Result<Table, String> {
+    Parser {
+        bytes: text.as_bytes(),
+        pos: 0,
+        line: 1,
+    }
+    .document()
+}
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    line: usize,
+}
+
+// Where `key = value` lines go: the root, a `[table]`, or the last table of an `[[array]]`.
+enum Section {
+    Root,
+    Table(String),
+    ArrayItem(String),
+}
+
+impl Parser<'_> {
+    fn document(mut self) -> Result<Table, String> {
+        let mut root = Table::new();
+        let mut section = Section::Root;
+        loop {
+            self.skip_blank_lines();
+            let Some(byte) = self.peek() else {
+                return Ok(root);
+            };
+            if byte == b'[' {
+                section = self.header(&mut root)?;
+            } else {
+                let key = self.key()?;
+                self.skip_spaces();
+                self.expect(b'=')?;
+                self.skip_spaces();
+                let value = self.value()?;
+                self.end_of_line()?;
+                let table = match &section {
+                    Section::Root => &mut root,
+                    Section::Table(name) => match lookup(&mut root, name) {
+                        Some(Value::Table(table)) => table,
+                        _ => unreachable!("section tables are created by their header"),
+                    },
+                    Section::ArrayItem(name) => match lookup(&mut root, name) {
+                        Some(Value::Array(items)) => match items.last_mut() {
+                            Some(Value::Table(table)) => table,
+                            _ => unreachable!("array headers push a table"),
+                        },
+                        _ => unreachable!("section arrays are created by their header"),
+                    },
+                };
+                self.insert(table, key, value)?;
+            }
+        }
+    }
+
+    fn header(&mut self, root: &mut Table) -> Result<Section, String> {
+        self.expect(b'[')?;
+        let array = self.peek() == Some(b'[');
+        if array {
+            self.pos += 1;
+        }
+        self.skip_spaces();
+        let name = self.key()?;
+        self.skip_spaces();
+        if self.peek() == Some(b'.') {
+            return Err(self.error("nested table names are not supported"));
+        }
+        self.expect(b']')?;
+        if array {
+            self.expect(b']')?;
+        }
+        self.end_of_line()?;
+
+        let item = || Value::Table(Table::new());
+        match lookup(root, &name) {
+            None if array => root.push((name.clone(), Value::Array(vec![item()]))),
+            None => root.push((name.clone(), item())),
+            Some(Value::Array(items))
+                if array && items.iter().all(|v| matches!(v, Value::Table(_))) =>
+            {
+                items.push(item())
+            }
+            Some(_) => return Err(self.error(&format!("`{name}` is defined twice"))),
+        }
+        Ok(if array {
+            Section::ArrayItem(name)
+        } else {
+            Section::Table(name)
+        })
+    }
+
+    fn insert(&self, table: &mut Table, key: String, value: Value) -> Result<(), String> {
+        if table.iter().any(|(existing, _)| *existing == key) {
+            return Err(self.error(&format!("`{key}` is defined twice")));
+        }
+        table.push((key, value));
+        Ok(())
+    }
+
+    fn key(&mut self) -> Result<String, String> {
+        if self.peek() == Some(b'"') {
+            return self.string();
+        }
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-')
+        {
+            self.pos += 1;
+        }
+        if start == self.pos {
+            return Err(self.unexpected("a key"));
+        }
+        Ok(String::from_utf8_lossy(&self.bytes[start..self.pos]).into_owned())
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        match self.peek() {
+            Some(b'"') => self.string().map(Value::String),
+            Some(b'[') => self.array(),
+            Some(b'{') => self.inline_table(),
+            Some(b't' | b'f') => {
+                let word = self.word();
+                match word.as_str() {
+                    "true" => Ok(Value::Boolean(true)),
+                    "false" => Ok(Value::Boolean(false)),
+                    _ => Err(self.error(&format!("unknown value `{word}`"))),
+                }
+            }
+            Some(b'+' | b'-' | b'0'..=b'9') => self.integer(),
+            _ => Err(self.unexpected("a value")),
+        }
+    }
+
+    fn integer(&mut self) -> Result<Value, String> {
+        let word = self.word();
+        let digits: String = word.chars().filter(|&c| c != '_').collect();
+        if digits.contains(['.', 'e', 'E']) {
+            return Err(self.error(&format!("`{word}`: only integers are supported")));
+        }
+        digits
+            .parse()
+            .map(Value::Integer)
+            .map_err(|_| self.error(&format!("`{word}` is not an integer")))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect(b'"')?;
+        let mut out = Vec::new();
+        loop {
+            match self.next() {
+                None | Some(b'\n') => return Err(self.error("unterminated string")),
+                Some(b'"') => break,
+                Some(b'\\') => match self.next() {
+                    Some(b'"') => out.push(b'"'),
+                    Some(b'\\') => out.push(b'\\'),
+                    Some(b'n') => out.push(b'\n'),
+                    Some(b't') => out.push(b'\t'),
+                    _ => return Err(self.error("unsupported escape in string")),
+                },
+                Some(byte) => out.push(byte),
+            }
+        }
+        String::from_utf8(out).map_err(|_| self.error("string is not valid UTF-8"))
+    }
+
+    fn array(&mut self) -> Result<Value, String> {
+        self.expect(b'[')?;
+        let mut items = Vec::new();
+        loop {
+            self.skip_blank_lines();
+            if self.peek() == Some(b']') {
+                self.pos += 1;
+                return Ok(Value::Array(items));
+            }
+            items.push(self.value()?);
+            self.skip_blank_lines();
+            match self.next() {
+                Some(b',') => {}
+                Some(b']') => return Ok(Value::Array(items)),
+                _ => return Err(self.error("expected `,` or `]` in array")),
+            }
+        }
+    }
+
+    fn inline_table(&mut self) -> Result<Value, String> {
+        self.expect(b'{')?;
+        let mut table = Table::new();
+        self.skip_spaces();
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(Value::Table(table));
+        }
+        loop {
+            self.skip_spaces();
+            let key = self.key()?;
+            self.skip_spaces();
+            self.expect(b'=')?;
+            self.skip_spaces();
+            let value = self.value()?;
+            self.insert(&mut table, key, value)?;
+            self.skip_spaces();
+            match self.next() {
+                Some(b',') => {}
+                Some(b'}') => return Ok(Value::Table(table)),
+                _ => return Err(self.error("expected `,` or `}` in inline table")),
+            }
+        }
+    }
+
+    fn word(&mut self) -> String {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|b| b.is_ascii_alphanumeric() || matches!(b, b'_' | b'+' | b'-' | b'.'))
+        {
+            self.pos += 1;
+        }
+        String::from_utf8_lossy(&self.bytes[start..self.pos]).into_owned()
+    }
+
+    fn skip_spaces(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t')) {
+            self.pos += 1;
+        }
+    }
+
+    // Skips whitespace, newlines and comments.
+    fn skip_blank_lines(&mut self) {
+        loop {
+            match self.peek() {
+                Some(b' ' | b'\t' | b'\r') => self.pos += 1,
+                Some(b'\n') => {
+                    self.pos += 1;
+                    self.line += 1;
+                }
+                Some(b'#') => self.skip_comment(),
+                _ => return,
+            }
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        while self.peek().is_some_and(|b| b != b'\n') {
+            self.pos += 1;
+        }
+    }
+
+    fn end_of_line(&mut self) -> Result<(), String> {
+        self.skip_spaces();
+        if self.peek() == Some(b'#') {
+            self.skip_comment();
+        }
+        if self.peek() == Some(b'\r') {
+            self.pos += 1;
+        }
+        match self.peek() {
+            None | Some(b'\n') => Ok(()),
+            _ => Err(self.unexpected("the end of the line")),
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), String> {
+        if self.peek() == Some(byte) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("`{}`", byte as char)))
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<u8> {
+        let byte = self.peek()?;
+        self.pos += 1;
+        Some(byte)
+    }
+
+    fn unexpected(&self, wanted: &str) -> String {
+        let mut message = format!("expected {wanted}, found ");
+        match self.peek() {
+            None => message.push_str("the end of the file"),
+            Some(b'\n') => message.push_str("the end of the line"),
+            Some(byte) => {
+                let _ = write!(message, "`{}`", byte as char);
+            }
+        }
+        self.error(&message)
+    }
+
+    fn error(&self, message: &str) -> String {
+        format!("line {}: {message}", self.line)
+    }
+}
+
+fn lookup<'t>(table: &'t mut Table, key: &str) -> Option<&'t mut Value> {
+    table
+        .iter_mut()
+        .find(|(name, _)| name == key)
+        .map(|(_, value)| value)
+}
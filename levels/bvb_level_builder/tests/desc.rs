@@ -0,0 +1,194 @@
+use bvb_level_builder::{LevelDesc, Shape, SpawnKind};
+use bvb_level_kit::{Hazard, HazardInfo, HazardState, Marker, Terrain};
+
+// 8 x 8 tiles of 8 px: one lane down the middle, an island beside it cut by an opening.
+const MINIMAL: &str = r##"
+name = "Test"
+tile_size = 8
+width = 8
+height = 8
+
+[palette]
+floor = "#101010"
+
+[ground]
+color = "floor"
+border = 4
+border_color = [200, 200, 200]
+
+[[lanes]]
+center = 32
+width = 16
+top = 8
+bottom = 56
+color = "#202020"
+
+[[islands]]
+x = 40
+y = 8
+w = 16
+h = 48
+fill = "#00ff0080"
+
+[[openings]]
+x = 40
+y = 24
+w = 16
+h = 8
+color = "floor"
+
+[[exits]]
+x = 0
+y = 8
+w = 8
+h = 8
+color = "floor"
+
+[[spawns]]
+kind = "alien"
+x = 32
+y = 12
+radius = 4
+color = "floor"
+
+[[spawns]]
+kind = "demon"
+x = 24
+y = 48
+w = 16
+h = 8
+color = "floor"
+"##;
+
+// Lava across the lane, two tiles wide.
+const LAVA: &str = r#"
+[[hazards]]
+kind = "lava"
+state = "warning"
+x = 24
+y = 32
+w = 16
+h = 8
+"#;
+
+#[test]
+fn parses_palette_and_spawn_shapes() {
+    let desc = LevelDesc::parse(MINIMAL).unwrap();
+    assert_eq!(desc.name, "Test");
+    assert_eq!(desc.author, "");
+    assert_eq!(desc.pixel_size(), (64, 64));
+    assert_eq!(desc.ground.color, [16, 16, 16, 255]);
+    assert_eq!(desc.ground.border_color, [200, 200, 200, 255]);
+    assert_eq!(desc.islands[0].fill, [0, 255, 0, 128]);
+    assert_eq!(desc.spawns[0].kind, SpawnKind::Alien);
+    assert!(matches!(
+        desc.spawns[0].shape,
+        Shape::Circle { radius: 4, .. }
+    ));
+    assert!(matches!(desc.spawns[1].shape, Shape::Rect(_)));
+}
+
+#[test]
+fn render_marks_the_grid_from_the_same_shapes() {
+    let desc = LevelDesc::parse(MINIMAL).unwrap();
+    let (pixels, grid) = desc.render(2);
+    assert_eq!(pixels.len(), 128 * 128 * 4);
+    assert_eq!((grid.width, grid.height, grid.tile_size), (8, 8, 8));
+
+    let tile = |x, y| *grid.get(x, y).unwrap();
+    // Border ring, with the exit broken through it.
+    assert_eq!(tile(0, 0).terrain, Terrain::Blocked);
+    assert_eq!(tile(7, 3).terrain, Terrain::Blocked);
+    assert_eq!(tile(0, 1).marker, Marker::HumanExit);
+    assert!(tile(0, 1).is_walkable());
+    // Lane 1 covers columns 3 and 4.
+    assert_eq!(tile(3, 4).lane, 1);
+    assert_eq!(tile(4, 4).lane, 1);
+    assert_eq!(tile(2, 4).lane, 0);
+    // The island blocks columns 5 and 6 except where the opening crosses it.
+    assert_eq!(tile(5, 2).terrain, Terrain::Blocked);
+    assert!(tile(5, 3).is_walkable());
+    assert_eq!(grid.find(Marker::AlienSpawnBase), [(3, 1), (4, 1)]);
+    assert_eq!(grid.find(Marker::DemonSpawnBase), [(3, 6), (4, 6)]);
+}
+
+#[test]
+fn hazards_mark_the_grid_and_keep_their_state() {
+    let desc = LevelDesc::parse(&format!("{MINIMAL}{LAVA}")).unwrap();
+    let (_, grid) = desc.render(1);
+    let hazards: Vec<_> = grid
+        .tiles
+        .iter()
+        .enumerate()
+        .filter(|(_, tile)| tile.hazard == Hazard::Lava)
+        .map(|(i, _)| i as u32)
+        .collect();
+    assert_eq!(hazards, [4 * 8 + 3, 4 * 8 + 4]);
+    assert_eq!(
+        desc.hazard_infos(),
+        [HazardInfo {
+            kind: Hazard::Lava,
+            state: HazardState::Warning,
+            x: 24,
+            y: 32,
+            width: 16,
+            height: 8,
+        }]
+    );
+    // Without `state` a hazard starts dormant.
+    let dormant = LevelDesc::parse(&format!(
+        "{MINIMAL}{}",
+        LAVA.replace("state = \"warning\"\n", "")
+    ))
+    .unwrap();
+    assert_eq!(dormant.hazards[0].state, HazardState::Dormant);
+}
+
+#[test]
+fn reports_errors_by_entry() {
+    let cases = [
+        (
+            MINIMAL.replace("width = 16\n", ""),
+            "lanes[0]: missing `width`",
+        ),
+        (
+            MINIMAL.replace("color = \"#202020\"", "color = \"lava\""),
+            "lanes[0]: `color`: no palette colour `lava`",
+        ),
+        (
+            MINIMAL.replace("fill = \"#00ff0080\"", "fill = \"#00ff0\""),
+            "islands[0]: `fill`: `#00ff0` is not a #rrggbb colour",
+        ),
+        (
+            MINIMAL.replace("kind = \"demon\"", "kind = \"robot\""),
+            "spawns[1]: unknown spawn kind `robot` (alien, demon, human or flag)",
+        ),
+        (
+            MINIMAL.replace("h = 48\n", "h = 48\nheight = 3\n"),
+            "islands[0]: unknown key `height`",
+        ),
+        (
+            MINIMAL.replace("kind = \"demon\"", "kind = \"alien\""),
+            "level needs a demon spawn",
+        ),
+        (
+            MINIMAL.replace("tile_size = 8", "tile_size = 512"),
+            "level is 4096x4096 pixels, at most 2048x2048 are allowed",
+        ),
+        (
+            format!("{MINIMAL}{}", LAVA.replace("\"lava\"", "\"spikes\"")),
+            "hazards[0]: unknown hazard kind `spikes` (lava, turret or collapse)",
+        ),
+        (
+            format!("{MINIMAL}{}", LAVA.replace("x = 24", "x = 20")),
+            "hazards[0]: must cover whole 8 px tiles inside the level",
+        ),
+        (
+            format!("{MINIMAL}{}", LAVA.replace("y = 32", "y = 64")),
+            "hazards[0]: must cover whole 8 px tiles inside the level",
+        ),
+    ];
+    for (text, expected) in cases {
+        assert_eq!(LevelDesc::parse(&text).unwrap_err(), expected);
+    }
+}
@@ -0,0 +1,81 @@
+use bvb_level_builder::LevelDesc;
+use bvb_level_kit::{Hazard, Marker, NavGraph};
+
+// Every description shipped as a level crate.
+const SHIPPED: [(&str, &str); 2] = [
+    (
+        "demon_lair",
+        include_str!("../../bvb_level_demon_lair/demon_lair.toml"),
+    ),
+    (
+        "alien_ship",
+        include_str!("../../bvb_level_alien_ship/alien_ship.toml"),
+    ),
+];
+
+// The same rules the parking lot's golden tests hold its variants to.
+#[test]
+fn shipped_levels_keep_invariants() {
+    for (label, source) in SHIPPED {
+        let desc = LevelDesc::parse(source).unwrap_or_else(|err| panic!("{label}: {err}"));
+        let (pixels, grid) = desc.render(1);
+        let (width, height) = desc.pixel_size();
+        assert_eq!(pixels.len(), (width * height * 4) as usize, "{label}");
+
+        let blocked = |x: u32, y: u32| !grid.get(x, y).unwrap().is_walkable();
+        let on_edge = |&(x, y): &(u32, u32)| {
+            (0..x).all(|tx| blocked(tx, y))
+                || (x + 1..grid.width).all(|tx| blocked(tx, y))
+                || (0..y).all(|ty| blocked(x, ty))
+                || (y + 1..grid.height).all(|ty| blocked(x, ty))
+        };
+        let regions = NavGraph::new(&grid).regions();
+        let alien = grid.find(Marker::AlienSpawnBase);
+        let demon = grid.find(Marker::DemonSpawnBase);
+        assert!(
+            regions.connected(&[alien[0], demon[0]]),
+            "{label}: demon base is not reachable from the alien base"
+        );
+        for (marker, what) in [
+            (Marker::HumanExit, "exit"),
+            (Marker::HumanSpawn, "human spawn"),
+            (Marker::FlagBase, "flag"),
+        ] {
+            let tiles = grid.find(marker);
+            assert!(!tiles.is_empty(), "{label}: no {what} tile");
+            assert!(
+                tiles
+                    .iter()
+                    .any(|&tile| regions.connected(&[alien[0], tile])),
+                "{label}: {what} is not reachable from the spawn bases"
+            );
+        }
+        assert!(
+            grid.find(Marker::HumanExit).iter().any(on_edge),
+            "{label}: exit does not touch the map edge"
+        );
+        // Hazard tiles are exactly the tiles the hazard table covers.
+        let tile = desc.tile_size;
+        let mut expected = vec![Hazard::None; grid.tiles.len()];
+        for hazard in desc.hazard_infos() {
+            for ty in hazard.y / tile..(hazard.y + hazard.height) / tile {
+                for tx in hazard.x / tile..(hazard.x + hazard.width) / tile {
+                    expected[(ty * grid.width + tx) as usize] = hazard.kind;
+                }
+            }
+        }
+        let marked: Vec<_> = grid.tiles.iter().map(|tile| tile.hazard).collect();
+        assert_eq!(marked, expected, "{label}: hazard tiles");
+        assert!(!desc.hazards.is_empty(), "{label}: no hazards");
+
+        for lane in 1..desc.lanes.len() as u8 {
+            assert!(
+                !NavGraph::new(&grid)
+                    .lane_crossings(lane, lane + 1)
+                    .is_empty(),
+                "{label}: lanes {lane} and {} never meet",
+                lane + 1
+            );
+        }
+    }
+}
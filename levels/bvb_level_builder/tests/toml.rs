@@ -0,0 +1,81 @@
+use bvb_level_builder::toml::{Value, parse};
+
+#[test]
+fn parses_tables_arrays_and_inline_values() {
+    let doc = parse(
+        r##"
+# comment
+name = "Lair" # trailing comment
+size = 1_024
+
+[palette]
+lava = "#a8321a"
+
+[[lanes]]
+center = -8
+points = [
+    [1, 2],
+    [3, 4],
+]
+
+[[lanes]]
+flag = { open = true, label = "a\"b" }
+"##,
+    )
+    .unwrap();
+
+    let get = |key: &str| doc.iter().find(|(name, _)| name == key).map(|(_, v)| v);
+    assert_eq!(get("name"), Some(&Value::String("Lair".into())));
+    assert_eq!(get("size"), Some(&Value::Integer(1024)));
+    assert_eq!(
+        get("palette"),
+        Some(&Value::Table(vec![(
+            "lava".into(),
+            Value::String("#a8321a".into())
+        )]))
+    );
+    let Some(Value::Array(lanes)) = get("lanes") else {
+        panic!("lanes is not an array");
+    };
+    assert_eq!(lanes.len(), 2);
+    assert_eq!(
+        lanes[0],
+        Value::Table(vec![
+            ("center".into(), Value::Integer(-8)),
+            (
+                "points".into(),
+                Value::Array(vec![
+                    Value::Array(vec![Value::Integer(1), Value::Integer(2)]),
+                    Value::Array(vec![Value::Integer(3), Value::Integer(4)]),
+                ])
+            ),
+        ])
+    );
+    assert_eq!(
+        lanes[1],
+        Value::Table(vec![(
+            "flag".into(),
+            Value::Table(vec![
+                ("open".into(), Value::Boolean(true)),
+                ("label".into(), Value::String("a\"b".into())),
+            ])
+        )])
+    );
+}
+
+#[test]
+fn rejects_unsupported_syntax_with_line_numbers() {
+    for (text, expected) in [
+        (
+            "a = 1\nb = 1.5",
+            "line 2: `1.5`: only integers are supported",
+        ),
+        ("a = 1\na = 2", "line 2: `a` is defined twice"),
+        ("[t]\n[t]", "line 2: `t` is defined twice"),
+        ("[a.b]", "line 1: nested table names are not supported"),
+        ("a = \"open", "line 1: unterminated string"),
+        ("a = 1 2", "line 1: expected the end of the line, found `2`"),
+    ] {
+        assert_eq!(parse(text).unwrap_err(), expected, "{text:?}");
+    }
+}
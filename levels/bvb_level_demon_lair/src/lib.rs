@@ -0,0 +1,3 @@
+// The Demon Lair map, built from `demon_lair.toml` by `bvb_level_builder`.
+
+bvb_level_builder::level_module!("../demon_lair.toml");
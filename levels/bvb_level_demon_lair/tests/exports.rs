@@ -0,0 +1,36 @@
+use bvb_level_builder::LevelDesc;
+use bvb_level_demon_lair::*;
+use bvb_level_kit::hazards_from_bytes;
+
+// The exports `level_module!` generates, called the way a host would.
+#[test]
+fn exports_follow_the_abi() {
+    bvb_level_init();
+    assert_eq!(bvb_level_abi_version(), bvb_level_builder::ABI_VERSION);
+    assert_eq!((bvb_level_width(), bvb_level_height()), (288, 512));
+    assert_eq!(bvb_level_pixels_len(), 288 * 512 * 4);
+    assert_eq!(
+        bvb_level_semantics_len(),
+        bvb_level_grid_width() * bvb_level_grid_height() * 4
+    );
+    let name =
+        unsafe { std::slice::from_raw_parts(bvb_level_name_ptr(), bvb_level_name_len() as usize) };
+    let desc = LevelDesc::parse(DESCRIPTION).unwrap();
+    assert_eq!(name, desc.name.as_bytes());
+    let hazards = unsafe {
+        std::slice::from_raw_parts(bvb_level_hazards_ptr(), bvb_level_hazards_len() as usize)
+    };
+    assert_eq!(hazards_from_bytes(hazards), Ok(desc.hazard_infos()));
+    let hash = bvb_level_content_hash();
+
+    assert_eq!(bvb_level_render(5), 1);
+    assert_eq!(bvb_level_render(2), 0);
+    assert_eq!((bvb_level_width(), bvb_level_scale()), (576, 2));
+    assert_ne!(bvb_level_content_hash(), hash);
+
+    bvb_level_release();
+    assert!(bvb_level_pixels_ptr().is_null());
+    assert_eq!(bvb_level_content_hash(), 0);
+    bvb_level_init();
+    assert_eq!(bvb_level_content_hash(), hash);
+}
@@ -0,0 +1,108 @@
+use crate::Hazard;
+
+/// Bytes per record in the `bvb_level_hazards_*` table: six little-endian `u32`s in `HazardInfo`
+/// field order.
+pub const HAZARD_INFO_BYTES: usize = 24;
+
+/// Where a hazard is in its trigger cycle. Simulation code moves hazards between states; the
+/// module only sets where each one starts.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HazardState {
+    /// Harmless and not drawn.
+    #[default]
+    Dormant = 0,
+    /// About to trigger; drawn as a flashing telegraph.
+    Warning = 1,
+    /// Hurting whatever stands on it.
+    Active = 2,
+    /// Spent for the rest of the match, e.g. a collapsed floor or a wrecked turret.
+    Depleted = 3,
+}
+
+/// One hazard: a rectangle of tiles in level pixels, aligned to the tile grid, whose tiles carry
+/// `kind` in the semantic grid.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HazardInfo {
+    pub kind: Hazard,
+    pub state: HazardState,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl HazardInfo {
+    pub fn to_bytes(self) -> [u8; HAZARD_INFO_BYTES] {
+        let fields = [
+            self.kind as u32,
+            self.state as u32,
+            self.x,
+            self.y,
+            self.width,
+            self.height,
+        ];
+        let mut bytes = [0; HAZARD_INFO_BYTES];
+        for (chunk, field) in bytes.chunks_exact_mut(4).zip(fields) {
+            chunk.copy_from_slice(&field.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Returns `None` for an unknown kind or state, `Hazard::None`, or a hazard without area.
+    pub fn from_bytes(bytes: [u8; HAZARD_INFO_BYTES]) -> Option<Self> {
+        let mut fields = bytes
+            .chunks_exact(4)
+            .map(|chunk| u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]));
+        let mut next = || fields.next().unwrap_or_default();
+        let kind = match next() {
+            1 => Hazard::Lava,
+            2 => Hazard::Turret,
+            3 => Hazard::Collapse,
+            _ => return None,
+        };
+        let state = match next() {
+            0 => HazardState::Dormant,
+            1 => HazardState::Warning,
+            2 => HazardState::Active,
+            3 => HazardState::Depleted,
+            _ => return None,
+        };
+        let info = Self {
+            kind,
+            state,
+            x: next(),
+            y: next(),
+            width: next(),
+            height: next(),
+        };
+        (info.width > 0 && info.height > 0).then_some(info)
+    }
+}
+
+/// Encodes the `bvb_level_hazards_*` table.
+pub fn hazards_to_bytes(hazards: &[HazardInfo]) -> Vec<u8> {
+    hazards
+        .iter()
+        .flat_map(|hazard| hazard.to_bytes())
+        .collect()
+}
+
+/// Decodes a `bvb_level_hazards_*` table.
+pub fn hazards_from_bytes(bytes: &[u8]) -> Result<Vec<HazardInfo>, String> {
+    if !bytes.len().is_multiple_of(HAZARD_INFO_BYTES) {
+        return Err(format!(
+            "hazard table has {} bytes, not a multiple of {HAZARD_INFO_BYTES}",
+            bytes.len()
+        ));
+    }
+    bytes
+        .chunks_exact(HAZARD_INFO_BYTES)
+        .enumerate()
+        .map(|(index, chunk)| {
+            let mut raw = [0u8; HAZARD_INFO_BYTES];
+            raw.copy_from_slice(chunk);
+            HazardInfo::from_bytes(raw).ok_or_else(|| format!("hazard {index} is invalid"))
+        })
+        .collect()
+}
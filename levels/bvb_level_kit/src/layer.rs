@@ -0,0 +1,121 @@
+/// Bytes per record in the `bvb_level_layers_*` table: seven little-endian `u32`s in `LayerInfo`
+/// field order.
+pub const LAYER_INFO_BYTES: usize = 28;
+
+/// Where a layer sits in the draw order.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LayerKind {
+    /// The static map under everything else, always the full level.
+    #[default]
+    Ground = 0,
+    /// Drawn over the ground and under units, cycling through its frames.
+    Animated = 1,
+    /// Drawn over units, e.g. tree canopies they walk behind.
+    Foreground = 2,
+}
+
+/// One layer of a level image. The rectangle is in level pixels; the layer's pixels are at the
+/// level's current scale, with its frames stacked top to bottom.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LayerInfo {
+    pub kind: LayerKind,
+    /// At least 1.
+    pub frames: u32,
+    /// How long each frame shows; 0 for single-frame layers.
+    pub frame_ms: u32,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl LayerInfo {
+    /// Bytes of RGBA pixels the layer holds at `scale`.
+    pub fn pixels_len(&self, scale: u32) -> usize {
+        4 * (self.width * scale) as usize * (self.height * scale) as usize * self.frames as usize
+    }
+
+    /// The frame showing `elapsed_ms` after the level appeared.
+    pub fn frame_at(&self, elapsed_ms: u64) -> u32 {
+        if self.frames <= 1 || self.frame_ms == 0 {
+            return 0;
+        }
+        ((elapsed_ms / u64::from(self.frame_ms)) % u64::from(self.frames)) as u32
+    }
+
+    pub fn to_bytes(self) -> [u8; LAYER_INFO_BYTES] {
+        let fields = [
+            self.kind as u32,
+            self.frames,
+            self.frame_ms,
+            self.x,
+            self.y,
+            self.width,
+            self.height,
+        ];
+        let mut bytes = [0; LAYER_INFO_BYTES];
+        for (chunk, field) in bytes.chunks_exact_mut(4).zip(fields) {
+            chunk.copy_from_slice(&field.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Returns `None` for an unknown kind or a layer without frames or area.
+    pub fn from_bytes(bytes: [u8; LAYER_INFO_BYTES]) -> Option<Self> {
+        let mut fields = bytes
+            .chunks_exact(4)
+            .map(|chunk| u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]));
+        let mut next = || fields.next().unwrap_or_default();
+        let kind = match next() {
+            0 => LayerKind::Ground,
+            1 => LayerKind::Animated,
+            2 => LayerKind::Foreground,
+            _ => return None,
+        };
+        let info = Self {
+            kind,
+            frames: next(),
+            frame_ms: next(),
+            x: next(),
+            y: next(),
+            width: next(),
+            height: next(),
+        };
+        (info.frames > 0 && info.width > 0 && info.height > 0).then_some(info)
+    }
+}
+
+/// A layer with its pixels, as level crates produce and hosts copy out.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LevelLayer {
+    pub info: LayerInfo,
+    pub pixels: Vec<u8>,
+}
+
+/// Encodes the `bvb_level_layers_*` table for `layers`.
+pub fn layers_to_bytes(layers: &[LevelLayer]) -> Vec<u8> {
+    layers
+        .iter()
+        .flat_map(|layer| layer.info.to_bytes())
+        .collect()
+}
+
+/// Decodes a `bvb_level_layers_*` table.
+pub fn layers_from_bytes(bytes: &[u8]) -> Result<Vec<LayerInfo>, String> {
+    if !bytes.len().is_multiple_of(LAYER_INFO_BYTES) {
+        return Err(format!(
+            "layer table has {} bytes, not a multiple of {LAYER_INFO_BYTES}",
+            bytes.len()
+        ));
+    }
+    bytes
+        .chunks_exact(LAYER_INFO_BYTES)
+        .enumerate()
+        .map(|(index, chunk)| {
+            let mut raw = [0u8; LAYER_INFO_BYTES];
+            raw.copy_from_slice(chunk);
+            LayerInfo::from_bytes(raw).ok_or_else(|| format!("layer {index} is invalid"))
+        })
+        .collect()
+}
@@ -0,0 +1,362 @@
+// Shared definitions for level modules and the hosts that load them.
+//
+// Level crates use this to build their exports; hosts use it to decode them, so both sides agree on
+// the ABI version and the semantic tile encoding. See `docs/levels.md`.
+
+mod hazard;
+mod layer;
+mod nav;
+
+pub use hazard::{
+    HAZARD_INFO_BYTES, HazardInfo, HazardState, hazards_from_bytes, hazards_to_bytes,
+};
+pub use layer::{
+    LAYER_INFO_BYTES, LayerInfo, LayerKind, LevelLayer, layers_from_bytes, layers_to_bytes,
+};
+pub use nav::{DIAGONAL_COST, FlowField, LaneCrossing, NavGraph, Regions, STEP_COST, Tile};
+
+/// Version reported by `bvb_level_abi_version`. Modules without that export are version 0.
+///
+/// Version 2 passes params in buffers from `bvb_alloc` instead of the `bvb_level_params_ptr`
+/// scratch buffer, and adds `bvb_level_release`.
+pub const ABI_VERSION: u32 = 2;
+
+/// Bytes per tile in the `bvb_level_semantics_*` buffer: terrain, lane, marker, hazard.
+pub const SEMANTIC_TILE_BYTES: usize = 4;
+
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Terrain {
+    #[default]
+    Blocked = 0,
+    Walkable = 1,
+}
+
+/// Gameplay anchors placed on the map.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Marker {
+    #[default]
+    None = 0,
+    AlienSpawnBase = 1,
+    DemonSpawnBase = 2,
+    FlagBase = 3,
+    /// Humans escaping through this tile with the flag win the match.
+    HumanExit = 4,
+    HumanSpawn = 5,
+}
+
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Hazard {
+    #[default]
+    None = 0,
+    Lava = 1,
+    Turret = 2,
+    Collapse = 3,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SemanticTile {
+    pub terrain: Terrain,
+    /// 0 outside lanes, otherwise the lane number counted from the left (1-based).
+    pub lane: u8,
+    pub marker: Marker,
+    pub hazard: Hazard,
+}
+
+impl SemanticTile {
+    pub const WALKABLE: Self = Self {
+        terrain: Terrain::Walkable,
+        lane: 0,
+        marker: Marker::None,
+        hazard: Hazard::None,
+    };
+
+    pub fn is_walkable(&self) -> bool {
+        self.terrain == Terrain::Walkable
+    }
+
+    pub fn to_bytes(self) -> [u8; SEMANTIC_TILE_BYTES] {
+        [
+            self.terrain as u8,
+            self.lane,
+            self.marker as u8,
+            self.hazard as u8,
+        ]
+    }
+
+    /// Returns `None` for unknown terrain, marker or hazard codes.
+    pub fn from_bytes(bytes: [u8; SEMANTIC_TILE_BYTES]) -> Option<Self> {
+        let terrain = match bytes[0] {
+            0 => Terrain::Blocked,
+            1 => Terrain::Walkable,
+            _ => return None,
+        };
+        let marker = match bytes[2] {
+            0 => Marker::None,
+            1 => Marker::AlienSpawnBase,
+            2 => Marker::DemonSpawnBase,
+            3 => Marker::FlagBase,
+            4 => Marker::HumanExit,
+            5 => Marker::HumanSpawn,
+            _ => return None,
+        };
+        let hazard = match bytes[3] {
+            0 => Hazard::None,
+            1 => Hazard::Lava,
+            2 => Hazard::Turret,
+            3 => Hazard::Collapse,
+            _ => return None,
+        };
+        Some(Self {
+            terrain,
+            lane: bytes[1],
+            marker,
+            hazard,
+        })
+    }
+}
+
+/// Row-major tile grid describing what each tile of a level means to the rules.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SemanticGrid {
+    pub width: u32,
+    pub height: u32,
+    pub tile_size: u32,
+    pub tiles: Vec<SemanticTile>,
+}
+
+impl SemanticGrid {
+    pub fn new(width: u32, height: u32, tile_size: u32, fill: SemanticTile) -> Self {
+        Self {
+            width,
+            height,
+            tile_size,
+            tiles: vec![fill; (width * height) as usize],
+        }
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> Option<&SemanticTile> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        self.tiles.get((y * self.width + x) as usize)
+    }
+
+    pub fn get_mut(&mut self, x: u32, y: u32) -> Option<&mut SemanticTile> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        self.tiles.get_mut((y * self.width + x) as usize)
+    }
+
+    /// Tile containing the pixel `(x, y)`.
+    pub fn at_pixel(&mut self, x: i32, y: i32) -> Option<&mut SemanticTile> {
+        if x < 0 || y < 0 {
+            return None;
+        }
+        let tile_size = self.tile_size.max(1);
+        self.get_mut(x as u32 / tile_size, y as u32 / tile_size)
+    }
+
+    /// Applies `f` to every tile whose centre lies inside the pixel rectangle.
+    ///
+    /// Sampling tile centres keeps the grid consistent with what the art shows at tile scale.
+    pub fn mark_rect(&mut self, x: i32, y: i32, w: i32, h: i32, f: impl Fn(&mut SemanticTile)) {
+        self.mark_where(f, |cx, cy| cx >= x && cx < x + w && cy >= y && cy < y + h);
+    }
+
+    /// Applies `f` to every tile whose centre lies inside the pixel circle.
+    pub fn mark_circle(&mut self, cx: i32, cy: i32, radius: i32, f: impl Fn(&mut SemanticTile)) {
+        self.mark_where(f, |x, y| {
+            let (dx, dy) = (x - cx, y - cy);
+            dx * dx + dy * dy <= radius * radius
+        });
+    }
+
+    fn mark_where(&mut self, f: impl Fn(&mut SemanticTile), inside: impl Fn(i32, i32) -> bool) {
+        let half = (self.tile_size / 2) as i32;
+        for ty in 0..self.height {
+            for tx in 0..self.width {
+                let cx = (tx * self.tile_size) as i32 + half;
+                let cy = (ty * self.tile_size) as i32 + half;
+                if inside(cx, cy) {
+                    f(&mut self.tiles[(ty * self.width + tx) as usize]);
+                }
+            }
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.tiles.iter().flat_map(|tile| tile.to_bytes()).collect()
+    }
+
+    pub fn from_bytes(
+        width: u32,
+        height: u32,
+        tile_size: u32,
+        bytes: &[u8],
+    ) -> Result<Self, String> {
+        let expected = (width as usize)
+            .checked_mul(height as usize)
+            .and_then(|tiles| tiles.checked_mul(SEMANTIC_TILE_BYTES))
+            .ok_or_else(|| "semantic grid dimensions overflow".to_string())?;
+        if bytes.len() != expected {
+            return Err(format!(
+                "semantic grid has {} bytes, expected {expected} for {width}x{height} tiles",
+                bytes.len()
+            ));
+        }
+        let tiles = bytes
+            .chunks_exact(SEMANTIC_TILE_BYTES)
+            .enumerate()
+            .map(|(index, chunk)| {
+                let mut raw = [0u8; SEMANTIC_TILE_BYTES];
+                raw.copy_from_slice(chunk);
+                SemanticTile::from_bytes(raw)
+                    .ok_or_else(|| format!("semantic tile {index} has an unknown code {raw:?}"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            width,
+            height,
+            tile_size,
+            tiles,
+        })
+    }
+
+    /// Tile coordinates of every tile carrying `marker`, in row-major order.
+    pub fn find(&self, marker: Marker) -> Vec<(u32, u32)> {
+        self.tiles
+            .iter()
+            .enumerate()
+            .filter(|(_, tile)| tile.marker == marker)
+            .map(|(index, _)| (index as u32 % self.width, index as u32 / self.width))
+            .collect()
+    }
+}
+
+/// Which map edge holds the human exit; the human spawn sits on the opposite edge.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ExitSide {
+    #[default]
+    Left = 0,
+    Right = 1,
+}
+
+/// Generation parameters passed to `bvb_level_init_with`.
+///
+/// Encoded as one byte per field in declaration order. Shorter buffers leave the remaining fields at
+/// their defaults and extra bytes are ignored, so hosts and modules can add fields independently.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LevelParams {
+    /// Lane width in pixels.
+    pub lane_width: u8,
+    /// Staggered openings in the median islands, besides the full middle crossover.
+    pub crossovers: u8,
+    /// Parking bay stalls: 0 none, 1 sparse, 2 normal, 3 dense.
+    pub bay_density: u8,
+    pub exit_side: ExitSide,
+}
+
+impl Default for LevelParams {
+    fn default() -> Self {
+        Self {
+            lane_width: 36,
+            crossovers: 2,
+            bay_density: 2,
+            exit_side: ExitSide::Left,
+        }
+    }
+}
+
+impl LevelParams {
+    pub const ENCODED_LEN: usize = 4;
+
+    pub fn to_bytes(self) -> [u8; Self::ENCODED_LEN] {
+        [
+            self.lane_width,
+            self.crossovers,
+            self.bay_density,
+            self.exit_side as u8,
+        ]
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let mut params = Self::default();
+        if let Some(&lane_width) = bytes.first() {
+            params.lane_width = lane_width;
+        }
+        if let Some(&crossovers) = bytes.get(1) {
+            params.crossovers = crossovers;
+        }
+        if let Some(&bay_density) = bytes.get(2) {
+            params.bay_density = bay_density;
+        }
+        if let Some(&exit_side) = bytes.get(3) {
+            params.exit_side = match exit_side {
+                0 => ExitSide::Left,
+                1 => ExitSide::Right,
+                other => return Err(format!("unknown exit side {other}")),
+            };
+        }
+        Ok(params)
+    }
+}
+
+/// SplitMix64: tiny, seedable and identical on every platform, so a seed always yields the same map.
+/// The renderer seeds its particle emitters with it too.
+#[derive(Clone, Debug)]
+pub struct LevelRng(u64);
+
+impl LevelRng {
+    pub fn new(seed: u32) -> Self {
+        Self(u64::from(seed))
+    }
+
+    /// Seeds all 64 bits of state; `new(seed)` equals `from_u64(seed as u64)`.
+    pub fn from_u64(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `min..=max`; returns `min` when the range is empty.
+    pub fn range(&mut self, min: i32, max: i32) -> i32 {
+        if max <= min {
+            return min;
+        }
+        let span = (i64::from(max) - i64::from(min) + 1) as u64;
+        (i64::from(min) + (self.next_u64() % span) as i64) as i32
+    }
+
+    /// Uniform in `[0, 1)` with 24 bits of precision.
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// Uniform in `[min, max)`.
+    pub fn range_f32(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.next_f32()
+    }
+}
+
+/// Value for `bvb_level_content_hash`: FNV-1a over the image dimensions and pixels, so any visual
+/// change yields a new hash.
+pub fn content_hash(width: u32, height: u32, pixels: &[u8]) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325u64;
+    let header = [width.to_le_bytes(), height.to_le_bytes()];
+    for &byte in header.iter().flatten().chain(pixels) {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash
+}
@@ -0,0 +1,294 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+
+use crate::SemanticGrid;
+
+/// Cost of a straight step; diagonal steps cost `DIAGONAL_COST`, roughly `STEP_COST * sqrt(2)`.
+pub const STEP_COST: u32 = 10;
+pub const DIAGONAL_COST: u32 = 14;
+
+/// Tile coordinates, as returned by `SemanticGrid::find`.
+pub type Tile = (u32, u32);
+
+const DIRECTIONS: [(i32, i32); 8] = [
+    (0, -1),
+    (1, 0),
+    (0, 1),
+    (-1, 0),
+    (1, -1),
+    (1, 1),
+    (-1, 1),
+    (-1, -1),
+];
+
+/// Walkability graph over a level's tiles: 8-connected, where a diagonal step needs both tiles it
+/// squeezes past to be walkable, so paths never cut through the corner of a wall.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NavGraph {
+    pub width: u32,
+    pub height: u32,
+    walkable: Vec<bool>,
+    lanes: Vec<u8>,
+}
+
+impl NavGraph {
+    pub fn new(grid: &SemanticGrid) -> Self {
+        Self {
+            width: grid.width,
+            height: grid.height,
+            walkable: grid.tiles.iter().map(|tile| tile.is_walkable()).collect(),
+            lanes: grid.tiles.iter().map(|tile| tile.lane).collect(),
+        }
+    }
+
+    pub fn is_walkable(&self, (x, y): Tile) -> bool {
+        x < self.width && y < self.height && self.walkable[self.index((x, y))]
+    }
+
+    /// Walkable tiles one step from `tile`, with the cost of the step.
+    pub fn neighbours(&self, (x, y): Tile) -> impl Iterator<Item = (Tile, u32)> + '_ {
+        DIRECTIONS.iter().filter_map(move |&(dx, dy)| {
+            let to = self.offset((x, y), dx, dy)?;
+            if dx == 0 || dy == 0 {
+                return Some((to, STEP_COST));
+            }
+            // Both tiles beside the diagonal must be walkable too.
+            self.offset((x, y), dx, 0)?;
+            self.offset((x, y), 0, dy)?;
+            Some((to, DIAGONAL_COST))
+        })
+    }
+
+    /// Shortest path from `from` to `to` with A*, both ends included. `None` when either end is
+    /// blocked or no path exists.
+    pub fn find_path(&self, from: Tile, to: Tile) -> Option<Vec<Tile>> {
+        if !self.is_walkable(from) || !self.is_walkable(to) {
+            return None;
+        }
+        let mut cost = vec![u32::MAX; self.walkable.len()];
+        let mut came_from = vec![usize::MAX; self.walkable.len()];
+        let mut open = BinaryHeap::new();
+        cost[self.index(from)] = 0;
+        open.push(Reverse((octile(from, to), 0, from)));
+        while let Some(Reverse((_, spent, tile))) = open.pop() {
+            if tile == to {
+                break;
+            }
+            if spent > cost[self.index(tile)] {
+                continue;
+            }
+            for (next, step) in self.neighbours(tile) {
+                let next_cost = spent + step;
+                let i = self.index(next);
+                if next_cost < cost[i] {
+                    cost[i] = next_cost;
+                    came_from[i] = self.index(tile);
+                    open.push(Reverse((next_cost + octile(next, to), next_cost, next)));
+                }
+            }
+        }
+        if cost[self.index(to)] == u32::MAX {
+            return None;
+        }
+        let mut path = vec![to];
+        let mut i = self.index(to);
+        while i != self.index(from) {
+            i = came_from[i];
+            path.push(self.tile(i));
+        }
+        path.reverse();
+        Some(path)
+    }
+
+    /// Distances from every tile to the nearest of `goals`, for steering many agents toward the
+    /// same targets, e.g. flag carriers heading for the exit.
+    pub fn flow_field(&self, goals: &[Tile]) -> FlowField {
+        let mut distance = vec![u32::MAX; self.walkable.len()];
+        let mut open = BinaryHeap::new();
+        for &goal in goals.iter().filter(|&&goal| self.is_walkable(goal)) {
+            distance[self.index(goal)] = 0;
+            open.push(Reverse((0, goal)));
+        }
+        while let Some(Reverse((spent, tile))) = open.pop() {
+            if spent > distance[self.index(tile)] {
+                continue;
+            }
+            for (next, step) in self.neighbours(tile) {
+                let i = self.index(next);
+                if spent + step < distance[i] {
+                    distance[i] = spent + step;
+                    open.push(Reverse((spent + step, next)));
+                }
+            }
+        }
+        FlowField {
+            graph: self.clone(),
+            distance,
+        }
+    }
+
+    /// Labels the connected walkable areas. More than one region means some walkable tiles can
+    /// never be reached from the others.
+    pub fn regions(&self) -> Regions {
+        let mut labels = vec![None; self.walkable.len()];
+        let mut count = 0;
+        for start in 0..self.walkable.len() {
+            if !self.walkable[start] || labels[start].is_some() {
+                continue;
+            }
+            labels[start] = Some(count);
+            let mut queue = VecDeque::from([self.tile(start)]);
+            while let Some(tile) = queue.pop_front() {
+                for (next, _) in self.neighbours(tile) {
+                    let i = self.index(next);
+                    if labels[i].is_none() {
+                        labels[i] = Some(count);
+                        queue.push_back(next);
+                    }
+                }
+            }
+            count += 1;
+        }
+        Regions {
+            width: self.width,
+            labels,
+            count,
+        }
+    }
+
+    /// Places where lanes `a` and `b` connect directly: runs of rows in which a straight walkable
+    /// stretch of unlaned tiles joins a lane `a` tile to a lane `b` tile. Ordered top to bottom.
+    ///
+    /// Level authors can compare these against the openings they meant to carve. An opening that
+    /// misses every tile centre leaves the island blocked and does not show up here.
+    pub fn lane_crossings(&self, a: u8, b: u8) -> Vec<LaneCrossing> {
+        let mut crossings: Vec<LaneCrossing> = Vec::new();
+        for y in 0..self.height {
+            if !self.row_crosses(y, a, b) {
+                continue;
+            }
+            match crossings.last_mut() {
+                Some(last) if last.bottom + 1 == y => last.bottom = y,
+                _ => crossings.push(LaneCrossing {
+                    lanes: (a, b),
+                    top: y,
+                    bottom: y,
+                }),
+            }
+        }
+        crossings
+    }
+
+    fn row_crosses(&self, y: u32, a: u8, b: u8) -> bool {
+        // Lane of the last laned tile since the last blocked tile in this row.
+        let mut last_lane = None;
+        for x in 0..self.width {
+            let i = self.index((x, y));
+            if !self.walkable[i] {
+                last_lane = None;
+                continue;
+            }
+            let lane = self.lanes[i];
+            if lane == 0 {
+                continue;
+            }
+            if last_lane.is_some_and(|prev| (prev, lane) == (a, b) || (prev, lane) == (b, a)) {
+                return true;
+            }
+            last_lane = Some(lane);
+        }
+        false
+    }
+
+    fn index(&self, (x, y): Tile) -> usize {
+        (y * self.width + x) as usize
+    }
+
+    fn tile(&self, index: usize) -> Tile {
+        (index as u32 % self.width, index as u32 / self.width)
+    }
+
+    fn offset(&self, (x, y): Tile, dx: i32, dy: i32) -> Option<Tile> {
+        let to = (x.checked_add_signed(dx)?, y.checked_add_signed(dy)?);
+        self.is_walkable(to).then_some(to)
+    }
+}
+
+/// Output of `NavGraph::flow_field`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FlowField {
+    graph: NavGraph,
+    distance: Vec<u32>,
+}
+
+impl FlowField {
+    /// Path cost from `tile` to the nearest goal, `None` when no goal is reachable.
+    pub fn distance(&self, tile: Tile) -> Option<u32> {
+        if tile.0 >= self.graph.width || tile.1 >= self.graph.height {
+            return None;
+        }
+        let distance = self.distance[self.graph.index(tile)];
+        (distance != u32::MAX).then_some(distance)
+    }
+
+    /// The neighbour to move to from `tile`, `None` at a goal or where no goal is reachable.
+    pub fn next_step(&self, tile: Tile) -> Option<Tile> {
+        let here = self.distance(tile)?;
+        self.graph
+            .neighbours(tile)
+            .filter_map(|(next, step)| Some((self.distance(next)? + step, next)))
+            .filter(|&(through, _)| through == here)
+            .map(|(_, next)| next)
+            .next()
+    }
+}
+
+/// Output of `NavGraph::regions`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Regions {
+    width: u32,
+    labels: Vec<Option<u32>>,
+    count: u32,
+}
+
+impl Regions {
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    /// Region of `tile`, `None` for blocked tiles.
+    pub fn label(&self, (x, y): Tile) -> Option<u32> {
+        if x >= self.width {
+            return None;
+        }
+        self.labels
+            .get((y * self.width + x) as usize)
+            .copied()
+            .flatten()
+    }
+
+    /// Whether every tile in `tiles` is walkable and in one region.
+    pub fn connected(&self, tiles: &[Tile]) -> bool {
+        let mut labels = tiles.iter().map(|&tile| self.label(tile));
+        match labels.next() {
+            Some(Some(first)) => labels.all(|label| label == Some(first)),
+            Some(None) => false,
+            None => true,
+        }
+    }
+}
+
+/// A run of rows where two lanes connect directly, from `NavGraph::lane_crossings`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LaneCrossing {
+    pub lanes: (u8, u8),
+    /// First and last tile row of the run.
+    pub top: u32,
+    pub bottom: u32,
+}
+
+fn octile(from: Tile, to: Tile) -> u32 {
+    let dx = from.0.abs_diff(to.0);
+    let dy = from.1.abs_diff(to.1);
+    STEP_COST * dx.max(dy) + (DIAGONAL_COST - STEP_COST) * dx.min(dy)
+}
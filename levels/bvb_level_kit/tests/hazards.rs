@@ -0,0 +1,42 @@
+use bvb_level_kit::{
+    HAZARD_INFO_BYTES, Hazard, HazardInfo, HazardState, hazards_from_bytes, hazards_to_bytes,
+};
+
+#[test]
+fn hazard_table_round_trips_and_rejects_bad_records() {
+    let hazards = [
+        HazardInfo {
+            kind: Hazard::Lava,
+            state: HazardState::Dormant,
+            x: 16,
+            y: 32,
+            width: 16,
+            height: 16,
+        },
+        HazardInfo {
+            kind: Hazard::Collapse,
+            state: HazardState::Depleted,
+            x: 112,
+            y: 160,
+            width: 64,
+            height: 16,
+        },
+    ];
+    let bytes = hazards_to_bytes(&hazards);
+    assert_eq!(bytes.len(), 2 * HAZARD_INFO_BYTES);
+    assert_eq!(hazards_from_bytes(&bytes), Ok(hazards.to_vec()));
+
+    assert!(hazards_from_bytes(&bytes[..HAZARD_INFO_BYTES - 1]).is_err());
+    // `Hazard::None` is not a hazard, and states stop at depleted.
+    for (offset, value) in [(0, 0), (0, 4), (4, 4)] {
+        let mut bad = bytes.clone();
+        bad[HAZARD_INFO_BYTES + offset] = value;
+        assert_eq!(
+            hazards_from_bytes(&bad),
+            Err("hazard 1 is invalid".to_string())
+        );
+    }
+    let mut empty = bytes;
+    empty[16..20].fill(0);
+    assert!(hazards_from_bytes(&empty).is_err());
+}
@@ -0,0 +1,56 @@
+use bvb_level_kit::{
+    LAYER_INFO_BYTES, LayerInfo, LayerKind, LevelLayer, layers_from_bytes, layers_to_bytes,
+};
+
+fn layer(kind: LayerKind, frames: u32, frame_ms: u32) -> LevelLayer {
+    LevelLayer {
+        info: LayerInfo {
+            kind,
+            frames,
+            frame_ms,
+            x: 8,
+            y: 16,
+            width: 32,
+            height: 24,
+        },
+        pixels: Vec::new(),
+    }
+}
+
+#[test]
+fn layer_table_round_trips_and_rejects_bad_records() {
+    let layers = [
+        layer(LayerKind::Ground, 1, 0),
+        layer(LayerKind::Animated, 4, 180),
+        layer(LayerKind::Foreground, 1, 0),
+    ];
+    let bytes = layers_to_bytes(&layers);
+    assert_eq!(bytes.len(), 3 * LAYER_INFO_BYTES);
+    let infos: Vec<_> = layers.iter().map(|layer| layer.info).collect();
+    assert_eq!(layers_from_bytes(&bytes), Ok(infos));
+    assert_eq!(layers_from_bytes(&[]), Ok(Vec::new()));
+
+    assert!(layers_from_bytes(&bytes[..LAYER_INFO_BYTES + 3]).is_err());
+    let mut unknown_kind = bytes.clone();
+    unknown_kind[LAYER_INFO_BYTES] = 9;
+    assert_eq!(
+        layers_from_bytes(&unknown_kind),
+        Err("layer 1 is invalid".to_string())
+    );
+    let mut no_frames = bytes;
+    no_frames[4..8].fill(0);
+    assert!(layers_from_bytes(&no_frames).is_err());
+}
+
+#[test]
+fn frames_cycle_with_elapsed_time() {
+    let info = layer(LayerKind::Animated, 4, 180).info;
+    let frames: Vec<_> = [0, 179, 180, 540, 719, 720, 900]
+        .into_iter()
+        .map(|ms| info.frame_at(ms))
+        .collect();
+    assert_eq!(frames, [0, 0, 1, 3, 3, 0, 1]);
+    assert_eq!(layer(LayerKind::Animated, 4, 0).info.frame_at(500), 0);
+    assert_eq!(layer(LayerKind::Ground, 1, 0).info.frame_at(500), 0);
+    assert_eq!(info.pixels_len(2), 4 * 64 * 48 * 4);
+}
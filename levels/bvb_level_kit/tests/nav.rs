@@ -0,0 +1,105 @@
+use bvb_level_kit::{LaneCrossing, NavGraph, SemanticGrid, SemanticTile, Terrain};
+
+// Builds a grid from rows of `#` (blocked), `.` (walkable) and digits (walkable lane tiles).
+fn grid(rows: &[&str]) -> SemanticGrid {
+    let mut grid = SemanticGrid::new(
+        rows[0].len() as u32,
+        rows.len() as u32,
+        16,
+        SemanticTile::WALKABLE,
+    );
+    for (y, row) in rows.iter().enumerate() {
+        for (x, c) in row.chars().enumerate() {
+            let tile = grid.get_mut(x as u32, y as u32).unwrap();
+            match c {
+                '#' => tile.terrain = Terrain::Blocked,
+                '1'..='9' => tile.lane = c as u8 - b'0',
+                _ => {}
+            }
+        }
+    }
+    grid
+}
+
+#[test]
+fn paths_go_around_walls_without_cutting_corners() {
+    let graph = NavGraph::new(&grid(&[
+        "....", //
+        ".##.", //
+        "...#", //
+    ]));
+    let path = graph.find_path((0, 2), (3, 1)).expect("path");
+    // Diagonals past the wall's corners, such as (0, 1) to (1, 0), are not allowed.
+    assert_eq!(
+        path,
+        [(0, 2), (0, 1), (0, 0), (1, 0), (2, 0), (3, 0), (3, 1)]
+    );
+    assert!(!graph.neighbours((2, 2)).any(|(tile, _)| tile == (3, 1)));
+    assert_eq!(graph.find_path((0, 0), (1, 1)), None);
+}
+
+#[test]
+fn flow_field_steers_toward_the_nearest_goal() {
+    let graph = NavGraph::new(&grid(&[
+        ".....", //
+        ".###.", //
+        ".#...", //
+    ]));
+    let field = graph.flow_field(&[(2, 2)]);
+    assert_eq!(field.distance((2, 2)), Some(0));
+    assert_eq!(field.distance((1, 1)), None);
+    assert_eq!(field.next_step((2, 2)), None);
+
+    let mut tile = (0, 2);
+    let mut visited = vec![tile];
+    while let Some(next) = field.next_step(tile) {
+        tile = next;
+        visited.push(tile);
+    }
+    assert_eq!(tile, (2, 2));
+    // Around the top of the wall, since the way under it is sealed.
+    assert_eq!(visited.len(), 11);
+}
+
+#[test]
+fn regions_find_sealed_off_pockets() {
+    let regions = NavGraph::new(&grid(&[
+        "..#..", //
+        "..#..", //
+        "###..", //
+    ]))
+    .regions();
+    assert_eq!(regions.count(), 2);
+    assert!(regions.connected(&[(0, 0), (1, 1)]));
+    assert!(!regions.connected(&[(0, 0), (4, 2)]));
+    assert_eq!(regions.label((2, 0)), None);
+}
+
+#[test]
+fn lane_crossings_group_rows_through_openings() {
+    let graph = NavGraph::new(&grid(&[
+        "1..2..3", //
+        "1#.2#.3", //
+        "1..2#.3", //
+        "1..2#.3", //
+        "1#.2#.3", //
+    ]));
+    assert_eq!(
+        graph.lane_crossings(1, 2),
+        [
+            LaneCrossing {
+                lanes: (1, 2),
+                top: 0,
+                bottom: 0
+            },
+            LaneCrossing {
+                lanes: (1, 2),
+                top: 2,
+                bottom: 3
+            },
+        ]
+    );
+    assert_eq!(graph.lane_crossings(2, 3).len(), 1);
+    // Lane 2 sits between them, so lanes 1 and 3 never meet directly.
+    assert!(graph.lane_crossings(1, 3).is_empty());
+}
@@ -0,0 +1,37 @@
+use bvb_level_kit::{ExitSide, LevelParams, LevelRng};
+
+#[test]
+fn params_round_trip_and_fill_defaults() {
+    let params = LevelParams {
+        lane_width: 28,
+        crossovers: 4,
+        bay_density: 0,
+        exit_side: ExitSide::Right,
+    };
+    assert_eq!(LevelParams::from_bytes(&params.to_bytes()), Ok(params));
+
+    // Missing fields keep their defaults and trailing bytes are ignored.
+    assert_eq!(LevelParams::from_bytes(&[]), Ok(LevelParams::default()));
+    let short = LevelParams::from_bytes(&[40]).unwrap();
+    assert_eq!(short.lane_width, 40);
+    assert_eq!(short.crossovers, LevelParams::default().crossovers);
+    assert_eq!(LevelParams::from_bytes(&[28, 4, 0, 1, 9, 9]), Ok(params));
+
+    assert!(LevelParams::from_bytes(&[36, 2, 2, 5]).is_err());
+}
+
+#[test]
+fn rng_is_deterministic_per_seed() {
+    let draw = |seed| {
+        let mut rng = LevelRng::new(seed);
+        (0..16).map(|_| rng.range(-12, 12)).collect::<Vec<_>>()
+    };
+    assert_eq!(draw(7), draw(7));
+    assert_ne!(draw(7), draw(8));
+    assert!(draw(7).iter().all(|value| (-12..=12).contains(value)));
+    assert_eq!(LevelRng::new(1).range(5, 5), 5);
+
+    let mut rng = LevelRng::from_u64(7);
+    assert_eq!(rng.next_u64(), LevelRng::new(7).next_u64());
+    assert!((0..64).all(|_| (2.0..3.0).contains(&rng.range_f32(2.0, 3.0))));
+}
@@ -0,0 +1,42 @@
+use bvb_level_kit::{Hazard, Marker, SemanticGrid, SemanticTile, Terrain};
+
+#[test]
+fn tiles_round_trip_through_bytes() {
+    let tile = SemanticTile {
+        terrain: Terrain::Walkable,
+        lane: 2,
+        marker: Marker::FlagBase,
+        hazard: Hazard::Collapse,
+    };
+
+    assert_eq!(SemanticTile::from_bytes(tile.to_bytes()), Some(tile));
+    assert_eq!(SemanticTile::from_bytes([1, 0, 9, 0]), None);
+}
+
+#[test]
+fn marking_samples_tile_centres() {
+    let mut grid = SemanticGrid::new(4, 2, 16, SemanticTile::WALKABLE);
+    // Covers the centres (8, 8) and (24, 8) but not (40, 8).
+    grid.mark_rect(0, 0, 36, 10, |tile| tile.terrain = Terrain::Blocked);
+
+    let blocked: Vec<bool> = grid.tiles.iter().map(|tile| !tile.is_walkable()).collect();
+    assert_eq!(
+        blocked,
+        [true, true, false, false, false, false, false, false]
+    );
+}
+
+#[test]
+fn grid_decoding_checks_size_and_codes() {
+    let mut grid = SemanticGrid::new(3, 2, 16, SemanticTile::WALKABLE);
+    grid.get_mut(2, 1).expect("tile in range").marker = Marker::HumanExit;
+    let bytes = grid.to_bytes();
+
+    assert_eq!(SemanticGrid::from_bytes(3, 2, 16, &bytes), Ok(grid.clone()));
+    assert_eq!(grid.find(Marker::HumanExit), [(2, 1)]);
+    assert!(SemanticGrid::from_bytes(3, 3, 16, &bytes).is_err());
+
+    let mut corrupt = bytes;
+    corrupt[0] = 7;
+    assert!(SemanticGrid::from_bytes(3, 2, 16, &corrupt).is_err());
+}
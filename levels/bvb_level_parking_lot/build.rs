@@ -0,0 +1,29 @@
+// Keys, crops and shrinks the alien and demon art into `OUT_DIR`. Decoding the full-size PNGs
+// inside the module would cost hundreds of millions of instructions on the first `generate`, more
+// than the host's fuel limit allows.
+
+use std::path::Path;
+
+use bvb_raster::{Anchor, Filter, KeyOptions, decode_png, encode_png};
+
+// `SPAWN_SPRITE_H * MAX_SCALE` in lib.rs, the largest the level draws the art.
+const ART_H: u32 = 120;
+
+fn main() {
+    let out_dir = std::env::var("OUT_DIR").expect("cargo sets OUT_DIR");
+    for name in ["alien", "demon"] {
+        let source = format!("../../assets/characters/{name}.png");
+        println!("cargo::rerun-if-changed={source}");
+        let bytes = std::fs::read(&source).unwrap_or_else(|err| panic!("{source}: {err}"));
+        let mut sprite = decode_png(&bytes).unwrap_or_else(|err| panic!("{source}: {err}"));
+        sprite.chroma_key(&KeyOptions::default());
+        let sprite = sprite
+            .crop_to_alpha(12)
+            .unwrap_or_else(|| panic!("{source} is fully transparent once keyed"));
+        let width = ((sprite.width * ART_H) / sprite.height).max(1);
+        let art = sprite.resample(width, ART_H, Filter::Box, Anchor::Bottom);
+        let png = encode_png(art.width, art.height, &art.pixels);
+        std::fs::write(Path::new(&out_dir).join(format!("{name}.png")), png)
+            .unwrap_or_else(|err| panic!("writing the {name} art: {err}"));
+    }
+}
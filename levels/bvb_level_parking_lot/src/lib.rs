@@ -2,137 +2,108 @@
 //
 // It intentionally uses a simple C-style ABI so the main app can instantiate it with
 // `WebAssembly.instantiate` and read pixels from this module's linear memory.
-
-const WIDTH: u32 = 288; // 18 tiles * 16 px (9:16)
-const HEIGHT: u32 = 512; // 32 tiles * 16 px
-const CHANNELS: u32 = 4;
-
-static mut PIXELS_PTR: *mut u8 = core::ptr::null_mut();
-static mut PIXELS_LEN: usize = 0;
-
-const ALIEN_BMP: &[u8] = include_bytes!("../../../assets/characters/alien_256.bmp");
-const DEMON_BMP: &[u8] = include_bytes!("../../../assets/characters/demon_256.bmp");
-
-struct Sprite {
-    width: usize,
-    height: usize,
-    pixels: Vec<u8>, // RGBA
-}
-
-#[inline]
-fn rgba(r: u8, g: u8, b: u8) -> [u8; 4] {
-    [r, g, b, 255]
-}
-
-#[inline]
-fn put(pixels: &mut [u8], x: i32, y: i32, c: [u8; 4]) {
-    if x < 0 || y < 0 {
-        return;
-    }
-    let x = x as u32;
-    let y = y as u32;
-    if x >= WIDTH || y >= HEIGHT {
-        return;
-    }
-    let idx = ((y * WIDTH + x) * CHANNELS) as usize;
-    pixels[idx..idx + 4].copy_from_slice(&c);
-}
-
-fn fill_rect(pixels: &mut [u8], x: i32, y: i32, w: i32, h: i32, c: [u8; 4]) {
-    for yy in y..(y + h) {
-        for xx in x..(x + w) {
-            put(pixels, xx, yy, c);
-        }
-    }
-}
-
-fn rect_outline(pixels: &mut [u8], x: i32, y: i32, w: i32, h: i32, c: [u8; 4]) {
-    for xx in x..(x + w) {
-        put(pixels, xx, y, c);
-        put(pixels, xx, y + h - 1, c);
-    }
-    for yy in y..(y + h) {
-        put(pixels, x, yy, c);
-        put(pixels, x + w - 1, yy, c);
-    }
-}
-
-fn fill_circle(pixels: &mut [u8], cx: i32, cy: i32, radius: i32, c: [u8; 4]) {
-    let r2 = radius * radius;
-    for y in (cy - radius)..=(cy + radius) {
-        let dy = y - cy;
-        let dy2 = dy * dy;
-        for x in (cx - radius)..=(cx + radius) {
-            let dx = x - cx;
-            if (dx * dx) + dy2 <= r2 {
-                put(pixels, x, y, c);
-            }
-        }
-    }
-}
-
-fn fill_ring(pixels: &mut [u8], cx: i32, cy: i32, outer: i32, inner: i32, c: [u8; 4]) {
-    let ro2 = outer * outer;
-    let ri2 = inner * inner;
-    for y in (cy - outer)..=(cy + outer) {
-        let dy = y - cy;
-        let dy2 = dy * dy;
-        for x in (cx - outer)..=(cx + outer) {
-            let dx = x - cx;
-            let d2 = (dx * dx) + dy2;
-            if d2 <= ro2 && d2 >= ri2 {
-                put(pixels, x, y, c);
-            }
-        }
-    }
-}
-
-fn draw_left_arrow(pixels: &mut [u8], x: i32, y: i32, c: [u8; 4]) {
-    fill_rect(pixels, x + 2, y + 2, 8, 2, c);
-    put(pixels, x + 1, y + 1, c);
-    put(pixels, x + 1, y + 2, c);
-    put(pixels, x, y + 2, c);
-    put(pixels, x + 1, y + 3, c);
-}
-
-fn draw_out_text(pixels: &mut [u8], x: i32, y: i32, c: [u8; 4]) {
+// See `docs/levels.md` for the ABI contract.
+
+use std::sync::{Mutex, MutexGuard, OnceLock, PoisonError};
+
+use bvb_level_kit::{
+    ABI_VERSION, ExitSide, LayerInfo, LayerKind, LevelLayer, LevelParams, LevelRng, Marker,
+    SemanticGrid, SemanticTile, Terrain, content_hash, layers_to_bytes,
+};
+use bvb_raster::{Anchor, Canvas, Filter, Pivot, Placement, Shadow, Transform, decode_png, rgba};
+
+/// What `bvb_level_name` and `bvb_level_author` export, for tools that run the generator
+/// in-process.
+pub const LEVEL_NAME: &str = "Mall Parking Lot";
+pub const LEVEL_AUTHOR: &str = "Beyond vs Below";
+
+const TILE_SIZE: u32 = 16;
+const GRID_WIDTH: u32 = 18;
+const GRID_HEIGHT: u32 = 32;
+const WIDTH: u32 = GRID_WIDTH * TILE_SIZE; // 288 px (9:16)
+const HEIGHT: u32 = GRID_HEIGHT * TILE_SIZE; // 512 px
+
+// Lanes narrower than this stop reading as lanes; wider ones squeeze the median islands shut.
+const MIN_LANE_W: i32 = 24;
+const MAX_LANE_W: i32 = 44;
+const MAX_STAGGERED_OPENINGS: u8 = 4;
+const STAGGER_JITTER: i32 = 12;
+// 4x keeps the 512 px edge within the host's 2048 px texture limit.
+const MAX_SCALE: u32 = 4;
+// Spawn bases animate in their own square layer, wide enough for the outer ring.
+const BASE_LAYER_SIZE: u32 = 64;
+const BASE_FRAMES: u32 = 4;
+const BASE_FRAME_MS: u32 = 180;
+// Alien base inner ring growth per frame; frame 0 is the still art.
+const ALIEN_PULSE: [i32; BASE_FRAMES as usize] = [0, 1, 2, 1];
+// Spawn sprites stand on their base's centre, their feet this far below it, in level pixels.
+const SPAWN_SPRITE_H: u32 = 30;
+const SPAWN_SPRITE_FEET: i32 = 6;
+
+// The current level. Exports hand out pointers into its buffers, which stay valid until the next
+// `bvb_level_init_with`, `bvb_level_render` or `bvb_level_release`.
+static LEVEL: Mutex<Option<Level>> = Mutex::new(None);
+
+// Keyed and cropped character art at the largest size a level draws it, prepared by build.rs.
+const ALIEN_PNG: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/alien.png"));
+const DEMON_PNG: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/demon.png"));
+// The decoded art, once per module, so only a small resample runs for each level.
+static SPAWN_ART: OnceLock<[Option<Canvas>; 2]> = OnceLock::new();
+
+fn draw_left_arrow(canvas: &mut Canvas, x: i32, y: i32, c: [u8; 4]) {
+    canvas.fill_rect(x + 2, y + 2, 8, 2, c);
+    canvas.put(x + 1, y + 1, c);
+    canvas.put(x + 1, y + 2, c);
+    canvas.put(x, y + 2, c);
+    canvas.put(x + 1, y + 3, c);
+}
+
+fn draw_right_arrow(canvas: &mut Canvas, x: i32, y: i32, c: [u8; 4]) {
+    canvas.fill_rect(x, y + 2, 8, 2, c);
+    canvas.put(x + 8, y + 1, c);
+    canvas.put(x + 8, y + 2, c);
+    canvas.put(x + 9, y + 2, c);
+    canvas.put(x + 8, y + 3, c);
+}
+
+fn draw_out_text(canvas: &mut Canvas, x: i32, y: i32, c: [u8; 4]) {
     // O
-    rect_outline(pixels, x, y, 5, 7, c);
+    canvas.rect_outline(x, y, 5, 7, c);
     // U
-    fill_rect(pixels, x + 7, y, 1, 6, c);
-    fill_rect(pixels, x + 11, y, 1, 6, c);
-    fill_rect(pixels, x + 7, y + 6, 5, 1, c);
+    canvas.fill_rect(x + 7, y, 1, 6, c);
+    canvas.fill_rect(x + 11, y, 1, 6, c);
+    canvas.fill_rect(x + 7, y + 6, 5, 1, c);
     // T
-    fill_rect(pixels, x + 14, y, 5, 1, c);
-    fill_rect(pixels, x + 16, y, 1, 7, c);
+    canvas.fill_rect(x + 14, y, 5, 1, c);
+    canvas.fill_rect(x + 16, y, 1, 7, c);
 }
 
-fn draw_mall_mark(pixels: &mut [u8], x: i32, y: i32, c: [u8; 4]) {
+fn draw_mall_mark(canvas: &mut Canvas, x: i32, y: i32, c: [u8; 4]) {
     // Tiny block "M" to label mall spawn.
-    fill_rect(pixels, x, y, 1, 7, c);
-    fill_rect(pixels, x + 6, y, 1, 7, c);
-    fill_rect(pixels, x + 1, y + 1, 1, 2, c);
-    fill_rect(pixels, x + 2, y + 2, 1, 2, c);
-    fill_rect(pixels, x + 4, y + 2, 1, 2, c);
-    fill_rect(pixels, x + 5, y + 1, 1, 2, c);
+    canvas.fill_rect(x, y, 1, 7, c);
+    canvas.fill_rect(x + 6, y, 1, 7, c);
+    canvas.fill_rect(x + 1, y + 1, 1, 2, c);
+    canvas.fill_rect(x + 2, y + 2, 1, 2, c);
+    canvas.fill_rect(x + 4, y + 2, 1, 2, c);
+    canvas.fill_rect(x + 5, y + 1, 1, 2, c);
 }
 
 fn draw_tree(
-    pixels: &mut [u8],
+    canvas: &mut Canvas,
     cx: i32,
     cy: i32,
     trunk: [u8; 4],
     canopy_dark: [u8; 4],
     canopy_light: [u8; 4],
 ) {
-    fill_rect(pixels, cx - 1, cy + 2, 2, 3, trunk);
-    fill_circle(pixels, cx, cy, 4, canopy_dark);
-    fill_circle(pixels, cx - 2, cy + 1, 2, canopy_light);
-    fill_circle(pixels, cx + 2, cy + 1, 2, canopy_light);
+    canvas.fill_rect(cx - 1, cy + 2, 2, 3, trunk);
+    canvas.fill_circle(cx, cy, 4, canopy_dark);
+    canvas.fill_circle(cx - 2, cy + 1, 2, canopy_light);
+    canvas.fill_circle(cx + 2, cy + 1, 2, canopy_light);
 }
 
 fn draw_alien_sigil(
-    pixels: &mut [u8],
+    canvas: &mut Canvas,
     cx: i32,
     cy: i32,
     outline: [u8; 4],
@@ -140,25 +111,101 @@ fn draw_alien_sigil(
     eye: [u8; 4],
 ) {
     // Smaller alien-head glyph sized for compact spawn pads.
-    fill_circle(pixels, cx, cy, 9, outline);
-    fill_circle(pixels, cx, cy - 1, 6, fill);
-    fill_rect(pixels, cx - 6, cy + 4, 12, 2, outline);
-    fill_circle(pixels, cx - 3, cy - 1, 1, eye);
-    fill_circle(pixels, cx + 3, cy - 1, 1, eye);
-    fill_rect(pixels, cx - 1, cy + 1, 2, 1, eye);
+    canvas.fill_circle(cx, cy, 9, outline);
+    canvas.fill_circle(cx, cy - 1, 6, fill);
+    canvas.fill_rect(cx - 6, cy + 4, 12, 2, outline);
+    canvas.fill_circle(cx - 3, cy - 1, 1, eye);
+    canvas.fill_circle(cx + 3, cy - 1, 1, eye);
+    canvas.fill_rect(cx - 1, cy + 1, 2, 1, eye);
 }
 
-fn draw_burrow_mouth(pixels: &mut [u8], cx: i32, cy: i32) {
-    fill_rect(pixels, cx - 13, cy + 5, 26, 6, rgba(20, 4, 8));
+// `chomp` swaps the long and short teeth.
+fn draw_burrow_mouth(canvas: &mut Canvas, cx: i32, cy: i32, chomp: bool) {
+    canvas.fill_rect(cx - 13, cy + 5, 26, 6, rgba(20, 4, 8));
     for i in 0..5 {
         let x = cx - 11 + (i * 5);
-        let tooth_h = if i % 2 == 0 { 5 } else { 3 };
-        fill_rect(pixels, x, cy + 6, 2, tooth_h, rgba(238, 210, 186));
+        let tooth_h = if (i % 2 == 0) != chomp { 5 } else { 3 };
+        canvas.fill_rect(x, cy + 6, 2, tooth_h, rgba(238, 210, 186));
+    }
+}
+
+fn draw_alien_base(canvas: &mut Canvas, cx: i32, cy: i32, outer_r: i32, pulse: i32) {
+    canvas.fill_circle(cx, cy, outer_r, rgba(58, 226, 248));
+    canvas.fill_circle(cx, cy, outer_r - 8, rgba(26, 142, 196));
+    canvas.fill_ring(cx, cy, outer_r, outer_r - 4, rgba(222, 230, 238));
+    canvas.fill_ring(cx, cy, 14 + pulse, 10 + pulse, rgba(88, 228, 255));
+    canvas.fill_circle(cx, cy, 10, rgba(214, 250, 255));
+    draw_alien_sigil(
+        canvas,
+        cx,
+        cy,
+        rgba(20, 34, 54),
+        rgba(112, 240, 255),
+        rgba(8, 16, 30),
+    );
+}
+
+// Odd frames brighten the core; the second half of the cycle chomps.
+fn draw_demon_base(canvas: &mut Canvas, cx: i32, cy: i32, outer_r: i32, frame: u32) {
+    let core = if frame.is_multiple_of(2) {
+        rgba(210, 74, 40)
+    } else {
+        rgba(240, 112, 56)
+    };
+    canvas.fill_circle(cx, cy, outer_r, rgba(128, 36, 42));
+    canvas.fill_circle(cx, cy, outer_r - 8, rgba(62, 14, 18));
+    canvas.fill_ring(cx, cy, outer_r, outer_r - 4, rgba(226, 120, 68));
+    canvas.fill_circle(cx, cy, 10, core);
+    draw_burrow_mouth(canvas, cx, cy, frame >= BASE_FRAMES / 2);
+}
+
+// A square animated layer centred on `(cx, cy)`, drawing each frame centred in its own slot.
+fn base_layer(
+    cx: i32,
+    cy: i32,
+    scale: u32,
+    draw: impl Fn(&mut Canvas, i32, i32, u32),
+) -> LevelLayer {
+    let size = BASE_LAYER_SIZE as i32;
+    let mut canvas = Canvas::with_scale(BASE_LAYER_SIZE, BASE_LAYER_SIZE * BASE_FRAMES, scale);
+    for frame in 0..BASE_FRAMES {
+        draw(&mut canvas, size / 2, size / 2 + frame as i32 * size, frame);
+    }
+    LevelLayer {
+        info: LayerInfo {
+            kind: LayerKind::Animated,
+            frames: BASE_FRAMES,
+            frame_ms: BASE_FRAME_MS,
+            x: (cx - size / 2) as u32,
+            y: (cy - size / 2) as u32,
+            width: BASE_LAYER_SIZE,
+            height: BASE_LAYER_SIZE,
+        },
+        pixels: canvas.pixels,
+    }
+}
+
+/// Flattens `layers` into one image: the ground with every other layer's first frame on top, as
+/// hosts without layer support show the level.
+pub fn flatten_layers(layers: &[LevelLayer], scale: u32) -> Vec<u8> {
+    let mut canvas = Canvas::with_scale(WIDTH, HEIGHT, scale);
+    for layer in layers {
+        let info = layer.info;
+        let frame_len = info.pixels_len(scale) / info.frames as usize;
+        let Ok(frame) = Canvas::from_rgba(
+            info.width * scale,
+            info.height * scale,
+            layer.pixels[..frame_len].to_vec(),
+        ) else {
+            continue;
+        };
+        canvas.blit(&frame, info.x as i32, info.y as i32, Transform::IDENTITY);
     }
+    canvas.pixels
 }
 
 fn carve_opening(
-    pixels: &mut [u8],
+    canvas: &mut Canvas,
     x: i32,
     y: i32,
     w: i32,
@@ -166,342 +213,46 @@ fn carve_opening(
     lane: [u8; 4],
     edge: [u8; 4],
 ) {
-    fill_rect(pixels, x, y, w, h, lane);
-    rect_outline(pixels, x, y, w, h, edge);
+    canvas.fill_rect(x, y, w, h, lane);
+    canvas.rect_outline(x, y, w, h, edge);
     let stripe_y = y + (h / 2) - 1;
-    fill_rect(pixels, x + 2, stripe_y, w - 4, 2, edge);
-}
-
-#[inline]
-fn color_dist_sq(r: u8, g: u8, b: u8, bg: [u8; 3]) -> u32 {
-    let dr = (r as i32) - (bg[0] as i32);
-    let dg = (g as i32) - (bg[1] as i32);
-    let db = (b as i32) - (bg[2] as i32);
-    ((dr * dr) + (dg * dg) + (db * db)) as u32
-}
-
-#[inline]
-fn read_le_u16(bytes: &[u8], at: usize) -> Option<u16> {
-    let end = at.checked_add(2)?;
-    let b = bytes.get(at..end)?;
-    Some(u16::from_le_bytes([b[0], b[1]]))
-}
-
-#[inline]
-fn read_le_u32(bytes: &[u8], at: usize) -> Option<u32> {
-    let end = at.checked_add(4)?;
-    let b = bytes.get(at..end)?;
-    Some(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
-}
-
-#[inline]
-fn read_le_i32(bytes: &[u8], at: usize) -> Option<i32> {
-    let end = at.checked_add(4)?;
-    let b = bytes.get(at..end)?;
-    Some(i32::from_le_bytes([b[0], b[1], b[2], b[3]]))
-}
-
-#[inline]
-fn bitfield_shift(mask: u32) -> u32 {
-    mask.trailing_zeros()
-}
-
-#[inline]
-fn bitfield_max(mask: u32) -> u32 {
-    let shifted = mask >> bitfield_shift(mask);
-    shifted.max(1)
-}
-
-#[inline]
-fn extract_channel(v: u32, mask: u32) -> u8 {
-    if mask == 0 {
-        return 255;
-    }
-    let shifted = (v & mask) >> bitfield_shift(mask);
-    ((shifted * 255) / bitfield_max(mask)) as u8
-}
-
-fn decode_bmp_to_rgba(bytes: &[u8]) -> Option<Sprite> {
-    if bytes.get(0..2)? != b"BM" {
-        return None;
-    }
-    let data_offset = read_le_u32(bytes, 10)? as usize;
-    let dib_size = read_le_u32(bytes, 14)? as usize;
-    if dib_size < 40 {
-        return None;
-    }
-
-    let width = read_le_i32(bytes, 18)?;
-    let height_raw = read_le_i32(bytes, 22)?;
-    let planes = read_le_u16(bytes, 26)?;
-    let bpp = read_le_u16(bytes, 28)?;
-    let compression = read_le_u32(bytes, 30)?;
-    if planes != 1 {
-        return None;
-    }
-
-    let w = width.unsigned_abs() as usize;
-    let h = height_raw.unsigned_abs() as usize;
-    if w == 0 || h == 0 {
-        return None;
-    }
-    let top_down = height_raw < 0;
-    let mut out = vec![0u8; w * h * 4];
-
-    match bpp {
-        24 => {
-            if compression != 0 {
-                return None;
-            }
-            let row_stride = (w * 3).div_ceil(4) * 4;
-            let payload_len = row_stride.checked_mul(h)?;
-            let payload = bytes.get(data_offset..data_offset + payload_len)?;
-
-            for y in 0..h {
-                let src_y = if top_down { y } else { h - 1 - y };
-                let row = &payload[(src_y * row_stride)..((src_y + 1) * row_stride)];
-                for x in 0..w {
-                    let si = x * 3;
-                    let di = (y * w + x) * 4;
-                    out[di] = row[si + 2];
-                    out[di + 1] = row[si + 1];
-                    out[di + 2] = row[si];
-                    out[di + 3] = 255;
-                }
-            }
-        }
-        32 => {
-            let row_stride = w * 4;
-            let payload_len = row_stride.checked_mul(h)?;
-            let payload = bytes.get(data_offset..data_offset + payload_len)?;
-
-            let (rmask, gmask, bmask, amask) = if compression == 3 && dib_size >= 56 {
-                (
-                    read_le_u32(bytes, 54)?,
-                    read_le_u32(bytes, 58)?,
-                    read_le_u32(bytes, 62)?,
-                    read_le_u32(bytes, 66)?,
-                )
-            } else {
-                (0x00FF0000, 0x0000FF00, 0x000000FF, 0xFF000000)
-            };
-
-            for y in 0..h {
-                let src_y = if top_down { y } else { h - 1 - y };
-                let row = &payload[(src_y * row_stride)..((src_y + 1) * row_stride)];
-                for x in 0..w {
-                    let si = x * 4;
-                    let di = (y * w + x) * 4;
-                    let px = u32::from_le_bytes([row[si], row[si + 1], row[si + 2], row[si + 3]]);
-                    out[di] = extract_channel(px, rmask);
-                    out[di + 1] = extract_channel(px, gmask);
-                    out[di + 2] = extract_channel(px, bmask);
-                    out[di + 3] = extract_channel(px, amask);
-                }
-            }
-        }
-        _ => return None,
-    }
-
-    Some(Sprite {
-        width: w,
-        height: h,
-        pixels: out,
-    })
-}
-
-fn key_background_transparent(sprite: &mut Sprite) {
-    let w = sprite.width;
-    let h = sprite.height;
-    if w == 0 || h == 0 {
-        return;
-    }
-
-    let mut border_samples: Vec<[u8; 3]> = Vec::new();
-    let step_x = (w / 12).max(1);
-    let step_y = (h / 12).max(1);
-
-    for x in (0..w).step_by(step_x) {
-        let top_i = (x * 4) as usize;
-        let bot_i = (((h - 1) * w + x) * 4) as usize;
-        border_samples.push([
-            sprite.pixels[top_i],
-            sprite.pixels[top_i + 1],
-            sprite.pixels[top_i + 2],
-        ]);
-        border_samples.push([
-            sprite.pixels[bot_i],
-            sprite.pixels[bot_i + 1],
-            sprite.pixels[bot_i + 2],
-        ]);
-    }
-    for y in (0..h).step_by(step_y) {
-        let left_i = ((y * w) * 4) as usize;
-        let right_i = ((y * w + (w - 1)) * 4) as usize;
-        border_samples.push([
-            sprite.pixels[left_i],
-            sprite.pixels[left_i + 1],
-            sprite.pixels[left_i + 2],
-        ]);
-        border_samples.push([
-            sprite.pixels[right_i],
-            sprite.pixels[right_i + 1],
-            sprite.pixels[right_i + 2],
-        ]);
-    }
-
-    if border_samples.is_empty() {
-        return;
-    }
-
-    let mut sum = [0u32; 3];
-    for s in &border_samples {
-        sum[0] += s[0] as u32;
-        sum[1] += s[1] as u32;
-        sum[2] += s[2] as u32;
-    }
-    let count = border_samples.len() as u32;
-    let bg = [
-        (sum[0] / count) as u8,
-        (sum[1] / count) as u8,
-        (sum[2] / count) as u8,
-    ];
-
-    let mut spread = 0u32;
-    for s in &border_samples {
-        spread = spread.max(color_dist_sq(s[0], s[1], s[2], bg));
-    }
-    let threshold = (spread + 900).clamp(900, 6400);
-
-    for px in sprite.pixels.chunks_exact_mut(4) {
-        if px[3] < 8 {
-            continue;
-        }
-        let d2 = color_dist_sq(px[0], px[1], px[2], bg);
-        if d2 <= threshold {
-            px[3] = 0;
-        }
-    }
-}
-
-fn crop_to_alpha(sprite: Sprite) -> Option<Sprite> {
-    let w = sprite.width;
-    let h = sprite.height;
-    let p = &sprite.pixels;
-    let mut min_x = w;
-    let mut min_y = h;
-    let mut max_x = 0usize;
-    let mut max_y = 0usize;
-    let mut found = false;
-
-    for y in 0..h {
-        for x in 0..w {
-            let i = (y * w + x) * 4;
-            if p[i + 3] > 12 {
-                found = true;
-                min_x = min_x.min(x);
-                min_y = min_y.min(y);
-                max_x = max_x.max(x);
-                max_y = max_y.max(y);
-            }
-        }
-    }
-
-    if !found {
-        return None;
-    }
-
-    let nw = max_x - min_x + 1;
-    let nh = max_y - min_y + 1;
-    let mut out = vec![0u8; nw * nh * 4];
-    for y in 0..nh {
-        for x in 0..nw {
-            let si = ((min_y + y) * w + (min_x + x)) * 4;
-            let di = (y * nw + x) * 4;
-            out[di..di + 4].copy_from_slice(&p[si..si + 4]);
-        }
-    }
-
-    Some(Sprite {
-        width: nw,
-        height: nh,
-        pixels: out,
-    })
+    canvas.fill_rect(x + 2, stripe_y, w - 4, 2, edge);
 }
 
-fn resize_nearest(sprite: &Sprite, target_h: usize) -> Sprite {
+fn fit_height(sprite: &Canvas, target_h: u32) -> Canvas {
     let target_h = target_h.max(1);
     let target_w = ((sprite.width * target_h) / sprite.height).max(1);
-    let mut out = vec![0u8; target_w * target_h * 4];
-
-    for y in 0..target_h {
-        let sy = (y * sprite.height) / target_h;
-        for x in 0..target_w {
-            let sx = (x * sprite.width) / target_w;
-            let si = (sy * sprite.width + sx) * 4;
-            let di = (y * target_w + x) * 4;
-            out[di..di + 4].copy_from_slice(&sprite.pixels[si..si + 4]);
-        }
-    }
-
-    Sprite {
-        width: target_w,
-        height: target_h,
-        pixels: out,
-    }
-}
-
-fn load_spawn_sprite(bytes: &[u8], target_h: usize) -> Option<Sprite> {
-    let mut sprite = decode_bmp_to_rgba(bytes)?;
-    key_background_transparent(&mut sprite);
-    let cropped = crop_to_alpha(sprite)?;
-    Some(resize_nearest(&cropped, target_h))
-}
-
-fn blend_pixel(dst: &mut [u8], src: &[u8]) {
-    let sa = src[3] as u32;
-    if sa == 0 {
-        return;
-    }
-    let inv = 255u32 - sa;
-    dst[0] = (((src[0] as u32 * sa) + (dst[0] as u32 * inv)) / 255) as u8;
-    dst[1] = (((src[1] as u32 * sa) + (dst[1] as u32 * inv)) / 255) as u8;
-    dst[2] = (((src[2] as u32 * sa) + (dst[2] as u32 * inv)) / 255) as u8;
-    dst[3] = 255;
-}
-
-fn blit_sprite_center_bottom(
-    pixels: &mut [u8],
-    sprite: &Sprite,
-    center_x: i32,
-    bottom_y: i32,
-    x_offset: i32,
-) {
-    let start_x = center_x - (sprite.width as i32 / 2) + x_offset;
-    let start_y = bottom_y - sprite.height as i32;
-
-    for sy in 0..(sprite.height as i32) {
-        let dy = start_y + sy;
-        if !(0..(HEIGHT as i32)).contains(&dy) {
-            continue;
-        }
-        for sx in 0..(sprite.width as i32) {
-            let dx = start_x + sx;
-            if !(0..(WIDTH as i32)).contains(&dx) {
-                continue;
-            }
-            let si = ((sy as usize * sprite.width + sx as usize) * 4) as usize;
-            if sprite.pixels[si + 3] == 0 {
-                continue;
-            }
-            let di = (((dy as u32 * WIDTH + dx as u32) * CHANNELS) as usize) as usize;
-            blend_pixel(&mut pixels[di..di + 4], &sprite.pixels[si..si + 4]);
-        }
-    }
-}
-
-fn generate() -> Vec<u8> {
-    let mut pixels = vec![0u8; (WIDTH * HEIGHT * CHANNELS) as usize];
+    sprite.resample(target_w, target_h, Filter::Box, Anchor::Bottom)
+}
+
+// The alien and demon, `target_h` image pixels tall: pass the level height times the canvas
+// scale to keep detail. `None` where the art fails to decode.
+fn spawn_sprites(target_h: u32) -> [Option<Canvas>; 2] {
+    let art = SPAWN_ART.get_or_init(|| [decode_png(ALIEN_PNG).ok(), decode_png(DEMON_PNG).ok()]);
+    art.each_ref()
+        .map(|art| art.as_ref().map(|art| fit_height(art, target_h)))
+}
+
+/// Returns the RGBA pixels plus the semantic tile grid, marked from the same geometry as the art.
+/// Seed 0 with default params is the canonical layout; any other input is a deterministic variant.
+/// The grid is in level pixels whatever the scale.
+pub fn generate(seed: u32, params: &LevelParams, scale: u32) -> (Vec<u8>, SemanticGrid) {
+    let (layers, grid) = generate_layers(seed, params, scale);
+    (flatten_layers(&layers, scale), grid)
+}
+
+/// The level split into layers: the ground, the animated alien and demon bases, then the island
+/// trees as a foreground units walk behind. `generate` is these flattened.
+pub fn generate_layers(
+    seed: u32,
+    params: &LevelParams,
+    scale: u32,
+) -> (Vec<LevelLayer>, SemanticGrid) {
+    let mut rng = LevelRng::new(seed);
+    let mut canvas = Canvas::with_scale(WIDTH, HEIGHT, scale);
+    let mut grid = SemanticGrid::new(GRID_WIDTH, GRID_HEIGHT, TILE_SIZE, SemanticTile::WALKABLE);
+    let block = |tile: &mut SemanticTile| tile.terrain = Terrain::Blocked;
+    let open = |tile: &mut SemanticTile| tile.terrain = Terrain::Walkable;
 
     // Deliberately restrained palette for strong objective readability.
     let asphalt_a = rgba(14, 22, 30);
@@ -519,14 +270,6 @@ fn generate() -> Vec<u8> {
     let island_trunk = rgba(102, 76, 48);
     let island_edge = rgba(196, 206, 154);
 
-    let alien_outer = rgba(58, 226, 248);
-    let alien_inner = rgba(26, 142, 196);
-    let alien_core = rgba(214, 250, 255);
-
-    let demon_outer = rgba(128, 36, 42);
-    let demon_inner = rgba(62, 14, 18);
-    let demon_core = rgba(210, 74, 40);
-
     let exit_road = rgba(38, 66, 58);
     let exit_glow = rgba(126, 216, 170);
 
@@ -538,22 +281,26 @@ fn generate() -> Vec<u8> {
     // Base asphalt with very light dithering.
     for y in 0..HEIGHT as i32 {
         for x in 0..WIDTH as i32 {
-            let pick = ((x * 3 + y * 5) & 7) as i32;
+            let pick = (x * 3 + y * 5) & 7;
             let c = if pick < 4 { asphalt_a } else { asphalt_b };
-            put(&mut pixels, x, y, c);
+            canvas.put(x, y, c);
         }
     }
 
     // Parking lot boundary curb.
-    fill_rect(&mut pixels, 0, 0, WIDTH as i32, 8, curb);
-    fill_rect(&mut pixels, 0, (HEIGHT as i32) - 8, WIDTH as i32, 8, curb);
-    fill_rect(&mut pixels, 0, 0, 8, HEIGHT as i32, curb);
-    fill_rect(&mut pixels, (WIDTH as i32) - 8, 0, 8, HEIGHT as i32, curb);
+    canvas.fill_rect(0, 0, WIDTH as i32, 8, curb);
+    canvas.fill_rect(0, (HEIGHT as i32) - 8, WIDTH as i32, 8, curb);
+    canvas.fill_rect(0, 0, 8, HEIGHT as i32, curb);
+    canvas.fill_rect((WIDTH as i32) - 8, 0, 8, HEIGHT as i32, curb);
+    // The curb is half a tile thick, so the whole outer ring of tiles is the boundary.
+    let ring = TILE_SIZE as i32;
+    grid.mark_rect(0, 0, WIDTH as i32, ring, block);
+    grid.mark_rect(0, (HEIGHT as i32) - ring, WIDTH as i32, ring, block);
+    grid.mark_rect(0, 0, ring, HEIGHT as i32, block);
+    grid.mark_rect((WIDTH as i32) - ring, 0, ring, HEIGHT as i32, block);
 
     // Symmetric compact base sizes for alien and demon spawn points.
     let base_outer_r = 30;
-    let base_inner_r = 22;
-    let base_core_r = 10;
 
     let alien_cx = (WIDTH as i32) / 2;
     let alien_cy = 66;
@@ -561,25 +308,10 @@ fn generate() -> Vec<u8> {
     let demon_cy = (HEIGHT as i32) - 66;
 
     // Top and bottom spawn platforms.
-    fill_rect(&mut pixels, 44, 34, (WIDTH as i32) - 88, 62, lane_tint);
-    rect_outline(
-        &mut pixels,
-        44,
-        34,
-        (WIDTH as i32) - 88,
-        62,
-        rgba(120, 226, 248),
-    );
-    fill_rect(
-        &mut pixels,
-        44,
-        (HEIGHT as i32) - 96,
-        (WIDTH as i32) - 88,
-        62,
-        lane_tint,
-    );
-    rect_outline(
-        &mut pixels,
+    canvas.fill_rect(44, 34, (WIDTH as i32) - 88, 62, lane_tint);
+    canvas.rect_outline(44, 34, (WIDTH as i32) - 88, 62, rgba(120, 226, 248));
+    canvas.fill_rect(44, (HEIGHT as i32) - 96, (WIDTH as i32) - 88, 62, lane_tint);
+    canvas.rect_outline(
         44,
         (HEIGHT as i32) - 96,
         (WIDTH as i32) - 88,
@@ -589,38 +321,31 @@ fn generate() -> Vec<u8> {
 
     // Three MOBA-style lanes.
     let lane_centers = [86, (WIDTH as i32) / 2, (WIDTH as i32) - 86];
-    let lane_w = 36;
+    let lane_w = i32::from(params.lane_width).clamp(MIN_LANE_W, MAX_LANE_W);
     let lane_top = alien_cy + base_outer_r + 12;
     let lane_bottom = demon_cy - base_outer_r - 12;
 
     for lane_center in lane_centers {
         let x0 = lane_center - (lane_w / 2);
-        fill_rect(
-            &mut pixels,
-            x0,
-            lane_top,
-            lane_w,
-            lane_bottom - lane_top,
-            lane_tint,
-        );
-        rect_outline(
-            &mut pixels,
-            x0,
-            lane_top,
-            lane_w,
-            lane_bottom - lane_top,
-            line_yellow,
-        );
+        canvas.fill_rect(x0, lane_top, lane_w, lane_bottom - lane_top, lane_tint);
+        canvas.rect_outline(x0, lane_top, lane_w, lane_bottom - lane_top, line_yellow);
 
         let mut dash_y = lane_top + 12;
         while dash_y < lane_bottom - 12 {
-            fill_rect(&mut pixels, lane_center - 1, dash_y, 2, 8, line_white);
+            canvas.fill_rect(lane_center - 1, dash_y, 2, 8, line_white);
             dash_y += 20;
         }
 
         // Connectors into both spawn circles.
-        fill_rect(&mut pixels, x0 - 2, lane_top - 14, lane_w + 4, 14, lane_tint);
-        fill_rect(&mut pixels, x0 - 2, lane_bottom, lane_w + 4, 14, lane_tint);
+        canvas.fill_rect(x0 - 2, lane_top - 14, lane_w + 4, 14, lane_tint);
+        canvas.fill_rect(x0 - 2, lane_bottom, lane_w + 4, 14, lane_tint);
+    }
+    for (lane, lane_center) in (1u8..).zip(lane_centers) {
+        let x0 = lane_center - (lane_w / 2);
+        let y0 = lane_top - 14;
+        grid.mark_rect(x0, y0, lane_w, lane_bottom + 14 - y0, |tile| {
+            tile.lane = lane;
+        });
     }
 
     // Median islands between lanes, with staggered openings and a full middle crossover.
@@ -632,10 +357,9 @@ fn generate() -> Vec<u8> {
     let island_y = lane_top + 6;
     let island_h = lane_bottom - lane_top - 12;
 
-    fill_rect(&mut pixels, gap_lc_x, island_y, gap_w, island_h, island_fill);
-    rect_outline(&mut pixels, gap_lc_x, island_y, gap_w, island_h, island_edge);
-    fill_rect(
-        &mut pixels,
+    canvas.fill_rect(gap_lc_x, island_y, gap_w, island_h, island_fill);
+    canvas.rect_outline(gap_lc_x, island_y, gap_w, island_h, island_edge);
+    canvas.fill_rect(
         gap_lc_x + 2,
         island_y + 2,
         gap_w - 4,
@@ -643,10 +367,12 @@ fn generate() -> Vec<u8> {
         island_grass,
     );
 
-    fill_rect(&mut pixels, gap_cr_x, island_y, gap_w, island_h, island_fill);
-    rect_outline(&mut pixels, gap_cr_x, island_y, gap_w, island_h, island_edge);
-    fill_rect(
-        &mut pixels,
+    grid.mark_rect(gap_lc_x, island_y, gap_w, island_h, block);
+    grid.mark_rect(gap_cr_x, island_y, gap_w, island_h, block);
+
+    canvas.fill_rect(gap_cr_x, island_y, gap_w, island_h, island_fill);
+    canvas.rect_outline(gap_cr_x, island_y, gap_w, island_h, island_edge);
+    canvas.fill_rect(
         gap_cr_x + 2,
         island_y + 2,
         gap_w - 4,
@@ -655,33 +381,20 @@ fn generate() -> Vec<u8> {
     );
 
     for y in ((island_y + 8)..(island_y + island_h - 8)).step_by(14usize) {
-        fill_rect(&mut pixels, gap_lc_x + 3, y, gap_w - 6, 1, island_grass_dark);
-        fill_rect(&mut pixels, gap_cr_x + 3, y, gap_w - 6, 1, island_grass_dark);
-    }
-    for y in ((island_y + 24)..(island_y + island_h - 22)).step_by(56usize) {
-        draw_tree(
-            &mut pixels,
-            gap_lc_x + (gap_w / 2),
-            y,
-            island_trunk,
-            island_tree,
-            island_tree_highlight,
-        );
-        draw_tree(
-            &mut pixels,
-            gap_cr_x + (gap_w / 2),
-            y,
-            island_trunk,
-            island_tree,
-            island_tree_highlight,
-        );
+        canvas.fill_rect(gap_lc_x + 3, y, gap_w - 6, 1, island_grass_dark);
+        canvas.fill_rect(gap_cr_x + 3, y, gap_w - 6, 1, island_grass_dark);
     }
+    // Island x, top and height of every opening, so no tree stands in a crossing.
+    let mut openings = vec![
+        (gap_lc_x, ((lane_top + lane_bottom) / 2) - 12, 24),
+        (gap_cr_x, ((lane_top + lane_bottom) / 2) - 12, 24),
+    ];
 
     // Full middle opening: connects all three lanes.
     let mid_open_y = ((lane_top + lane_bottom) / 2) - 12;
     let mid_open_h = 24;
     carve_opening(
-        &mut pixels,
+        &mut canvas,
         gap_lc_x,
         mid_open_y,
         gap_w,
@@ -690,7 +403,7 @@ fn generate() -> Vec<u8> {
         line_white,
     );
     carve_opening(
-        &mut pixels,
+        &mut canvas,
         gap_cr_x,
         mid_open_y,
         gap_w,
@@ -698,239 +411,497 @@ fn generate() -> Vec<u8> {
         lane_tint,
         line_white,
     );
+    grid.mark_rect(gap_lc_x, mid_open_y, gap_w, mid_open_h, open);
+    grid.mark_rect(gap_cr_x, mid_open_y, gap_w, mid_open_h, open);
 
-    // Staggered extra openings for rotational depth.
-    let upper_open_y = lane_top + 58;
-    let lower_open_y = lane_bottom - 78;
+    // Staggered extra openings for rotational depth, alternating between the top and bottom halves
+    // and between the two islands. Seed 0 keeps them at their canonical positions.
     let stagger_h = 20;
-    carve_opening(
-        &mut pixels,
-        gap_lc_x,
-        upper_open_y,
-        gap_w,
-        stagger_h,
-        lane_tint,
-        line_white,
-    );
-    carve_opening(
-        &mut pixels,
-        gap_cr_x,
-        lower_open_y,
-        gap_w,
-        stagger_h,
-        lane_tint,
-        line_white,
-    );
+    for i in 0..i32::from(params.crossovers.min(MAX_STAGGERED_OPENINGS)) {
+        let row = i / 2;
+        let (base_y, island_x) = if i % 2 == 0 {
+            let island_x = if row % 2 == 0 { gap_lc_x } else { gap_cr_x };
+            (lane_top + 58 + row * 40, island_x)
+        } else {
+            let island_x = if row % 2 == 0 { gap_cr_x } else { gap_lc_x };
+            (lane_bottom - 78 - row * 40, island_x)
+        };
+        let jitter = if seed == 0 {
+            0
+        } else {
+            rng.range(-STAGGER_JITTER, STAGGER_JITTER)
+        };
+        let open_y = base_y + jitter;
+        carve_opening(
+            &mut canvas,
+            island_x,
+            open_y,
+            gap_w,
+            stagger_h,
+            lane_tint,
+            line_white,
+        );
+        grid.mark_rect(island_x, open_y, gap_w, stagger_h, open);
+        openings.push((island_x, open_y, stagger_h));
+    }
+
+    // Trees go in a foreground layer over both islands; a canopy spans 4 px above its centre to
+    // the trunk's foot.
+    let trees_w = gap_cr_x + gap_w - gap_lc_x;
+    let mut trees = Canvas::with_scale(trees_w as u32, island_h as u32, scale);
+    for y in ((island_y + 24)..(island_y + island_h - 22)).step_by(56usize) {
+        for island_x in [gap_lc_x, gap_cr_x] {
+            let in_opening = openings.iter().any(|&(open_x, open_y, open_h)| {
+                open_x == island_x && y - 4 < open_y + open_h && open_y < y + 5
+            });
+            if !in_opening {
+                draw_tree(
+                    &mut trees,
+                    island_x - gap_lc_x + (gap_w / 2),
+                    y - island_y,
+                    island_trunk,
+                    island_tree,
+                    island_tree_highlight,
+                );
+            }
+        }
+    }
+
+    // The flag sits in the centre lane, on the middle crossover.
+    if let Some(tile) = grid.at_pixel(lane_centers[1], (lane_top + lane_bottom) / 2) {
+        tile.marker = Marker::FlagBase;
+    }
 
     // Parking bays.
-    fill_rect(&mut pixels, 14, lane_top + 2, 38, lane_bottom - lane_top - 4, lot_surface);
-    fill_rect(
-        &mut pixels,
-        (WIDTH as i32) - 52,
-        lane_top + 2,
-        38,
-        lane_bottom - lane_top - 4,
-        lot_surface,
-    );
-    let mut mark_y = lane_top + 14;
-    while mark_y < lane_bottom - 10 {
-        fill_rect(&mut pixels, 16, mark_y, 34, 1, line_white);
-        fill_rect(&mut pixels, (WIDTH as i32) - 50, mark_y, 34, 1, line_white);
-        mark_y += 26;
+    let stall_step = match params.bay_density {
+        0 => None,
+        1 => Some(39),
+        2 => Some(26),
+        _ => Some(18),
+    };
+    if let Some(stall_step) = stall_step {
+        canvas.fill_rect(
+            14,
+            lane_top + 2,
+            38,
+            lane_bottom - lane_top - 4,
+            lot_surface,
+        );
+        canvas.fill_rect(
+            (WIDTH as i32) - 52,
+            lane_top + 2,
+            38,
+            lane_bottom - lane_top - 4,
+            lot_surface,
+        );
+        let mut mark_y = lane_top + 14;
+        while mark_y < lane_bottom - 10 {
+            canvas.fill_rect(16, mark_y, 34, 1, line_white);
+            canvas.fill_rect((WIDTH as i32) - 50, mark_y, 34, 1, line_white);
+            mark_y += stall_step;
+        }
     }
 
-    // Left-side parking lot exit (human win condition).
+    // Parking lot exit (human win condition), on the left unless mirrored to the right.
+    let mirror = params.exit_side == ExitSide::Right;
+    let side_x = |x: i32, w: i32| if mirror { (WIDTH as i32) - x - w } else { x };
     let exit_y = ((lane_top + lane_bottom) / 2) - 18;
     let exit_h = 36;
-    fill_rect(&mut pixels, 0, exit_y - 4, 8, exit_h + 8, asphalt_a); // curb break
-    fill_rect(&mut pixels, 8, exit_y, 60, exit_h, exit_road);
-    rect_outline(&mut pixels, 8, exit_y, 60, exit_h, exit_glow);
-    fill_rect(&mut pixels, 12, exit_y + (exit_h / 2) - 1, 46, 2, line_yellow);
-    fill_rect(&mut pixels, 55, exit_y + 6, 3, exit_h - 12, exit_glow);
-    draw_left_arrow(&mut pixels, 18, exit_y + 7, line_white);
-    draw_left_arrow(&mut pixels, 18, exit_y + 19, line_white);
-
-    // "OUT" sign so the left opening reads as a clear parking-lot exit.
-    fill_rect(&mut pixels, 12, exit_y - 22, 34, 14, exit_glow);
-    fill_rect(&mut pixels, 13, exit_y - 21, 32, 12, rgba(8, 20, 16));
-    draw_out_text(&mut pixels, 17, exit_y - 18, line_white);
-    fill_rect(&mut pixels, 28, exit_y - 8, 2, 8, curb);
-
-    // Right-side mall entrance (human spawn point).
+    canvas.fill_rect(side_x(0, 8), exit_y - 4, 8, exit_h + 8, asphalt_a); // curb break
+    canvas.fill_rect(side_x(8, 60), exit_y, 60, exit_h, exit_road);
+    canvas.rect_outline(side_x(8, 60), exit_y, 60, exit_h, exit_glow);
+    canvas.fill_rect(
+        side_x(12, 46),
+        exit_y + (exit_h / 2) - 1,
+        46,
+        2,
+        line_yellow,
+    );
+    canvas.fill_rect(side_x(55, 3), exit_y + 6, 3, exit_h - 12, exit_glow);
+    for arrow_y in [exit_y + 7, exit_y + 19] {
+        if mirror {
+            draw_right_arrow(&mut canvas, side_x(18, 10), arrow_y, line_white);
+        } else {
+            draw_left_arrow(&mut canvas, 18, arrow_y, line_white);
+        }
+    }
+    // Exit tiles sit in the boundary column where the curb is broken.
+    grid.mark_rect(side_x(0, 68), exit_y, 68, exit_h, open);
+    grid.mark_rect(
+        side_x(0, TILE_SIZE as i32),
+        exit_y,
+        TILE_SIZE as i32,
+        exit_h,
+        |tile| {
+            tile.marker = Marker::HumanExit;
+        },
+    );
+
+    // "OUT" sign so the opening reads as a clear parking-lot exit.
+    canvas.fill_rect(side_x(12, 34), exit_y - 22, 34, 14, exit_glow);
+    canvas.fill_rect(side_x(13, 32), exit_y - 21, 32, 12, rgba(8, 20, 16));
+    draw_out_text(&mut canvas, side_x(17, 19), exit_y - 18, line_white);
+    canvas.fill_rect(side_x(28, 2), exit_y - 8, 2, 8, curb);
+
+    // Mall entrance (human spawn point), on the edge opposite the exit.
     let entrance_w = 48;
     let entrance_h = 56;
-    let entrance_x = (WIDTH as i32) - 8 - entrance_w;
+    let entrance_x = side_x((WIDTH as i32) - 8 - entrance_w, entrance_w);
+    // Offsets inside the entrance are mirrored too, so the side walk always faces the lanes.
+    let mall_x = |dx: i32, w: i32| {
+        if mirror {
+            entrance_x + entrance_w - dx - w
+        } else {
+            entrance_x + dx
+        }
+    };
     let entrance_y = ((lane_top + lane_bottom) / 2) - 28;
-    fill_rect(
-        &mut pixels,
-        entrance_x,
-        entrance_y,
-        entrance_w,
-        entrance_h,
-        mall_wall,
-    );
-    rect_outline(
-        &mut pixels,
+    canvas.fill_rect(entrance_x, entrance_y, entrance_w, entrance_h, mall_wall);
+    canvas.rect_outline(
         entrance_x,
         entrance_y,
         entrance_w,
         entrance_h,
         rgba(172, 196, 214),
     );
-    fill_rect(
-        &mut pixels,
-        entrance_x + 4,
+    canvas.fill_rect(
+        mall_x(4, entrance_w - 8),
         entrance_y + 4,
         entrance_w - 8,
         8,
         mall_shadow,
     );
-    fill_rect(
-        &mut pixels,
-        entrance_x + 16,
-        entrance_y + 22,
-        16,
-        24,
-        mall_door,
-    );
-    rect_outline(
-        &mut pixels,
-        entrance_x + 16,
-        entrance_y + 22,
-        16,
-        24,
-        rgba(118, 146, 170),
-    );
-    fill_rect(
-        &mut pixels,
-        entrance_x + 22,
-        entrance_y + 22,
-        1,
-        24,
-        rgba(118, 146, 170),
-    );
-    fill_rect(
-        &mut pixels,
-        entrance_x + 25,
-        entrance_y + 22,
-        1,
-        24,
-        rgba(118, 146, 170),
-    );
-    fill_rect(
-        &mut pixels,
-        entrance_x - 12,
-        entrance_y + 30,
-        12,
-        8,
-        mall_walk,
-    );
-    rect_outline(
-        &mut pixels,
-        entrance_x - 12,
-        entrance_y + 30,
-        12,
-        8,
-        rgba(176, 204, 220),
-    );
-    fill_rect(
-        &mut pixels,
-        entrance_x + 13,
-        entrance_y + 12,
-        22,
-        8,
-        mall_walk,
-    );
-    rect_outline(
-        &mut pixels,
-        entrance_x + 13,
-        entrance_y + 12,
-        22,
-        8,
-        rgba(176, 204, 220),
-    );
+    canvas.fill_rect(mall_x(16, 16), entrance_y + 22, 16, 24, mall_door);
+    canvas.rect_outline(mall_x(16, 16), entrance_y + 22, 16, 24, rgba(118, 146, 170));
+    canvas.fill_rect(mall_x(22, 1), entrance_y + 22, 1, 24, rgba(118, 146, 170));
+    canvas.fill_rect(mall_x(25, 1), entrance_y + 22, 1, 24, rgba(118, 146, 170));
+    canvas.fill_rect(mall_x(-12, 12), entrance_y + 30, 12, 8, mall_walk);
+    canvas.rect_outline(mall_x(-12, 12), entrance_y + 30, 12, 8, rgba(176, 204, 220));
+    canvas.fill_rect(mall_x(13, 22), entrance_y + 12, 22, 8, mall_walk);
+    canvas.rect_outline(mall_x(13, 22), entrance_y + 12, 22, 8, rgba(176, 204, 220));
     draw_mall_mark(
-        &mut pixels,
-        entrance_x + 20,
+        &mut canvas,
+        mall_x(20, 7),
         entrance_y + 13,
         rgba(30, 52, 70),
     );
+    // The building blocks movement except for a path from the side walk to the doors.
+    grid.mark_rect(entrance_x, entrance_y, entrance_w, entrance_h, block);
+    grid.mark_rect(mall_x(-12, 12 + 32), entrance_y + 22, 12 + 32, 24, open);
+    grid.mark_rect(mall_x(16, 16), entrance_y + 22, 16, 24, |tile| {
+        tile.marker = Marker::HumanSpawn;
+    });
+
+    // Spawn bases, each in its own animated layer.
+    grid.mark_circle(alien_cx, alien_cy, base_outer_r, |tile| {
+        tile.terrain = Terrain::Walkable;
+        tile.marker = Marker::AlienSpawnBase;
+    });
+    grid.mark_circle(demon_cx, demon_cy, base_outer_r, |tile| {
+        tile.terrain = Terrain::Walkable;
+        tile.marker = Marker::DemonSpawnBase;
+    });
+    // The characters stand on the base centre, outlined so they read against the rings.
+    let [alien, demon] = spawn_sprites(SPAWN_SPRITE_H * scale);
+    let s = scale as i32;
+    let placement = |outline| Placement {
+        pivot: Pivot::BottomCenter,
+        shadow: Some(Shadow {
+            offset: (s, 2 * s),
+            ..Shadow::default()
+        }),
+        outline: Some(outline),
+        ..Placement::default()
+    };
+    let alien_placement = placement(rgba(8, 16, 30));
+    let demon_placement = placement(rgba(34, 6, 10));
+    let stand = |canvas: &mut Canvas, sprite: &Option<Canvas>, cx, cy, placement: &Placement| {
+        if let Some(sprite) = sprite {
+            canvas.place(sprite, cx, cy + SPAWN_SPRITE_FEET, placement);
+        }
+    };
+    let alien_base = base_layer(alien_cx, alien_cy, scale, |canvas, cx, cy, frame| {
+        draw_alien_base(canvas, cx, cy, base_outer_r, ALIEN_PULSE[frame as usize]);
+        stand(canvas, &alien, cx, cy, &alien_placement);
+    });
+    let demon_base = base_layer(demon_cx, demon_cy, scale, |canvas, cx, cy, frame| {
+        draw_demon_base(canvas, cx, cy, base_outer_r, frame);
+        stand(canvas, &demon, cx, cy, &demon_placement);
+    });
+
+    let still = |kind, x: i32, y: i32, width: i32, height: i32, pixels| LevelLayer {
+        info: LayerInfo {
+            kind,
+            frames: 1,
+            frame_ms: 0,
+            x: x as u32,
+            y: y as u32,
+            width: width as u32,
+            height: height as u32,
+        },
+        pixels,
+    };
+    let layers = vec![
+        still(
+            LayerKind::Ground,
+            0,
+            0,
+            WIDTH as i32,
+            HEIGHT as i32,
+            canvas.pixels,
+        ),
+        alien_base,
+        demon_base,
+        still(
+            LayerKind::Foreground,
+            gap_lc_x,
+            island_y,
+            trees_w,
+            island_h,
+            trees.pixels,
+        ),
+    ];
+    (layers, grid)
+}
 
-    // Alien spawn base (top).
-    fill_circle(&mut pixels, alien_cx, alien_cy, base_outer_r, alien_outer);
-    fill_circle(&mut pixels, alien_cx, alien_cy, base_inner_r, alien_inner);
-    fill_ring(
-        &mut pixels,
-        alien_cx,
-        alien_cy,
-        base_outer_r,
-        base_outer_r - 4,
-        line_white,
-    );
-    fill_ring(&mut pixels, alien_cx, alien_cy, 14, 10, rgba(88, 228, 255));
-    fill_circle(&mut pixels, alien_cx, alien_cy, base_core_r, alien_core);
-    draw_alien_sigil(
-        &mut pixels,
-        alien_cx,
-        alien_cy,
-        rgba(20, 34, 54),
-        rgba(112, 240, 255),
-        rgba(8, 16, 30),
-    );
-
-    // Demon spawn base (bottom).
-    fill_circle(&mut pixels, demon_cx, demon_cy, base_outer_r, demon_outer);
-    fill_circle(&mut pixels, demon_cx, demon_cy, base_inner_r, demon_inner);
-    fill_ring(
-        &mut pixels,
-        demon_cx,
-        demon_cy,
-        base_outer_r,
-        base_outer_r - 4,
-        rgba(226, 120, 68),
-    );
-    fill_circle(&mut pixels, demon_cx, demon_cy, base_core_r, demon_core);
-    draw_burrow_mouth(&mut pixels, demon_cx, demon_cy);
-
-    pixels
+#[unsafe(no_mangle)]
+pub extern "C" fn bvb_level_abi_version() -> u32 {
+    ABI_VERSION
 }
 
 #[unsafe(no_mangle)]
 pub extern "C" fn bvb_level_init() {
-    // Idempotent init.
-    if unsafe { !PIXELS_PTR.is_null() } {
-        return;
+    // Idempotent while a level is loaded.
+    let mut level = current();
+    if level.is_none() {
+        *level = Some(Level::new(0, &LevelParams::default(), 1));
+    }
+}
+
+/// Regenerates the level from `seed` and the `LevelParams` bytes at `params_ptr`, replacing any
+/// previous output. Returns 0 on success and 1 if the params could not be decoded, in which case
+/// the current level is left untouched. The current scale is kept.
+///
+/// Hosts allocate the params buffer with `bvb_alloc` and free it afterwards.
+///
+/// # Safety
+///
+/// `params_ptr` must be null or point to `params_len` readable bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bvb_level_init_with(
+    seed: u32,
+    params_ptr: *const u8,
+    params_len: u32,
+) -> u32 {
+    let bytes = if params_ptr.is_null() || params_len == 0 {
+        &[][..]
+    } else {
+        unsafe { core::slice::from_raw_parts(params_ptr, params_len as usize) }
+    };
+    match LevelParams::from_bytes(bytes) {
+        Ok(params) => {
+            let mut level = current();
+            let scale = level.as_ref().map_or(1, |level| level.scale);
+            *level = Some(Level::new(seed, &params, scale));
+            0
+        }
+        Err(_) => 1,
+    }
+}
+
+/// Regenerates the current level at `scale` image pixels per level pixel (1 to 4), keeping its seed
+/// and params. Returns 0 on success and 1 for an unsupported scale.
+#[unsafe(no_mangle)]
+pub extern "C" fn bvb_level_render(scale: u32) -> u32 {
+    if !(1..=MAX_SCALE).contains(&scale) {
+        return 1;
     }
+    let mut level = current();
+    let (seed, params) = level.as_ref().map_or((0, LevelParams::default()), |level| {
+        (level.seed, level.params)
+    });
+    *level = Some(Level::new(seed, &params, scale));
+    0
+}
 
-    let mut pixels = generate();
-    let ptr = pixels.as_mut_ptr();
-    let len = pixels.len();
+/// Frees the level's buffers. Pointer exports return null until the next init; the next
+/// `bvb_level_init` starts over from seed 0 at 1x.
+#[unsafe(no_mangle)]
+pub extern "C" fn bvb_level_release() {
+    *current() = None;
+}
 
-    unsafe {
-        PIXELS_PTR = ptr;
-        PIXELS_LEN = len;
+#[unsafe(no_mangle)]
+pub extern "C" fn bvb_level_scale() -> u32 {
+    current().as_ref().map_or(1, |level| level.scale)
+}
+
+/// Allocates `len` zeroed bytes for the host, e.g. to pass params to `bvb_level_init_with`.
+#[unsafe(no_mangle)]
+pub extern "C" fn bvb_alloc(len: u32) -> *mut u8 {
+    Box::into_raw(vec![0u8; len as usize].into_boxed_slice()).cast::<u8>()
+}
+
+/// Frees a buffer from `bvb_alloc`.
+///
+/// # Safety
+///
+/// `ptr` and `len` must be the result and argument of one `bvb_alloc` call, freed only once.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bvb_free(ptr: *mut u8, len: u32) {
+    if !ptr.is_null() {
+        drop(unsafe { Box::from_raw(core::ptr::slice_from_raw_parts_mut(ptr, len as usize)) });
+    }
+}
+
+struct Level {
+    seed: u32,
+    params: LevelParams,
+    scale: u32,
+    pixels: Box<[u8]>,
+    semantics: Box<[u8]>,
+    content_hash: u64,
+    layers: Box<[u8]>,
+    layer_pixels: Vec<Box<[u8]>>,
+}
+
+impl Level {
+    fn new(seed: u32, params: &LevelParams, scale: u32) -> Self {
+        let (layers, grid) = generate_layers(seed, params, scale);
+        let pixels = flatten_layers(&layers, scale);
+        Self {
+            seed,
+            params: *params,
+            scale,
+            content_hash: content_hash(WIDTH * scale, HEIGHT * scale, &pixels),
+            pixels: pixels.into_boxed_slice(),
+            semantics: grid.to_bytes().into_boxed_slice(),
+            layers: layers_to_bytes(&layers).into_boxed_slice(),
+            layer_pixels: layers
+                .into_iter()
+                .map(|layer| layer.pixels.into_boxed_slice())
+                .collect(),
+        }
     }
+}
 
-    // Intentionally leak to keep the buffer alive for the lifetime of the module.
-    core::mem::forget(pixels);
+// A panic while holding the lock cannot leave a half-written level, so poisoning is ignored.
+fn current() -> MutexGuard<'static, Option<Level>> {
+    LEVEL.lock().unwrap_or_else(PoisonError::into_inner)
 }
 
+fn buffer(bytes: impl FnOnce(&Level) -> &[u8]) -> (*const u8, u32) {
+    current().as_ref().map_or((core::ptr::null(), 0), |level| {
+        let bytes = bytes(level);
+        (bytes.as_ptr(), bytes.len() as u32)
+    })
+}
+
+/// Image width in pixels: the level width times the current scale.
 #[unsafe(no_mangle)]
 pub extern "C" fn bvb_level_width() -> u32 {
-    WIDTH
+    WIDTH * bvb_level_scale()
 }
 
 #[unsafe(no_mangle)]
 pub extern "C" fn bvb_level_height() -> u32 {
-    HEIGHT
+    HEIGHT * bvb_level_scale()
 }
 
 #[unsafe(no_mangle)]
 pub extern "C" fn bvb_level_pixels_ptr() -> *const u8 {
-    unsafe { PIXELS_PTR as *const u8 }
+    buffer(|level| &level.pixels).0
 }
 
 #[unsafe(no_mangle)]
 pub extern "C" fn bvb_level_pixels_len() -> u32 {
-    unsafe { PIXELS_LEN as u32 }
+    buffer(|level| &level.pixels).1
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn bvb_level_name_ptr() -> *const u8 {
+    LEVEL_NAME.as_ptr()
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn bvb_level_name_len() -> u32 {
+    LEVEL_NAME.len() as u32
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn bvb_level_author_ptr() -> *const u8 {
+    LEVEL_AUTHOR.as_ptr()
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn bvb_level_author_len() -> u32 {
+    LEVEL_AUTHOR.len() as u32
+}
+
+/// Tile edge in level pixels, independent of the scale.
+#[unsafe(no_mangle)]
+pub extern "C" fn bvb_level_tile_size() -> u32 {
+    TILE_SIZE
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn bvb_level_grid_width() -> u32 {
+    GRID_WIDTH
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn bvb_level_grid_height() -> u32 {
+    GRID_HEIGHT
+}
+
+/// Hash of the level as last generated, at its seed, params and scale; 0 before init or after
+/// release.
+#[unsafe(no_mangle)]
+pub extern "C" fn bvb_level_content_hash() -> u64 {
+    current().as_ref().map_or(0, |level| level.content_hash)
+}
+
+/// Tile-level semantic grid (`bvb_level_grid_width` x `bvb_level_grid_height`, row-major,
+/// `SEMANTIC_TILE_BYTES` per tile), valid after init.
+#[unsafe(no_mangle)]
+pub extern "C" fn bvb_level_semantics_ptr() -> *const u8 {
+    buffer(|level| &level.semantics).0
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn bvb_level_semantics_len() -> u32 {
+    buffer(|level| &level.semantics).1
+}
+
+/// Layer table (`LAYER_INFO_BYTES` per layer: ground, the two animated spawn bases, then the
+/// island trees), valid after init. `bvb_level_pixels_*` stays the flattened image.
+#[unsafe(no_mangle)]
+pub extern "C" fn bvb_level_layers_ptr() -> *const u8 {
+    buffer(|level| &level.layers).0
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn bvb_level_layers_len() -> u32 {
+    buffer(|level| &level.layers).1
+}
+
+/// RGBA pixels of layer `index` at the current scale, frames stacked top to bottom; null for an
+/// unknown layer.
+#[unsafe(no_mangle)]
+pub extern "C" fn bvb_level_layer_pixels_ptr(index: u32) -> *const u8 {
+    layer_buffer(index).0
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn bvb_level_layer_pixels_len(index: u32) -> u32 {
+    layer_buffer(index).1
+}
+
+fn layer_buffer(index: u32) -> (*const u8, u32) {
+    current()
+        .as_ref()
+        .and_then(|level| level.layer_pixels.get(index as usize))
+        .map_or((core::ptr::null(), 0), |pixels| {
+            (pixels.as_ptr(), pixels.len() as u32)
+        })
 }
@@ -0,0 +1,158 @@
+use std::path::{Path, PathBuf};
+
+use bvb_level_kit::{ExitSide, LevelParams, Marker, NavGraph, SemanticGrid};
+use bvb_level_parking_lot::generate;
+use bvb_raster::{decode_png, encode_png};
+
+// A pixel differs when any channel is off by more than this.
+const CHANNEL_TOLERANCE: u8 = 8;
+// Differing pixels allowed before the snapshot fails, so dithering tweaks do not need a re-bless.
+const MAX_DIFF_PIXELS: usize = 64;
+
+fn golden_path(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{name}.png"))
+}
+
+fn output_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("target/golden")
+}
+
+// Compares a generated level against `tests/golden/<name>.png`. On failure the actual image and a
+// diff (changed pixels red over a dimmed golden) are written to `target/golden/`. Set
+// `BVB_UPDATE_GOLDEN=1` to rewrite the golden instead.
+fn assert_matches_golden(name: &str, width: u32, height: u32, pixels: &[u8]) {
+    let path = golden_path(name);
+    if std::env::var_os("BVB_UPDATE_GOLDEN").is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, encode_png(width, height, pixels)).unwrap();
+        return;
+    }
+    let bytes = std::fs::read(&path).unwrap_or_else(|err| {
+        panic!(
+            "{}: {err}; run with BVB_UPDATE_GOLDEN=1 to create it",
+            path.display()
+        )
+    });
+    let golden = decode_png(&bytes).expect("golden PNG");
+    assert_eq!(
+        (golden.width, golden.height),
+        (width, height),
+        "{name}: size changed"
+    );
+
+    let mut diff = Vec::with_capacity(pixels.len());
+    let mut changed = 0;
+    for (actual, expected) in pixels.chunks_exact(4).zip(golden.pixels.chunks_exact(4)) {
+        let differs = actual
+            .iter()
+            .zip(expected)
+            .any(|(a, e)| a.abs_diff(*e) > CHANNEL_TOLERANCE);
+        if differs {
+            changed += 1;
+            diff.extend_from_slice(&[255, 0, 64, 255]);
+        } else {
+            let luma =
+                (u32::from(expected[0]) + u32::from(expected[1]) + u32::from(expected[2])) / 9;
+            diff.extend_from_slice(&[luma as u8; 3]);
+            diff.push(255);
+        }
+    }
+    if changed > MAX_DIFF_PIXELS {
+        let dir = output_dir();
+        std::fs::create_dir_all(&dir).unwrap();
+        let actual_path = dir.join(format!("{name}.actual.png"));
+        let diff_path = dir.join(format!("{name}.diff.png"));
+        std::fs::write(&actual_path, encode_png(width, height, pixels)).unwrap();
+        std::fs::write(&diff_path, encode_png(width, height, &diff)).unwrap();
+        panic!(
+            "{name}: {changed} pixels differ from {} (at most {MAX_DIFF_PIXELS} allowed); see {} and {}",
+            path.display(),
+            actual_path.display(),
+            diff_path.display()
+        );
+    }
+}
+
+fn assert_invariants(label: &str, grid: &SemanticGrid) {
+    // A tile touches the map edge when nothing walkable lies between it and the edge in some
+    // direction: the exit sits in the broken curb and the mall doors in a building backed onto it.
+    let blocked = |x: u32, y: u32| !grid.get(x, y).unwrap().is_walkable();
+    let on_edge = |&(x, y): &(u32, u32)| {
+        (0..x).all(|tx| blocked(tx, y))
+            || (x + 1..grid.width).all(|tx| blocked(tx, y))
+            || (0..y).all(|ty| blocked(x, ty))
+            || (y + 1..grid.height).all(|ty| blocked(x, ty))
+    };
+
+    let alien = grid.find(Marker::AlienSpawnBase);
+    let demon = grid.find(Marker::DemonSpawnBase);
+    assert!(!alien.is_empty(), "{label}: no alien spawn base");
+    assert!(!demon.is_empty(), "{label}: no demon spawn base");
+    let regions = NavGraph::new(grid).regions();
+    assert!(
+        regions.connected(&[alien[0], demon[0]]),
+        "{label}: demon base is not reachable from the alien base"
+    );
+
+    for (marker, what) in [
+        (Marker::HumanExit, "exit"),
+        (Marker::HumanSpawn, "entrance"),
+    ] {
+        let tiles = grid.find(marker);
+        assert!(
+            tiles.iter().any(on_edge),
+            "{label}: {what} does not touch the map edge"
+        );
+        assert!(
+            tiles
+                .iter()
+                .any(|&tile| regions.connected(&[alien[0], tile])),
+            "{label}: {what} is not reachable from the spawn bases"
+        );
+    }
+}
+
+#[test]
+fn default_level_matches_golden() {
+    let (pixels, grid) = generate(0, &LevelParams::default(), 1);
+    let (width, height) = (grid.width * grid.tile_size, grid.height * grid.tile_size);
+    assert_matches_golden("mall_parking_lot", width, height, &pixels);
+    assert_invariants("default", &grid);
+}
+
+#[test]
+fn variants_keep_invariants() {
+    for seed in 1..=24 {
+        for exit_side in [ExitSide::Left, ExitSide::Right] {
+            let params = LevelParams {
+                lane_width: 24 + (seed as u8 * 5) % 21,
+                crossovers: seed as u8 % 5,
+                bay_density: seed as u8 % 4,
+                exit_side,
+            };
+            let (_, grid) = generate(seed, &params, 1);
+            assert_invariants(&format!("seed {seed}, {params:?}"), &grid);
+        }
+    }
+}
+
+#[test]
+fn scaled_render_matches_nearest_upscale() {
+    let (base, _) = generate(7, &LevelParams::default(), 1);
+    let (scaled, grid) = generate(7, &LevelParams::default(), 2);
+    let width = (grid.width * grid.tile_size) as usize;
+    let differing = (0..base.len() / 4)
+        .filter(|&i| {
+            let (x, y) = (i % width, i / width);
+            let j = (2 * y) * 2 * width + 2 * x;
+            base[i * 4..i * 4 + 4] != scaled[j * 4..j * 4 + 4]
+        })
+        .count();
+    // Shapes are drawn at the higher resolution, so only edge pixels may land differently.
+    assert!(
+        differing * 20 < base.len() / 4,
+        "{differing} pixels differ between 1x and the top-left samples of 2x"
+    );
+}
@@ -0,0 +1,56 @@
+use bvb_level_kit::{LayerKind, LevelParams, content_hash};
+use bvb_level_parking_lot::{
+    bvb_level_content_hash, bvb_level_init, bvb_level_layer_pixels_len, bvb_level_layers_len,
+    flatten_layers, generate, generate_layers,
+};
+
+#[test]
+fn layers_flatten_to_the_level_image() {
+    let params = LevelParams {
+        crossovers: 4,
+        ..LevelParams::default()
+    };
+    for (seed, scale) in [(0, 1), (5, 2)] {
+        let (layers, _) = generate_layers(seed, &params, scale);
+        let (pixels, _) = generate(seed, &params, scale);
+        assert_eq!(flatten_layers(&layers, scale), pixels, "seed {seed}");
+
+        let kinds: Vec<_> = layers.iter().map(|layer| layer.info.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                LayerKind::Ground,
+                LayerKind::Animated,
+                LayerKind::Animated,
+                LayerKind::Foreground
+            ]
+        );
+        for layer in &layers {
+            let info = layer.info;
+            assert!(info.x + info.width <= 288 && info.y + info.height <= 512);
+            assert_eq!(layer.pixels.len(), info.pixels_len(scale));
+        }
+        // Every base frame differs from the still art somewhere.
+        let base = &layers[1];
+        let frame_len = base.pixels.len() / base.info.frames as usize;
+        let frames: Vec<_> = base.pixels.chunks_exact(frame_len).collect();
+        assert!(frames[1..].iter().all(|frame| *frame != frames[0]));
+    }
+}
+
+#[test]
+fn layer_exports_match_the_flattened_level() {
+    assert_eq!(bvb_level_content_hash(), 0);
+    bvb_level_init();
+    let hash = bvb_level_content_hash();
+    let (layers, _) = generate_layers(0, &LevelParams::default(), 1);
+    assert_eq!(bvb_level_layers_len() as usize, layers.len() * 28);
+    for (index, layer) in (0..).zip(&layers) {
+        assert_eq!(
+            bvb_level_layer_pixels_len(index) as usize,
+            layer.pixels.len()
+        );
+    }
+    assert_eq!(bvb_level_layer_pixels_len(layers.len() as u32), 0);
+    assert_eq!(content_hash(288, 512, &flatten_layers(&layers, 1)), hash);
+}
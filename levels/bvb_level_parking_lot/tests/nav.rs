@@ -0,0 +1,76 @@
+use bvb_level_kit::{DIAGONAL_COST, ExitSide, LevelParams, Marker, NavGraph, STEP_COST};
+use bvb_level_parking_lot::generate;
+
+// Tile rows where adjacent lanes connect directly, over both median islands.
+fn crossing_rows(graph: &NavGraph) -> u32 {
+    [(1, 2), (2, 3)]
+        .into_iter()
+        .flat_map(|(a, b)| graph.lane_crossings(a, b))
+        .map(|crossing| crossing.bottom - crossing.top + 1)
+        .sum()
+}
+
+#[test]
+fn every_staggered_opening_connects_lanes() {
+    for seed in 0..16 {
+        let (_, grid) = generate(
+            seed,
+            &LevelParams {
+                crossovers: 0,
+                ..LevelParams::default()
+            },
+            1,
+        );
+        let graph = NavGraph::new(&grid);
+        // The lanes meet at both spawn connectors and the middle crossover.
+        assert_eq!(graph.lane_crossings(1, 2).len(), 3, "seed {seed}");
+        assert_eq!(graph.lane_crossings(2, 3).len(), 3, "seed {seed}");
+
+        // Adding one opening keeps the jitter of the earlier ones, so each one must add rows.
+        let mut rows = crossing_rows(&graph);
+        for crossovers in 1..=4 {
+            let params = LevelParams {
+                crossovers,
+                ..LevelParams::default()
+            };
+            let (_, grid) = generate(seed, &params, 1);
+            let more = crossing_rows(&NavGraph::new(&grid));
+            assert!(
+                more > rows,
+                "seed {seed}: staggered opening {crossovers} does not connect its lanes"
+            );
+            rows = more;
+        }
+    }
+}
+
+#[test]
+fn flag_carriers_reach_the_exit() {
+    for exit_side in [ExitSide::Left, ExitSide::Right] {
+        let params = LevelParams {
+            exit_side,
+            ..LevelParams::default()
+        };
+        let (_, grid) = generate(0, &params, 1);
+        let graph = NavGraph::new(&grid);
+        let exits = grid.find(Marker::HumanExit);
+        let flag = grid.find(Marker::FlagBase)[0];
+
+        let field = graph.flow_field(&exits);
+        let mut tile = flag;
+        while let Some(next) = field.next_step(tile) {
+            tile = next;
+        }
+        assert!(exits.contains(&tile), "{exit_side:?}: stopped at {tile:?}");
+
+        let path = graph.find_path(flag, tile).expect("path to the exit");
+        let cost: u32 = path
+            .windows(2)
+            .map(|step| {
+                let diagonal = step[0].0 != step[1].0 && step[0].1 != step[1].1;
+                if diagonal { DIAGONAL_COST } else { STEP_COST }
+            })
+            .sum();
+        assert_eq!(Some(cost), field.distance(flag), "{exit_side:?}");
+    }
+}
@@ -0,0 +1,385 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::Canvas;
+
+// Compression codes from the BITMAPINFOHEADER.
+const BI_RGB: u32 = 0;
+const BI_RLE8: u32 = 1;
+const BI_RLE4: u32 = 2;
+const BI_BITFIELDS: u32 = 3;
+const BI_ALPHABITFIELDS: u32 = 6;
+
+const FILE_HEADER_LEN: usize = 14;
+const INFO_HEADER_LEN: usize = 40;
+// Enough for any sprite sheet, and keeps a corrupt header from asking for gigabytes.
+const MAX_PIXELS: u64 = 1 << 26;
+
+/// Why `decode_bmp` rejected a file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BmpError {
+    /// The file does not start with `BM`.
+    NotBmp,
+    /// The file ends before `what`.
+    Truncated {
+        what: &'static str,
+    },
+    /// Only BITMAPINFOHEADER (40 bytes) and its V2 to V5 extensions are supported.
+    UnsupportedHeader {
+        size: u32,
+    },
+    InvalidDimensions {
+        width: i32,
+        height: i32,
+    },
+    InvalidPlanes {
+        planes: u16,
+    },
+    UnsupportedFormat {
+        bits_per_pixel: u16,
+        compression: u32,
+    },
+    /// RLE images must be stored bottom-up.
+    TopDownRle,
+    PaletteIndex {
+        index: u8,
+        palette_len: usize,
+    },
+    /// An RLE escape or run points outside the image or the data.
+    InvalidRle {
+        offset: usize,
+    },
+}
+
+impl fmt::Display for BmpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BmpError::NotBmp => write!(f, "not a BMP file (missing `BM` signature)"),
+            BmpError::Truncated { what } => write!(f, "BMP file is truncated in the {what}"),
+            BmpError::UnsupportedHeader { size } => {
+                write!(f, "unsupported BMP header of {size} bytes")
+            }
+            BmpError::InvalidDimensions { width, height } => {
+                write!(f, "invalid BMP dimensions {width}x{height}")
+            }
+            BmpError::InvalidPlanes { planes } => {
+                write!(f, "BMP has {planes} colour planes, expected 1")
+            }
+            BmpError::UnsupportedFormat {
+                bits_per_pixel,
+                compression,
+            } => write!(
+                f,
+                "unsupported BMP format: {bits_per_pixel} bits per pixel with compression {compression}"
+            ),
+            BmpError::TopDownRle => write!(f, "RLE-compressed BMP cannot be top-down"),
+            BmpError::PaletteIndex { index, palette_len } => write!(
+                f,
+                "BMP palette index {index} is outside its {palette_len}-colour palette"
+            ),
+            BmpError::InvalidRle { offset } => {
+                write!(f, "invalid BMP RLE data at byte {offset}")
+            }
+        }
+    }
+}
+
+impl core::error::Error for BmpError {}
+
+#[inline]
+fn read_le_u16(bytes: &[u8], at: usize, what: &'static str) -> Result<u16, BmpError> {
+    let b = bytes.get(at..at + 2).ok_or(BmpError::Truncated { what })?;
+    Ok(u16::from_le_bytes([b[0], b[1]]))
+}
+
+#[inline]
+fn read_le_u32(bytes: &[u8], at: usize, what: &'static str) -> Result<u32, BmpError> {
+    let b = bytes.get(at..at + 4).ok_or(BmpError::Truncated { what })?;
+    Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+/// Channel masks for 16- and 32-bit pixels. A zero alpha mask means opaque.
+#[derive(Clone, Copy)]
+struct Masks([u32; 4]);
+
+impl Masks {
+    const RGB555: Self = Self([0x7C00, 0x03E0, 0x001F, 0]);
+    const BGRA8888: Self = Self([0x00FF_0000, 0x0000_FF00, 0x0000_00FF, 0xFF00_0000]);
+
+    fn decode(&self, v: u32) -> [u8; 4] {
+        self.0.map(|mask| extract_channel(v, mask))
+    }
+}
+
+// Scales the masked field to 0..=255; an absent channel reads as 255.
+#[inline]
+fn extract_channel(v: u32, mask: u32) -> u8 {
+    if mask == 0 {
+        return 255;
+    }
+    let shift = mask.trailing_zeros();
+    let max = u64::from((mask >> shift).max(1));
+    let value = u64::from((v & mask) >> shift);
+    ((value * 255) / max) as u8
+}
+
+struct Header {
+    data_offset: usize,
+    header_len: usize,
+    width: usize,
+    height: usize,
+    top_down: bool,
+    bits_per_pixel: u16,
+    compression: u32,
+    colors_used: usize,
+}
+
+fn read_header(bytes: &[u8]) -> Result<Header, BmpError> {
+    if bytes.get(0..2) != Some(b"BM".as_slice()) {
+        return Err(BmpError::NotBmp);
+    }
+    let data_offset = read_le_u32(bytes, 10, "file header")? as usize;
+    let header_len = read_le_u32(bytes, 14, "file header")?;
+    if !matches!(header_len, 40 | 52 | 56 | 108 | 124) {
+        return Err(BmpError::UnsupportedHeader { size: header_len });
+    }
+    let header_len = header_len as usize;
+    if bytes.len() < FILE_HEADER_LEN + header_len {
+        return Err(BmpError::Truncated {
+            what: "info header",
+        });
+    }
+
+    let width = read_le_u32(bytes, 18, "info header")? as i32;
+    let height = read_le_u32(bytes, 22, "info header")? as i32;
+    let planes = read_le_u16(bytes, 26, "info header")?;
+    if planes != 1 {
+        return Err(BmpError::InvalidPlanes { planes });
+    }
+    let pixels = u64::from(width.unsigned_abs()) * u64::from(height.unsigned_abs());
+    if width <= 0 || height == 0 || height == i32::MIN || pixels > MAX_PIXELS {
+        return Err(BmpError::InvalidDimensions { width, height });
+    }
+
+    Ok(Header {
+        data_offset,
+        header_len,
+        width: width as usize,
+        height: height.unsigned_abs() as usize,
+        top_down: height < 0,
+        bits_per_pixel: read_le_u16(bytes, 28, "info header")?,
+        compression: read_le_u32(bytes, 30, "info header")?,
+        colors_used: read_le_u32(bytes, 46, "info header")? as usize,
+    })
+}
+
+// BITFIELDS masks follow a 40-byte header and live inside V2+ headers; V3+ headers add alpha.
+fn read_masks(bytes: &[u8], header: &Header) -> Result<Option<Masks>, BmpError> {
+    let channels = match header.compression {
+        BI_BITFIELDS if header.header_len >= 56 => 4,
+        BI_BITFIELDS => 3,
+        BI_ALPHABITFIELDS => 4,
+        _ => return Ok(None),
+    };
+    let start = FILE_HEADER_LEN + INFO_HEADER_LEN;
+    let mut masks = [0u32; 4];
+    for (i, mask) in masks.iter_mut().enumerate().take(channels) {
+        *mask = read_le_u32(bytes, start + 4 * i, "colour masks")?;
+    }
+    Ok(Some(Masks(masks)))
+}
+
+fn read_palette(bytes: &[u8], header: &Header) -> Result<Vec<[u8; 4]>, BmpError> {
+    let max = 1usize << header.bits_per_pixel;
+    let len = match header.colors_used {
+        0 => max,
+        n => n.min(max),
+    };
+    let start = FILE_HEADER_LEN + header.header_len;
+    let table = bytes
+        .get(start..start + 4 * len)
+        .ok_or(BmpError::Truncated { what: "palette" })?;
+    Ok(table
+        .chunks_exact(4)
+        .map(|bgr| [bgr[2], bgr[1], bgr[0], 255])
+        .collect())
+}
+
+/// Decodes a BMP file into an RGBA canvas.
+///
+/// Supports 1-, 4- and 8-bit palettized images (uncompressed, RLE8 and RLE4), 16-bit (555, or any
+/// layout given by BITFIELDS masks), 24-bit, and 32-bit with BITFIELDS or BITMAPV4/V5 colour masks.
+/// Pixels an RLE image skips with delta or end-of-line codes are transparent.
+pub fn decode_bmp(bytes: &[u8]) -> Result<Canvas, BmpError> {
+    let header = read_header(bytes)?;
+    let masks = read_masks(bytes, &header)?;
+    let (w, h) = (header.width, header.height);
+    let mut out = vec![0u8; w * h * 4];
+    let data = bytes
+        .get(header.data_offset..)
+        .ok_or(BmpError::Truncated { what: "pixel data" })?;
+    let unsupported = BmpError::UnsupportedFormat {
+        bits_per_pixel: header.bits_per_pixel,
+        compression: header.compression,
+    };
+
+    match (header.bits_per_pixel, header.compression) {
+        (1 | 4 | 8, BI_RGB) => {
+            let palette = read_palette(bytes, &header)?;
+            let bpp = usize::from(header.bits_per_pixel);
+            decode_rows(data, &header, (w * bpp).div_ceil(8), &mut out, |row, x| {
+                let bit = x * bpp;
+                let shift = 8 - bpp - (bit % 8);
+                let index = (row[bit / 8] >> shift) & ((1u16 << bpp) - 1) as u8;
+                palette_color(&palette, index)
+            })?;
+        }
+        (8, BI_RLE8) | (4, BI_RLE4) => {
+            if header.top_down {
+                return Err(BmpError::TopDownRle);
+            }
+            let palette = read_palette(bytes, &header)?;
+            decode_rle(data, &header, &palette, &mut out)?;
+        }
+        (16, BI_RGB | BI_BITFIELDS | BI_ALPHABITFIELDS) => {
+            let masks = masks.unwrap_or(Masks::RGB555);
+            decode_rows(data, &header, w * 2, &mut out, |row, x| {
+                let v = u16::from_le_bytes([row[2 * x], row[2 * x + 1]]);
+                Ok(masks.decode(u32::from(v)))
+            })?;
+        }
+        (24, BI_RGB) => {
+            decode_rows(data, &header, w * 3, &mut out, |row, x| {
+                let p = &row[3 * x..3 * x + 3];
+                Ok([p[2], p[1], p[0], 255])
+            })?;
+        }
+        (32, BI_RGB | BI_BITFIELDS | BI_ALPHABITFIELDS) => {
+            let masks = masks.unwrap_or(Masks::BGRA8888);
+            decode_rows(data, &header, w * 4, &mut out, |row, x| {
+                let p = &row[4 * x..4 * x + 4];
+                Ok(masks.decode(u32::from_le_bytes([p[0], p[1], p[2], p[3]])))
+            })?;
+        }
+        _ => return Err(unsupported),
+    }
+
+    Canvas::from_rgba(w as u32, h as u32, out).map_err(|_| BmpError::InvalidDimensions {
+        width: w as i32,
+        height: h as i32,
+    })
+}
+
+fn palette_color(palette: &[[u8; 4]], index: u8) -> Result<[u8; 4], BmpError> {
+    palette
+        .get(usize::from(index))
+        .copied()
+        .ok_or(BmpError::PaletteIndex {
+            index,
+            palette_len: palette.len(),
+        })
+}
+
+// Uncompressed rows of `row_len` bytes, each padded to 4 bytes.
+fn decode_rows(
+    data: &[u8],
+    header: &Header,
+    row_len: usize,
+    out: &mut [u8],
+    pixel: impl Fn(&[u8], usize) -> Result<[u8; 4], BmpError>,
+) -> Result<(), BmpError> {
+    let (w, h) = (header.width, header.height);
+    let stride = row_len.div_ceil(4) * 4;
+    let payload = data
+        .get(..stride * h)
+        .ok_or(BmpError::Truncated { what: "pixel data" })?;
+    for y in 0..h {
+        let src_y = if header.top_down { y } else { h - 1 - y };
+        let row = &payload[src_y * stride..src_y * stride + row_len];
+        for x in 0..w {
+            let di = (y * w + x) * 4;
+            out[di..di + 4].copy_from_slice(&pixel(row, x)?);
+        }
+    }
+    Ok(())
+}
+
+// RLE8 and RLE4: runs of one index (alternating two nibbles for RLE4), or escapes for end of line,
+// end of bitmap, a cursor delta, or an absolute run of literal indices padded to 2 bytes.
+fn decode_rle(
+    data: &[u8],
+    header: &Header,
+    palette: &[[u8; 4]],
+    out: &mut [u8],
+) -> Result<(), BmpError> {
+    let (w, h) = (header.width, header.height);
+    let four_bit = header.compression == BI_RLE4;
+    let nibble = |byte: u8, i: usize| {
+        if !four_bit {
+            byte
+        } else if i.is_multiple_of(2) {
+            byte >> 4
+        } else {
+            byte & 0x0F
+        }
+    };
+    // Rows count from the bottom of the image.
+    let (mut x, mut row) = (0usize, 0usize);
+    let mut put = |x: usize, row: usize, index: u8, at: usize| -> Result<(), BmpError> {
+        if x >= w || row >= h {
+            return Err(BmpError::InvalidRle { offset: at });
+        }
+        let di = ((h - 1 - row) * w + x) * 4;
+        out[di..di + 4].copy_from_slice(&palette_color(palette, index)?);
+        Ok(())
+    };
+
+    let mut at = 0;
+    while at + 1 < data.len() {
+        let (count, value) = (usize::from(data[at]), data[at + 1]);
+        let escape_at = at;
+        at += 2;
+        if count > 0 {
+            for i in 0..count {
+                put(x, row, nibble(value, i), escape_at)?;
+                x += 1;
+            }
+            continue;
+        }
+        match value {
+            0 => {
+                x = 0;
+                row += 1;
+            }
+            1 => return Ok(()),
+            2 => {
+                let delta = data
+                    .get(at..at + 2)
+                    .ok_or(BmpError::InvalidRle { offset: escape_at })?;
+                x += usize::from(delta[0]);
+                row += usize::from(delta[1]);
+                at += 2;
+            }
+            literal => {
+                let literal = usize::from(literal);
+                let len = if four_bit {
+                    literal.div_ceil(2)
+                } else {
+                    literal
+                };
+                let run = data
+                    .get(at..at + len)
+                    .ok_or(BmpError::InvalidRle { offset: escape_at })?;
+                for i in 0..literal {
+                    let byte = if four_bit { run[i / 2] } else { run[i] };
+                    put(x, row, nibble(byte, i), escape_at)?;
+                    x += 1;
+                }
+                at += len.div_ceil(2) * 2;
+            }
+        }
+    }
+    // A missing end-of-bitmap marker is tolerated, as most decoders do.
+    Ok(())
+}
@@ -0,0 +1,151 @@
+// zlib / DEFLATE compression (RFC 1950, RFC 1951) for `encode_png`.
+//
+// One fixed-Huffman block with hash-chain matching: no dynamic code tables to build, and pixel art
+// compresses well on repeated runs alone.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::inflate::{DIST_BASE, DIST_EXTRA, LENGTH_BASE, LENGTH_EXTRA, adler32};
+
+const WINDOW: usize = 32 * 1024;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+const MAX_CHAIN: usize = 32;
+const HASH_BITS: u32 = 15;
+
+struct BitWriter {
+    out: Vec<u8>,
+    bits: u64,
+    count: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u32, len: u32) {
+        self.bits |= u64::from(value) << self.count;
+        self.count += len;
+        while self.count >= 8 {
+            self.out.push(self.bits as u8);
+            self.bits >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    // Huffman codes are stored most-significant bit first.
+    fn write_code(&mut self, code: u32, len: u32) {
+        self.write(code.reverse_bits() >> (32 - len), len);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.out.push(self.bits as u8);
+        }
+        self.out
+    }
+}
+
+fn write_literal(writer: &mut BitWriter, symbol: u32) {
+    match symbol {
+        0..=143 => writer.write_code(0x30 + symbol, 8),
+        144..=255 => writer.write_code(0x190 + (symbol - 144), 9),
+        256..=279 => writer.write_code(symbol - 256, 7),
+        _ => writer.write_code(0xC0 + (symbol - 280), 8),
+    }
+}
+
+fn write_match(writer: &mut BitWriter, length: usize, distance: usize) {
+    let code = LENGTH_BASE
+        .iter()
+        .rposition(|&base| usize::from(base) <= length)
+        .unwrap_or(0);
+    write_literal(writer, 257 + code as u32);
+    writer.write(
+        (length - usize::from(LENGTH_BASE[code])) as u32,
+        u32::from(LENGTH_EXTRA[code]),
+    );
+
+    let code = DIST_BASE
+        .iter()
+        .rposition(|&base| usize::from(base) <= distance)
+        .unwrap_or(0);
+    writer.write_code(code as u32, 5);
+    writer.write(
+        (distance - usize::from(DIST_BASE[code])) as u32,
+        u32::from(DIST_EXTRA[code]),
+    );
+}
+
+fn hash3(bytes: &[u8]) -> usize {
+    let v = u32::from(bytes[0]) << 16 | u32::from(bytes[1]) << 8 | u32::from(bytes[2]);
+    (v.wrapping_mul(0x9E37_79B1) >> (32 - HASH_BITS)) as usize
+}
+
+/// Wraps `data` in a zlib stream holding a single fixed-Huffman deflate block.
+pub(crate) fn zlib_compress(data: &[u8]) -> Vec<u8> {
+    let mut writer = BitWriter {
+        out: vec![0x78, 0x01],
+        bits: 0,
+        count: 0,
+    };
+    writer.write(1, 1); // final block
+    writer.write(1, 2); // fixed Huffman codes
+
+    let mut head = vec![usize::MAX; 1 << HASH_BITS];
+    let mut prev = vec![usize::MAX; WINDOW];
+    let mut pos = 0;
+
+    while pos < data.len() {
+        let mut best_len = 0;
+        let mut best_dist = 0;
+
+        if pos + MIN_MATCH <= data.len() {
+            let hash = hash3(&data[pos..]);
+            let mut candidate = head[hash];
+            let max_len = MAX_MATCH.min(data.len() - pos);
+            let mut chain = 0;
+            while candidate != usize::MAX && pos - candidate <= WINDOW && chain < MAX_CHAIN {
+                let len = data[candidate..]
+                    .iter()
+                    .zip(&data[pos..pos + max_len])
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                if len > best_len {
+                    best_len = len;
+                    best_dist = pos - candidate;
+                    if len == max_len {
+                        break;
+                    }
+                }
+                // The chain lives in a ring buffer; a non-decreasing link means it was overwritten.
+                let next = prev[candidate % WINDOW];
+                if next >= candidate {
+                    break;
+                }
+                candidate = next;
+                chain += 1;
+            }
+            prev[pos % WINDOW] = head[hash];
+            head[hash] = pos;
+        }
+
+        if best_len >= MIN_MATCH {
+            write_match(&mut writer, best_len, best_dist);
+            for p in (pos + 1)..(pos + best_len) {
+                if p + MIN_MATCH <= data.len() {
+                    let hash = hash3(&data[p..]);
+                    prev[p % WINDOW] = head[hash];
+                    head[hash] = p;
+                }
+            }
+            pos += best_len;
+        } else {
+            write_literal(&mut writer, u32::from(data[pos]));
+            pos += 1;
+        }
+    }
+
+    write_literal(&mut writer, 256);
+    let mut out = writer.finish();
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
@@ -0,0 +1,372 @@
+// zlib / DEFLATE decompression (RFC 1950, RFC 1951) for `decode_png`.
+//
+// Canonical Huffman codes are decoded in the style of zlib's `puff`, a bit at a time, behind a
+// lookup table for the short codes that make up nearly all of a real stream. Character art is
+// megabytes of pixels, and level modules decode it under a fuel budget.
+
+use alloc::vec::Vec;
+
+const MAX_BITS: usize = 15;
+// Codes up to this many bits decode with one table lookup.
+const FAST_BITS: u32 = 9;
+
+pub(crate) const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+pub(crate) const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+pub(crate) const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+pub(crate) const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+// Order in which a dynamic block lists its code length code lengths.
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+/// Decompresses a zlib stream, failing once the output would exceed `limit` bytes.
+///
+/// Errors are short reasons for `PngError::InvalidZlib`.
+pub(crate) fn zlib_decompress(data: &[u8], limit: usize) -> Result<Vec<u8>, &'static str> {
+    let [cmf, flg, ..] = *data else {
+        return Err("missing zlib header");
+    };
+    if cmf & 0x0F != 8 || cmf >> 4 > 7 {
+        return Err("compression method is not deflate");
+    }
+    if (u16::from(cmf) << 8 | u16::from(flg)) % 31 != 0 {
+        return Err("bad zlib header check");
+    }
+    if flg & 0x20 != 0 {
+        return Err("preset dictionaries are not supported");
+    }
+
+    let mut bits = BitReader::new(&data[2..]);
+    let mut out = Vec::with_capacity(limit);
+    loop {
+        let last = bits.read(1)? == 1;
+        match bits.read(2)? {
+            0 => stored(&mut bits, &mut out, limit)?,
+            1 => {
+                let (lit, dist) = fixed_codes()?;
+                codes(&mut bits, &mut out, limit, &lit, &dist)?;
+            }
+            2 => {
+                let (lit, dist) = dynamic_codes(&mut bits)?;
+                codes(&mut bits, &mut out, limit, &lit, &dist)?;
+            }
+            _ => return Err("invalid block type"),
+        }
+        if last {
+            break;
+        }
+    }
+
+    bits.align();
+    let checksum = bits
+        .remaining()
+        .get(..4)
+        .ok_or("missing Adler-32 checksum")?;
+    if u32::from_be_bytes([checksum[0], checksum[1], checksum[2], checksum[3]]) != adler32(&out) {
+        return Err("Adler-32 checksum mismatch");
+    }
+    Ok(out)
+}
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    buf: u32,
+    len: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            pos: 0,
+            buf: 0,
+            len: 0,
+        }
+    }
+
+    // The next `n <= 16` bits without consuming them, and how many of those exist before the end
+    // of the data.
+    fn peek(&mut self, n: u32) -> (u32, u32) {
+        while self.len < n {
+            let Some(&byte) = self.data.get(self.pos) else {
+                break;
+            };
+            self.pos += 1;
+            self.buf |= u32::from(byte) << self.len;
+            self.len += 8;
+        }
+        (self.buf & ((1 << n) - 1), self.len.min(n))
+    }
+
+    // Drops `n` bits that `peek` returned.
+    fn consume(&mut self, n: u32) {
+        self.buf >>= n;
+        self.len -= n;
+    }
+
+    // Reads `n <= 16` bits, least significant first.
+    fn read(&mut self, n: u32) -> Result<u32, &'static str> {
+        while self.len < n {
+            let byte = *self.data.get(self.pos).ok_or("unexpected end of data")?;
+            self.pos += 1;
+            self.buf |= u32::from(byte) << self.len;
+            self.len += 8;
+        }
+        let value = self.buf & ((1 << n) - 1);
+        self.buf >>= n;
+        self.len -= n;
+        Ok(value)
+    }
+
+    // Drops the rest of the current byte, handing back whole bytes `peek` buffered ahead.
+    fn align(&mut self) {
+        self.pos -= (self.len / 8) as usize;
+        self.buf = 0;
+        self.len = 0;
+    }
+
+    fn remaining(&self) -> &'a [u8] {
+        &self.data[self.pos..]
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], &'static str> {
+        let bytes = self
+            .data
+            .get(self.pos..self.pos + n)
+            .ok_or("unexpected end of data")?;
+        self.pos += n;
+        Ok(bytes)
+    }
+}
+
+struct Huffman {
+    // Number of codes of each length.
+    counts: [u16; MAX_BITS + 1],
+    // Symbols ordered by code.
+    symbols: Vec<u16>,
+    // Indexed by the next `FAST_BITS` bits of the stream: `symbol << 4 | length` for codes of at
+    // most `FAST_BITS` bits, 0 where the code is longer.
+    fast: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Result<Self, &'static str> {
+        let mut counts = [0u16; MAX_BITS + 1];
+        for &len in lengths {
+            counts[usize::from(len)] += 1;
+        }
+        counts[0] = 0;
+        let mut left = 1i32;
+        for &count in &counts[1..] {
+            left = (left << 1) - i32::from(count);
+            if left < 0 {
+                return Err("over-subscribed Huffman code");
+            }
+        }
+
+        let mut offsets = [0u16; MAX_BITS + 1];
+        for len in 1..MAX_BITS {
+            offsets[len + 1] = offsets[len] + counts[len];
+        }
+        let mut symbols = alloc::vec![0u16; lengths.len()];
+        for (symbol, &len) in lengths.iter().enumerate() {
+            if len != 0 {
+                let slot = &mut offsets[usize::from(len)];
+                symbols[usize::from(*slot)] = symbol as u16;
+                *slot += 1;
+            }
+        }
+
+        // Walks the codes in canonical order. Codes are sent most significant bit first, so their
+        // table index is the code reversed, repeated for every value of the bits after it.
+        let mut fast = alloc::vec![0u16; 1 << FAST_BITS];
+        let (mut code, mut index) = (0u32, 0usize);
+        for len in 1..=FAST_BITS {
+            for _ in 0..counts[len as usize] {
+                let reversed = code.reverse_bits() >> (32 - len);
+                let entry = symbols[index] << 4 | len as u16;
+                for rest in 0..1 << (FAST_BITS - len) {
+                    fast[(reversed | rest << len) as usize] = entry;
+                }
+                code += 1;
+                index += 1;
+            }
+            code <<= 1;
+        }
+        Ok(Self {
+            counts,
+            symbols,
+            fast,
+        })
+    }
+
+    fn decode(&self, bits: &mut BitReader) -> Result<u16, &'static str> {
+        let (peeked, available) = bits.peek(FAST_BITS);
+        let entry = self.fast[peeked as usize];
+        let len = u32::from(entry & 0xF);
+        if len != 0 && len <= available {
+            bits.consume(len);
+            return Ok(entry >> 4);
+        }
+
+        // `code` is the bits read so far; `first` the first code of the current length and
+        // `index` the position of that code in `symbols`.
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for &count in &self.counts[1..] {
+            code |= bits.read(1)? as i32;
+            let count = i32::from(count);
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err("invalid Huffman code")
+    }
+}
+
+fn stored(bits: &mut BitReader, out: &mut Vec<u8>, limit: usize) -> Result<(), &'static str> {
+    bits.align();
+    let header = bits.take(4)?;
+    let len = u16::from_le_bytes([header[0], header[1]]);
+    let nlen = u16::from_le_bytes([header[2], header[3]]);
+    if len != !nlen {
+        return Err("stored block length check failed");
+    }
+    let len = usize::from(len);
+    if out.len() + len > limit {
+        return Err("more data than the image holds");
+    }
+    out.extend_from_slice(bits.take(len)?);
+    Ok(())
+}
+
+fn fixed_codes() -> Result<(Huffman, Huffman), &'static str> {
+    let mut lengths = [0u8; 288];
+    lengths[..144].fill(8);
+    lengths[144..256].fill(9);
+    lengths[256..280].fill(7);
+    lengths[280..].fill(8);
+    Ok((Huffman::new(&lengths)?, Huffman::new(&[5; 30])?))
+}
+
+fn dynamic_codes(bits: &mut BitReader) -> Result<(Huffman, Huffman), &'static str> {
+    let literals = bits.read(5)? as usize + 257;
+    let distances = bits.read(5)? as usize + 1;
+    let code_lengths = bits.read(4)? as usize + 4;
+    if literals > 286 || distances > 30 {
+        return Err("too many length or distance codes");
+    }
+
+    let mut lengths = [0u8; 19];
+    for &symbol in &CODE_LENGTH_ORDER[..code_lengths] {
+        lengths[symbol] = bits.read(3)? as u8;
+    }
+    let length_code = Huffman::new(&lengths)?;
+
+    let mut lengths = [0u8; 286 + 30];
+    let mut at = 0;
+    while at < literals + distances {
+        let symbol = length_code.decode(bits)?;
+        let (value, repeat) = match symbol {
+            0..=15 => (symbol as u8, 1),
+            16 => {
+                let previous = *at
+                    .checked_sub(1)
+                    .and_then(|i| lengths.get(i))
+                    .ok_or("length repeat with no previous length")?;
+                (previous, 3 + bits.read(2)?)
+            }
+            17 => (0, 3 + bits.read(3)?),
+            _ => (0, 11 + bits.read(7)?),
+        };
+        let end = at + repeat as usize;
+        if end > literals + distances {
+            return Err("code lengths overrun the code count");
+        }
+        lengths[at..end].fill(value);
+        at = end;
+    }
+    if lengths[256] == 0 {
+        return Err("missing end-of-block code");
+    }
+
+    Ok((
+        Huffman::new(&lengths[..literals])?,
+        Huffman::new(&lengths[literals..literals + distances])?,
+    ))
+}
+
+fn codes(
+    bits: &mut BitReader,
+    out: &mut Vec<u8>,
+    limit: usize,
+    literal: &Huffman,
+    distance: &Huffman,
+) -> Result<(), &'static str> {
+    loop {
+        let symbol = usize::from(literal.decode(bits)?);
+        if symbol < 256 {
+            if out.len() >= limit {
+                return Err("more data than the image holds");
+            }
+            out.push(symbol as u8);
+            continue;
+        }
+        if symbol == 256 {
+            return Ok(());
+        }
+
+        let index = symbol - 257;
+        if index >= LENGTH_BASE.len() {
+            return Err("invalid length symbol");
+        }
+        let len =
+            usize::from(LENGTH_BASE[index]) + bits.read(u32::from(LENGTH_EXTRA[index]))? as usize;
+        let index = usize::from(distance.decode(bits)?);
+        if index >= DIST_BASE.len() {
+            return Err("invalid distance symbol");
+        }
+        let dist =
+            usize::from(DIST_BASE[index]) + bits.read(u32::from(DIST_EXTRA[index]))? as usize;
+        if dist > out.len() {
+            return Err("distance reaches before the start of the data");
+        }
+        if out.len() + len > limit {
+            return Err("more data than the image holds");
+        }
+        // Byte by byte, since the copy may overlap the bytes it produces.
+        let start = out.len() - dist;
+        for i in 0..len {
+            out.push(out[start + i]);
+        }
+    }
+}
+
+pub(crate) fn adler32(data: &[u8]) -> u32 {
+    const MOD: u32 = 65521;
+    let (mut a, mut b) = (1u32, 0u32);
+    // 5552 bytes is the most that can be summed before `b` could overflow.
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += u32::from(byte);
+            b += a;
+        }
+        a %= MOD;
+        b %= MOD;
+    }
+    b << 16 | a
+}
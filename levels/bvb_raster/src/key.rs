@@ -0,0 +1,203 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::{Canvas, Rgba};
+
+// Bounds of the adaptive tolerance, as RGB distances.
+const MIN_ADAPTIVE_TOLERANCE: u32 = 30;
+const MAX_ADAPTIVE_TOLERANCE: u32 = 80;
+
+/// How `Canvas::chroma_key` picks the background to remove.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyOptions {
+    /// Background colour; `None` averages the opaque border pixels. Alpha is ignored.
+    pub key: Option<Rgba>,
+    /// RGB distance up to which a pixel is fully keyed. `None` adapts it to how much the border
+    /// varies around the key, between 30 and 80.
+    pub tolerance: Option<u16>,
+    /// Width of the distance band past the tolerance over which alpha ramps back up to opaque.
+    /// 0 gives hard edges.
+    pub softness: u16,
+    /// Only key background connected to the image border, so key-coloured interiors survive.
+    pub connected: bool,
+    /// Removes the key colour blended into soft-edged pixels, so edges do not keep a halo.
+    pub despill: bool,
+}
+
+impl Default for KeyOptions {
+    fn default() -> Self {
+        Self {
+            key: None,
+            tolerance: None,
+            softness: 16,
+            connected: true,
+            despill: true,
+        }
+    }
+}
+
+/// What `Canvas::chroma_key` did, for flagging bad imports.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct KeyReport {
+    /// The key colour used, estimated or explicit. Fully transparent when nothing was keyed
+    /// because the border has no opaque pixels to estimate from.
+    pub key: Rgba,
+    pub tolerance: u16,
+    /// Pixels made fully transparent.
+    pub keyed: usize,
+    /// Pixels given partial alpha in the soft edge band.
+    pub softened: usize,
+    /// Softened pixels whose colour was despilled.
+    pub despilled: usize,
+    pub pixels: usize,
+}
+
+impl KeyReport {
+    /// Share of the image made fully transparent. Close to 0 usually means the wrong key colour;
+    /// close to 1 means the key ate the sprite.
+    pub fn keyed_fraction(&self) -> f32 {
+        self.keyed as f32 / self.pixels.max(1) as f32
+    }
+}
+
+impl Canvas {
+    /// Makes the background transparent by colour distance to a key colour.
+    ///
+    /// Pixels within the tolerance become transparent and pixels in the soft band past it get
+    /// alpha proportional to how far past the tolerance they are. Already transparent pixels
+    /// count as background for `connected` keying but are not counted in the report.
+    pub fn chroma_key(&mut self, options: &KeyOptions) -> KeyReport {
+        let (w, h) = (self.width as usize, self.height as usize);
+        let mut report = KeyReport {
+            pixels: w * h,
+            ..KeyReport::default()
+        };
+        let border = border_indices(w, h);
+        let key = match options.key {
+            Some(key) => [key[0], key[1], key[2]],
+            None => match self.border_average(&border) {
+                Some(key) => key,
+                None => return report,
+            },
+        };
+        let tolerance = match options.tolerance {
+            Some(tolerance) => u32::from(tolerance),
+            None => self.adaptive_tolerance(&border, key),
+        };
+        let softness = u32::from(options.softness);
+        report.key = [key[0], key[1], key[2], 255];
+        report.tolerance = tolerance as u16;
+
+        let distance = |px: &[u8]| distance_sq(px, key).isqrt();
+        // Pixels the key may touch: transparent already, or within the soft band.
+        let matte = |px: &[u8]| px[3] == 0 || distance(px) <= tolerance + softness;
+        let mut inside = vec![!options.connected; w * h];
+        if options.connected {
+            let mut stack: Vec<usize> = border
+                .iter()
+                .copied()
+                .filter(|&i| matte(&self.pixels[i * 4..i * 4 + 4]))
+                .collect();
+            for &i in &stack {
+                inside[i] = true;
+            }
+            while let Some(i) = stack.pop() {
+                let (x, y) = (i % w, i / w);
+                let neighbours = [
+                    (x > 0).then(|| i - 1),
+                    (x + 1 < w).then(|| i + 1),
+                    (y > 0).then(|| i - w),
+                    (y + 1 < h).then(|| i + w),
+                ];
+                for n in neighbours.into_iter().flatten() {
+                    if !inside[n] && matte(&self.pixels[n * 4..n * 4 + 4]) {
+                        inside[n] = true;
+                        stack.push(n);
+                    }
+                }
+            }
+        }
+
+        for (i, px) in self.pixels.chunks_exact_mut(4).enumerate() {
+            if !inside[i] || px[3] == 0 {
+                continue;
+            }
+            let d = distance(px);
+            if d <= tolerance {
+                px[3] = 0;
+                report.keyed += 1;
+            } else if d <= tolerance + softness {
+                // `past / softness` of the pixel is foreground, the rest is blended-in key.
+                let past = d - tolerance;
+                px[3] = (u32::from(px[3]) * past / softness) as u8;
+                report.softened += 1;
+                if options.despill {
+                    for channel in 0..3 {
+                        let mixed = i64::from(px[channel]) * i64::from(softness);
+                        let spill = i64::from(key[channel]) * i64::from(softness - past);
+                        px[channel] = ((mixed - spill) / i64::from(past)).clamp(0, 255) as u8;
+                    }
+                    report.despilled += 1;
+                }
+            }
+        }
+        report
+    }
+
+    fn border_average(&self, border: &[usize]) -> Option<[u8; 3]> {
+        let mut sum = [0u64; 3];
+        let mut count = 0u64;
+        for &i in border {
+            let px = &self.pixels[i * 4..i * 4 + 4];
+            if px[3] == 0 {
+                continue;
+            }
+            for channel in 0..3 {
+                sum[channel] += u64::from(px[channel]);
+            }
+            count += 1;
+        }
+        (count > 0).then(|| sum.map(|s| (s / count) as u8))
+    }
+
+    // Widens the tolerance when the border is noisy, e.g. a gradient or a compressed backdrop.
+    fn adaptive_tolerance(&self, border: &[usize], key: [u8; 3]) -> u32 {
+        let spread = border
+            .iter()
+            .map(|&i| &self.pixels[i * 4..i * 4 + 4])
+            .filter(|px| px[3] != 0)
+            .map(|px| distance_sq(px, key))
+            .max()
+            .unwrap_or(0);
+        let min = MIN_ADAPTIVE_TOLERANCE * MIN_ADAPTIVE_TOLERANCE;
+        let max = MAX_ADAPTIVE_TOLERANCE * MAX_ADAPTIVE_TOLERANCE;
+        (spread + min).clamp(min, max).isqrt()
+    }
+}
+
+fn distance_sq(px: &[u8], key: [u8; 3]) -> u32 {
+    (0..3)
+        .map(|channel| {
+            let d = i32::from(px[channel]) - i32::from(key[channel]);
+            (d * d) as u32
+        })
+        .sum()
+}
+
+// Pixel indices along the image edges, each once.
+fn border_indices(w: usize, h: usize) -> Vec<usize> {
+    if w == 0 || h == 0 {
+        return Vec::new();
+    }
+    let mut border: Vec<usize> = (0..w).collect();
+    if h > 1 {
+        border.extend((0..w).map(|x| (h - 1) * w + x));
+    }
+    for y in 1..h.saturating_sub(1) {
+        border.push(y * w);
+        if w > 1 {
+            border.push(y * w + w - 1);
+        }
+    }
+    border
+}
@@ -0,0 +1,522 @@
+// Software rasteriser shared by the level modules.
+//
+// Levels draw their maps on the CPU into a `Canvas` and export the pixels; nothing here depends on a
+// particular level's size. See `docs/levels.md`.
+//
+// `no_std` with `alloc`, so level modules can use it without pulling in the standard library.
+
+#![no_std]
+
+extern crate alloc;
+
+mod bmp;
+mod deflate;
+mod inflate;
+mod key;
+mod place;
+mod png;
+mod resample;
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+pub use bmp::{BmpError, decode_bmp};
+pub use key::{KeyOptions, KeyReport};
+pub use place::{Pivot, Placement, Shadow};
+pub use png::{EncodeError, PngError, decode_png, encode_apng, encode_png};
+pub use resample::{Anchor, Filter};
+
+pub type Rgba = [u8; 4];
+
+pub const fn rgba(r: u8, g: u8, b: u8) -> Rgba {
+    [r, g, b, 255]
+}
+
+/// Quarter turns applied by `Canvas::blit`, clockwise.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Rotation {
+    #[default]
+    None,
+    Cw90,
+    Cw180,
+    Cw270,
+}
+
+/// Orientation of a blitted sprite: rotated first, then mirrored.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Transform {
+    pub rotation: Rotation,
+    pub flip_x: bool,
+    pub flip_y: bool,
+}
+
+impl Transform {
+    pub const IDENTITY: Self = Self {
+        rotation: Rotation::None,
+        flip_x: false,
+        flip_y: false,
+    };
+
+    // Size of a `width` x `height` source once transformed.
+    fn output_size(&self, width: u32, height: u32) -> (u32, u32) {
+        match self.rotation {
+            Rotation::None | Rotation::Cw180 => (width, height),
+            Rotation::Cw90 | Rotation::Cw270 => (height, width),
+        }
+    }
+
+    // Source pixel shown at `(u, v)` of the transformed output.
+    fn source(&self, u: u32, v: u32, width: u32, height: u32) -> (u32, u32) {
+        let (out_w, out_h) = self.output_size(width, height);
+        let u = if self.flip_x { out_w - 1 - u } else { u };
+        let v = if self.flip_y { out_h - 1 - v } else { v };
+        match self.rotation {
+            Rotation::None => (u, v),
+            Rotation::Cw90 => (v, height - 1 - u),
+            Rotation::Cw180 => (width - 1 - u, height - 1 - v),
+            Rotation::Cw270 => (width - 1 - v, u),
+        }
+    }
+}
+
+/// Fill patterns, evaluated in canvas coordinates so neighbouring fills line up.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pattern {
+    Solid(Rgba),
+    /// Alternates `a` and `b` like a chess board.
+    Checker(Rgba, Rgba),
+    /// 4x4 ordered dither showing `b` on `level` of every 16 pixels (0 all `a`, 16 all `b`).
+    Bayer4 {
+        a: Rgba,
+        b: Rgba,
+        level: u8,
+    },
+    /// Horizontal bands of `a` and `b`, each `height` pixels tall.
+    Stripes {
+        a: Rgba,
+        b: Rgba,
+        height: u8,
+    },
+}
+
+const BAYER4: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+impl Pattern {
+    pub fn color_at(&self, x: i32, y: i32) -> Rgba {
+        match *self {
+            Pattern::Solid(c) => c,
+            Pattern::Checker(a, b) => {
+                if (x + y) & 1 == 0 {
+                    a
+                } else {
+                    b
+                }
+            }
+            Pattern::Bayer4 { a, b, level } => {
+                let threshold = BAYER4[(y & 3) as usize][(x & 3) as usize];
+                if threshold < level { b } else { a }
+            }
+            Pattern::Stripes { a, b, height } => {
+                let band = y.div_euclid(i32::from(height.max(1)));
+                if band & 1 == 0 { a } else { b }
+            }
+        }
+    }
+}
+
+/// RGBA8 image with clipped drawing primitives.
+///
+/// Drawing coordinates are in units of `scale` image pixels, so a level can draw once in its own
+/// coordinates and render at 2x or 3x: rectangles and blocks scale exactly, while circles and
+/// polygons are rasterised at the full image resolution and stay crisp. Sprites passed to `blit`
+/// are in image pixels.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Canvas {
+    /// Image size in pixels.
+    pub width: u32,
+    pub height: u32,
+    /// RGBA8, row-major.
+    pub pixels: Vec<u8>,
+    scale: u32,
+}
+
+impl Canvas {
+    /// Transparent canvas at scale 1.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self::with_scale(width, height, 1)
+    }
+
+    /// Transparent canvas of `width` x `height` drawing units, each `scale` x `scale` image pixels.
+    pub fn with_scale(width: u32, height: u32, scale: u32) -> Self {
+        let scale = scale.max(1);
+        let (width, height) = (width * scale, height * scale);
+        Self {
+            width,
+            height,
+            pixels: vec![0u8; (width * height * 4) as usize],
+            scale,
+        }
+    }
+
+    pub fn from_rgba(width: u32, height: u32, pixels: Vec<u8>) -> Result<Self, String> {
+        let expected = (width as usize) * (height as usize) * 4;
+        if pixels.len() != expected {
+            return Err(format!(
+                "canvas has {} bytes, expected {expected} for {width}x{height}",
+                pixels.len()
+            ));
+        }
+        Ok(Self {
+            width,
+            height,
+            pixels,
+            scale: 1,
+        })
+    }
+
+    pub fn scale(&self) -> u32 {
+        self.scale
+    }
+
+    /// Pixel at image coordinates.
+    pub fn pixel(&self, x: u32, y: u32) -> Option<Rgba> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        let i = ((y * self.width + x) * 4) as usize;
+        let mut c = [0u8; 4];
+        c.copy_from_slice(&self.pixels[i..i + 4]);
+        Some(c)
+    }
+
+    pub fn clear(&mut self, c: Rgba) {
+        for px in self.pixels.chunks_exact_mut(4) {
+            px.copy_from_slice(&c);
+        }
+    }
+
+    #[inline]
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+            return None;
+        }
+        Some(((y as u32 * self.width + x as u32) * 4) as usize)
+    }
+
+    #[inline]
+    fn put_image(&mut self, x: i32, y: i32, c: Rgba) {
+        if let Some(i) = self.index(x, y) {
+            self.pixels[i..i + 4].copy_from_slice(&c);
+        }
+    }
+
+    #[inline]
+    fn blend_image(&mut self, x: i32, y: i32, c: Rgba) {
+        if let Some(i) = self.index(x, y) {
+            blend_pixel(&mut self.pixels[i..i + 4], c);
+        }
+    }
+
+    // Fills image pixels `x0..x1` x `y0..y1`, clipped to the canvas.
+    fn fill_image_rect(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, c: Rgba) {
+        let (x0, x1) = (x0.max(0), x1.min(self.width as i32));
+        let (y0, y1) = (y0.max(0), y1.min(self.height as i32));
+        for y in y0..y1 {
+            for x in x0..x1 {
+                self.put_image(x, y, c);
+            }
+        }
+    }
+
+    // Twice the offset of an image pixel centre from the centre of drawing unit `center`, in image
+    // pixels, so shape tests stay in integers.
+    #[inline]
+    fn centre_offset2(&self, image: i32, center: i32) -> i32 {
+        let s = self.scale as i32;
+        2 * image + 1 - s - 2 * s * center
+    }
+
+    pub fn put(&mut self, x: i32, y: i32, c: Rgba) {
+        self.fill_rect(x, y, 1, 1, c);
+    }
+
+    /// Alpha-blends `c` over one drawing unit.
+    pub fn blend(&mut self, x: i32, y: i32, c: Rgba) {
+        let s = self.scale as i32;
+        for yy in (y * s)..((y + 1) * s) {
+            for xx in (x * s)..((x + 1) * s) {
+                self.blend_image(xx, yy, c);
+            }
+        }
+    }
+
+    pub fn fill_rect(&mut self, x: i32, y: i32, w: i32, h: i32, c: Rgba) {
+        let s = self.scale as i32;
+        self.fill_image_rect(x * s, y * s, (x + w) * s, (y + h) * s, c);
+    }
+
+    pub fn fill_rect_pattern(&mut self, x: i32, y: i32, w: i32, h: i32, pattern: &Pattern) {
+        for yy in y..(y + h) {
+            for xx in x..(x + w) {
+                self.put(xx, yy, pattern.color_at(xx, yy));
+            }
+        }
+    }
+
+    /// One-unit border just inside the rectangle.
+    pub fn rect_outline(&mut self, x: i32, y: i32, w: i32, h: i32, c: Rgba) {
+        if w <= 0 || h <= 0 {
+            return;
+        }
+        self.fill_rect(x, y, w, 1, c);
+        self.fill_rect(x, y + h - 1, w, 1, c);
+        self.fill_rect(x, y, 1, h, c);
+        self.fill_rect(x + w - 1, y, 1, h, c);
+    }
+
+    /// Every unit whose centre lies within `radius` of unit `(cx, cy)`'s centre.
+    pub fn fill_circle(&mut self, cx: i32, cy: i32, radius: i32, c: Rgba) {
+        self.fill_ring(cx, cy, radius, 0, c);
+    }
+
+    /// Units between `inner` and `outer` (both inclusive) from unit `(cx, cy)`'s centre.
+    pub fn fill_ring(&mut self, cx: i32, cy: i32, outer: i32, inner: i32, c: Rgba) {
+        let s = self.scale as i32;
+        let ro2 = (2 * s * outer) * (2 * s * outer);
+        let ri2 = (2 * s * inner) * (2 * s * inner);
+        for y in ((cy - outer) * s)..((cy + outer + 1) * s) {
+            let dy = self.centre_offset2(y, cy);
+            let dy2 = dy * dy;
+            for x in ((cx - outer) * s)..((cx + outer + 1) * s) {
+                let dx = self.centre_offset2(x, cx);
+                let d2 = (dx * dx) + dy2;
+                if d2 <= ro2 && d2 >= ri2 {
+                    self.put_image(x, y, c);
+                }
+            }
+        }
+    }
+
+    /// Bresenham line including both end points.
+    pub fn line(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, c: Rgba) {
+        let (dx, dy) = ((x1 - x0).abs(), -(y1 - y0).abs());
+        let (sx, sy) = ((x1 - x0).signum(), (y1 - y0).signum());
+        let (mut x, mut y) = (x0, y0);
+        let mut err = dx + dy;
+        loop {
+            self.put(x, y, c);
+            if x == x1 && y == y1 {
+                break;
+            }
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x += sx;
+            }
+            if e2 <= dx {
+                err += dx;
+                y += sy;
+            }
+        }
+    }
+
+    /// Fills the polygon with the even-odd rule. Vertices are unit corners, so `(0, 0), (2, 0),
+    /// (2, 2), (0, 2)` covers units `0..2` x `0..2`; image pixels are filled when their centre is
+    /// inside.
+    pub fn fill_polygon(&mut self, points: &[(i32, i32)], c: Rgba) {
+        if points.len() < 3 {
+            return;
+        }
+        let s = self.scale as i32;
+        let scaled: Vec<(f64, f64)> = points
+            .iter()
+            .map(|&(x, y)| (f64::from(x * s), f64::from(y * s)))
+            .collect();
+        let y_start = points.iter().map(|p| p.1 * s).min().unwrap_or(0).max(0);
+        let y_end = points
+            .iter()
+            .map(|p| p.1 * s)
+            .max()
+            .unwrap_or(0)
+            .min(self.height as i32);
+        let mut crossings = Vec::new();
+        for y in y_start..y_end {
+            let yc = f64::from(y) + 0.5;
+            crossings.clear();
+            for (i, &(x0, y0)) in scaled.iter().enumerate() {
+                let (x1, y1) = scaled[(i + 1) % scaled.len()];
+                if (y0 <= yc) != (y1 <= yc) {
+                    crossings.push(x0 + (yc - y0) * (x1 - x0) / (y1 - y0));
+                }
+            }
+            crossings.sort_by(f64::total_cmp);
+            for span in crossings.chunks_exact(2) {
+                // Pixels whose centre `x + 0.5` lies in `span[0]..span[1]`.
+                let x0 = ceil(span[0] - 0.5);
+                let x1 = ceil(span[1] - 0.5);
+                self.fill_image_rect(x0, y, x1, y + 1, c);
+            }
+        }
+    }
+
+    /// Replaces the 4-connected region of image pixels matching the colour under unit `(x, y)`.
+    /// Returns the number of image pixels changed.
+    pub fn flood_fill(&mut self, x: i32, y: i32, c: Rgba) -> usize {
+        let s = self.scale as i32;
+        let (x, y) = (x * s, y * s);
+        let Some(start) = self.index(x, y) else {
+            return 0;
+        };
+        let mut from = [0u8; 4];
+        from.copy_from_slice(&self.pixels[start..start + 4]);
+        if from == c {
+            return 0;
+        }
+        let mut filled = 0;
+        let mut stack = vec![(x, y)];
+        while let Some((x, y)) = stack.pop() {
+            let Some(i) = self.index(x, y) else {
+                continue;
+            };
+            if self.pixels[i..i + 4] != from {
+                continue;
+            }
+            self.pixels[i..i + 4].copy_from_slice(&c);
+            filled += 1;
+            stack.extend([(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)]);
+        }
+        filled
+    }
+
+    /// Alpha-blends `sprite` (image pixels) with its transformed top-left corner at unit `(x, y)`.
+    /// Fully transparent sprite pixels are skipped.
+    pub fn blit(&mut self, sprite: &Canvas, x: i32, y: i32, transform: Transform) {
+        let s = self.scale as i32;
+        self.blit_image(sprite, x * s, y * s, transform);
+    }
+
+    /// Blits `sprite` horizontally centred on unit `center_x` with its bottom edge at unit `bottom_y`.
+    pub fn blit_center_bottom(
+        &mut self,
+        sprite: &Canvas,
+        center_x: i32,
+        bottom_y: i32,
+        transform: Transform,
+    ) {
+        let s = self.scale as i32;
+        let (w, h) = transform.output_size(sprite.width, sprite.height);
+        self.blit_image(
+            sprite,
+            center_x * s - (w as i32 / 2),
+            bottom_y * s - h as i32,
+            transform,
+        );
+    }
+
+    fn blit_image(&mut self, sprite: &Canvas, x: i32, y: i32, transform: Transform) {
+        let (w, h) = transform.output_size(sprite.width, sprite.height);
+        for v in 0..h {
+            let dy = y + v as i32;
+            if !(0..self.height as i32).contains(&dy) {
+                continue;
+            }
+            for u in 0..w {
+                let dx = x + u as i32;
+                if !(0..self.width as i32).contains(&dx) {
+                    continue;
+                }
+                let (sx, sy) = transform.source(u, v, sprite.width, sprite.height);
+                let Some(c) = sprite.pixel(sx, sy) else {
+                    continue;
+                };
+                if c[3] != 0 {
+                    self.blend_image(dx, dy, c);
+                }
+            }
+        }
+    }
+
+    /// Smallest sub-image holding every pixel with alpha above `threshold`, or `None` if there is
+    /// none.
+    pub fn crop_to_alpha(&self, threshold: u8) -> Option<Canvas> {
+        let (w, h) = (self.width, self.height);
+        let mut min_x = w;
+        let mut min_y = h;
+        let mut max_x = 0;
+        let mut max_y = 0;
+        let mut found = false;
+
+        for y in 0..h {
+            for x in 0..w {
+                if self.pixels[((y * w + x) * 4 + 3) as usize] > threshold {
+                    found = true;
+                    min_x = min_x.min(x);
+                    min_y = min_y.min(y);
+                    max_x = max_x.max(x);
+                    max_y = max_y.max(y);
+                }
+            }
+        }
+        if !found {
+            return None;
+        }
+
+        let (nw, nh) = (max_x - min_x + 1, max_y - min_y + 1);
+        let mut out = Canvas::new(nw, nh);
+        for y in 0..nh {
+            let si = (((min_y + y) * w + min_x) * 4) as usize;
+            let di = ((y * nw) * 4) as usize;
+            let len = (nw * 4) as usize;
+            out.pixels[di..di + len].copy_from_slice(&self.pixels[si..si + len]);
+        }
+        Some(out)
+    }
+
+    /// Nearest-neighbour resample to `width` x `height` (each at least 1).
+    pub fn resize_nearest(&self, width: u32, height: u32) -> Canvas {
+        let (width, height) = (width.max(1), height.max(1));
+        let mut out = Canvas::new(width, height);
+        if self.width == 0 || self.height == 0 {
+            return out;
+        }
+        for y in 0..height {
+            let sy = (y * self.height) / height;
+            for x in 0..width {
+                let sx = (x * self.width) / width;
+                let si = ((sy * self.width + sx) * 4) as usize;
+                let di = ((y * width + x) * 4) as usize;
+                out.pixels[di..di + 4].copy_from_slice(&self.pixels[si..si + 4]);
+            }
+        }
+        out
+    }
+}
+
+/// Source-over blend of `src` onto the RGBA pixel `dst`, both straight alpha. Over an opaque
+/// destination this is the usual `src * a + dst * (1 - a)`; over transparent layer canvases the
+/// colour is divided by the output alpha, so it keeps its brightness.
+pub fn blend_pixel(dst: &mut [u8], src: Rgba) {
+    let sa = u32::from(src[3]);
+    if sa == 0 {
+        return;
+    }
+    let inv = 255 - sa;
+    // Weights in 255ths of 255ths, so an opaque destination blends exactly as `/ 255` would.
+    let src_weight = sa * 255;
+    let dst_weight = u32::from(dst[3]) * inv;
+    let total = src_weight + dst_weight;
+    for channel in 0..3 {
+        dst[channel] = ((u32::from(src[channel]) * src_weight
+            + u32::from(dst[channel]) * dst_weight)
+            / total) as u8;
+    }
+    dst[3] = (sa + u32::from(dst[3]) * inv / 255) as u8;
+}
+
+// `f64::ceil` lives in std; casts truncate towards zero, so only positive fractions need a bump.
+fn ceil(v: f64) -> i32 {
+    let t = v as i32;
+    if f64::from(t) < v { t + 1 } else { t }
+}
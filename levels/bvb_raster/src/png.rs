@@ -0,0 +1,751 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::Canvas;
+use crate::deflate::zlib_compress;
+use crate::inflate::zlib_decompress;
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+// Same cap as `decode_bmp`.
+const MAX_PIXELS: u64 = 1 << 26;
+
+// Colour types from the IHDR chunk.
+const GRAY: u8 = 0;
+const RGB: u8 = 2;
+const INDEXED: u8 = 3;
+const GRAY_ALPHA: u8 = 4;
+const RGBA: u8 = 6;
+
+// Adam7 passes as (x start, y start, x step, y step).
+const ADAM7: [(usize, usize, usize, usize); 7] = [
+    (0, 0, 8, 8),
+    (4, 0, 8, 8),
+    (0, 4, 4, 8),
+    (2, 0, 4, 4),
+    (0, 2, 2, 4),
+    (1, 0, 2, 2),
+    (0, 1, 1, 2),
+];
+
+/// Why `decode_png` rejected a file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PngError {
+    /// The file does not start with the PNG signature.
+    NotPng,
+    /// The file ends before `what`.
+    Truncated {
+        what: &'static str,
+    },
+    ChunkCrc {
+        chunk: [u8; 4],
+    },
+    /// A required chunk (IHDR, PLTE for palettized images, IDAT) is absent or out of order.
+    MissingChunk {
+        chunk: [u8; 4],
+    },
+    /// A chunk has the wrong length or content for the image.
+    InvalidChunk {
+        chunk: [u8; 4],
+    },
+    /// A critical chunk this decoder does not know, which the PNG spec forbids skipping.
+    UnknownCriticalChunk {
+        chunk: [u8; 4],
+    },
+    InvalidDimensions {
+        width: u32,
+        height: u32,
+    },
+    UnsupportedFormat {
+        color_type: u8,
+        bit_depth: u8,
+    },
+    /// Only compression method 0, filter method 0 and interlace methods 0 (none) and 1 (Adam7)
+    /// exist.
+    UnsupportedMethod {
+        compression: u8,
+        filter: u8,
+        interlace: u8,
+    },
+    /// The IDAT stream is not valid zlib data.
+    InvalidZlib {
+        reason: &'static str,
+    },
+    /// The image data decompressed to the wrong number of bytes.
+    ImageDataLength {
+        expected: usize,
+        actual: usize,
+    },
+    InvalidFilter {
+        row: usize,
+        filter: u8,
+    },
+    PaletteIndex {
+        index: u8,
+        palette_len: usize,
+    },
+}
+
+impl fmt::Display for PngError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PngError::NotPng => write!(f, "not a PNG file (missing signature)"),
+            PngError::Truncated { what } => write!(f, "PNG file is truncated in the {what}"),
+            PngError::ChunkCrc { chunk } => {
+                write!(f, "PNG {} chunk fails its CRC check", chunk_name(chunk))
+            }
+            PngError::MissingChunk { chunk } => {
+                write!(f, "PNG is missing its {} chunk", chunk_name(chunk))
+            }
+            PngError::InvalidChunk { chunk } => {
+                write!(f, "invalid PNG {} chunk", chunk_name(chunk))
+            }
+            PngError::UnknownCriticalChunk { chunk } => {
+                write!(f, "unknown critical PNG chunk {}", chunk_name(chunk))
+            }
+            PngError::InvalidDimensions { width, height } => {
+                write!(f, "invalid PNG dimensions {width}x{height}")
+            }
+            PngError::UnsupportedFormat {
+                color_type,
+                bit_depth,
+            } => write!(
+                f,
+                "unsupported PNG format: colour type {color_type} at bit depth {bit_depth}"
+            ),
+            PngError::UnsupportedMethod {
+                compression,
+                filter,
+                interlace,
+            } => write!(
+                f,
+                "unsupported PNG methods: compression {compression}, filter {filter}, interlace {interlace}"
+            ),
+            PngError::InvalidZlib { reason } => write!(f, "invalid PNG image data: {reason}"),
+            PngError::ImageDataLength { expected, actual } => {
+                write!(f, "PNG image data is {actual} bytes, expected {expected}")
+            }
+            PngError::InvalidFilter { row, filter } => {
+                write!(f, "PNG row {row} has unknown filter type {filter}")
+            }
+            PngError::PaletteIndex { index, palette_len } => write!(
+                f,
+                "PNG palette index {index} is outside its {palette_len}-colour palette"
+            ),
+        }
+    }
+}
+
+impl core::error::Error for PngError {}
+
+fn chunk_name(chunk: &[u8; 4]) -> &str {
+    core::str::from_utf8(chunk).unwrap_or("(non-ASCII)")
+}
+
+struct Header {
+    width: usize,
+    height: usize,
+    bit_depth: u8,
+    color_type: u8,
+    interlaced: bool,
+}
+
+impl Header {
+    fn channels(&self) -> usize {
+        match self.color_type {
+            RGB => 3,
+            GRAY_ALPHA => 2,
+            RGBA => 4,
+            _ => 1,
+        }
+    }
+
+    fn bits_per_pixel(&self) -> usize {
+        self.channels() * usize::from(self.bit_depth)
+    }
+
+    fn row_len(&self, width: usize) -> usize {
+        (width * self.bits_per_pixel()).div_ceil(8)
+    }
+}
+
+fn read_header(data: &[u8]) -> Result<Header, PngError> {
+    if data.len() != 13 {
+        return Err(PngError::InvalidChunk { chunk: *b"IHDR" });
+    }
+    let width = u32::from_be_bytes([data[0], data[1], data[2], data[3]]);
+    let height = u32::from_be_bytes([data[4], data[5], data[6], data[7]]);
+    let [bit_depth, color_type, compression, filter, interlace] =
+        [8, 9, 10, 11, 12].map(|i| data[i]);
+    if width == 0 || height == 0 || u64::from(width) * u64::from(height) > MAX_PIXELS {
+        return Err(PngError::InvalidDimensions { width, height });
+    }
+    let depth_ok = match color_type {
+        GRAY => matches!(bit_depth, 1 | 2 | 4 | 8 | 16),
+        INDEXED => matches!(bit_depth, 1 | 2 | 4 | 8),
+        RGB | GRAY_ALPHA | RGBA => matches!(bit_depth, 8 | 16),
+        _ => false,
+    };
+    if !depth_ok {
+        return Err(PngError::UnsupportedFormat {
+            color_type,
+            bit_depth,
+        });
+    }
+    if compression != 0 || filter != 0 || interlace > 1 {
+        return Err(PngError::UnsupportedMethod {
+            compression,
+            filter,
+            interlace,
+        });
+    }
+    Ok(Header {
+        width: width as usize,
+        height: height as usize,
+        bit_depth,
+        color_type,
+        interlaced: interlace == 1,
+    })
+}
+
+/// Colour-keyed transparency from the tRNS chunk.
+#[derive(Default)]
+struct Transparency {
+    // Alpha per palette entry; entries past the end are opaque.
+    palette_alpha: Vec<u8>,
+    // Raw gray or RGB sample values that are fully transparent.
+    key: Option<[u16; 3]>,
+}
+
+fn read_transparency(
+    data: &[u8],
+    header: &Header,
+    palette_len: usize,
+) -> Result<Transparency, PngError> {
+    let invalid = PngError::InvalidChunk { chunk: *b"tRNS" };
+    let sample = |i: usize| u16::from_be_bytes([data[2 * i], data[2 * i + 1]]);
+    match header.color_type {
+        GRAY if data.len() == 2 => Ok(Transparency {
+            key: Some([sample(0); 3]),
+            ..Transparency::default()
+        }),
+        RGB if data.len() == 6 => Ok(Transparency {
+            key: Some([sample(0), sample(1), sample(2)]),
+            ..Transparency::default()
+        }),
+        INDEXED if data.len() <= palette_len => Ok(Transparency {
+            palette_alpha: data.to_vec(),
+            key: None,
+        }),
+        _ => Err(invalid),
+    }
+}
+
+/// Decodes a PNG file into an RGBA canvas.
+///
+/// Supports every standard colour type and bit depth: grayscale (1 to 16 bits), RGB, palettized
+/// (1 to 8 bits), grayscale with alpha and RGBA, interlaced or not. tRNS transparency applies to
+/// grayscale, RGB and palettized images. 16-bit samples are reduced to 8 bits and ancillary
+/// chunks such as gamma and text are ignored.
+pub fn decode_png(bytes: &[u8]) -> Result<Canvas, PngError> {
+    if bytes.get(..8) != Some(SIGNATURE.as_slice()) {
+        return Err(PngError::NotPng);
+    }
+
+    let mut header = None;
+    let mut palette: Vec<[u8; 4]> = Vec::new();
+    let mut transparency = Transparency::default();
+    let mut idat = Vec::new();
+    let mut at = 8;
+    loop {
+        let (kind, data, next) = read_chunk(bytes, at)?;
+        at = next;
+        if header.is_none() && &kind != b"IHDR" {
+            return Err(PngError::MissingChunk { chunk: *b"IHDR" });
+        }
+        match &kind {
+            b"IHDR" if header.is_none() => header = Some(read_header(data)?),
+            b"IHDR" => return Err(PngError::InvalidChunk { chunk: kind }),
+            b"PLTE" => {
+                if data.len() % 3 != 0 || data.len() > 3 * 256 || !idat.is_empty() {
+                    return Err(PngError::InvalidChunk { chunk: kind });
+                }
+                palette = data
+                    .chunks_exact(3)
+                    .map(|c| [c[0], c[1], c[2], 255])
+                    .collect();
+            }
+            b"tRNS" => {
+                let header = header.as_ref().expect("IHDR comes first");
+                transparency = read_transparency(data, header, palette.len())?;
+            }
+            b"IDAT" => idat.extend_from_slice(data),
+            b"IEND" => break,
+            _ if kind[0].is_ascii_uppercase() => {
+                return Err(PngError::UnknownCriticalChunk { chunk: kind });
+            }
+            _ => {}
+        }
+    }
+
+    let header = header.expect("IHDR comes first");
+    if idat.is_empty() {
+        return Err(PngError::MissingChunk { chunk: *b"IDAT" });
+    }
+    if header.color_type == INDEXED {
+        if palette.is_empty() {
+            return Err(PngError::MissingChunk { chunk: *b"PLTE" });
+        }
+        for (entry, &alpha) in palette.iter_mut().zip(&transparency.palette_alpha) {
+            entry[3] = alpha;
+        }
+    }
+
+    let passes = passes(&header);
+    let expected = passes
+        .iter()
+        .map(|&(_, _, _, _, w, h)| {
+            if w == 0 {
+                0
+            } else {
+                h * (1 + header.row_len(w))
+            }
+        })
+        .sum();
+    let data =
+        zlib_decompress(&idat, expected).map_err(|reason| PngError::InvalidZlib { reason })?;
+    if data.len() != expected {
+        return Err(PngError::ImageDataLength {
+            expected,
+            actual: data.len(),
+        });
+    }
+
+    let mut out = vec![0u8; header.width * header.height * 4];
+    let mut at = 0;
+    let mut row_index = 0;
+    for (x0, y0, dx, dy, w, h) in passes {
+        if w == 0 {
+            continue;
+        }
+        let row_len = header.row_len(w);
+        let mut previous = vec![0u8; row_len];
+        let mut row = vec![0u8; row_len];
+        for r in 0..h {
+            let filter = data[at];
+            row.copy_from_slice(&data[at + 1..at + 1 + row_len]);
+            at += 1 + row_len;
+            unfilter(
+                &mut row,
+                &previous,
+                filter,
+                header.bits_per_pixel().div_ceil(8),
+            )
+            .ok_or(PngError::InvalidFilter {
+                row: row_index,
+                filter,
+            })?;
+            row_index += 1;
+            for i in 0..w {
+                let pixel = to_rgba(&header, &row, i, &palette, &transparency)?;
+                let o = ((y0 + r * dy) * header.width + x0 + i * dx) * 4;
+                out[o..o + 4].copy_from_slice(&pixel);
+            }
+            core::mem::swap(&mut row, &mut previous);
+        }
+    }
+
+    Canvas::from_rgba(header.width as u32, header.height as u32, out).map_err(|_| {
+        PngError::InvalidDimensions {
+            width: header.width as u32,
+            height: header.height as u32,
+        }
+    })
+}
+
+// Returns the chunk type, its data and the offset of the next chunk.
+fn read_chunk(bytes: &[u8], at: usize) -> Result<([u8; 4], &[u8], usize), PngError> {
+    let truncated = PngError::Truncated { what: "chunk list" };
+    let head = bytes.get(at..at + 8).ok_or(truncated.clone())?;
+    let len = u32::from_be_bytes([head[0], head[1], head[2], head[3]]) as usize;
+    let kind = [head[4], head[5], head[6], head[7]];
+    let end = (at + 8).checked_add(len).ok_or(truncated.clone())?;
+    let data = bytes.get(at + 8..end).ok_or(truncated.clone())?;
+    let crc = bytes.get(end..end + 4).ok_or(truncated)?;
+    if u32::from_be_bytes([crc[0], crc[1], crc[2], crc[3]]) != crc32(&bytes[at + 4..end]) {
+        return Err(PngError::ChunkCrc { chunk: kind });
+    }
+    Ok((kind, data, end + 4))
+}
+
+// Sub-images as (x start, y start, x step, y step, width, height); one full pass when not interlaced.
+fn passes(header: &Header) -> Vec<(usize, usize, usize, usize, usize, usize)> {
+    let (w, h) = (header.width, header.height);
+    if !header.interlaced {
+        return vec![(0, 0, 1, 1, w, h)];
+    }
+    ADAM7
+        .iter()
+        .map(|&(x0, y0, dx, dy)| {
+            let pw = w.saturating_sub(x0).div_ceil(dx);
+            let ph = h.saturating_sub(y0).div_ceil(dy);
+            (x0, y0, dx, dy, if ph == 0 { 0 } else { pw }, ph)
+        })
+        .collect()
+}
+
+// Reverses the row filter in place; `bpp` is the byte distance to the previous pixel. Returns
+// `None` for an unknown filter type.
+fn unfilter(row: &mut [u8], previous: &[u8], filter: u8, bpp: usize) -> Option<()> {
+    match filter {
+        0 => {}
+        1 => {
+            for i in bpp..row.len() {
+                row[i] = row[i].wrapping_add(row[i - bpp]);
+            }
+        }
+        2 => {
+            for (byte, &up) in row.iter_mut().zip(previous) {
+                *byte = byte.wrapping_add(up);
+            }
+        }
+        3 => {
+            for i in 0..row.len() {
+                let left = if i >= bpp { u16::from(row[i - bpp]) } else { 0 };
+                let average = ((left + u16::from(previous[i])) / 2) as u8;
+                row[i] = row[i].wrapping_add(average);
+            }
+        }
+        4 => {
+            for i in 0..row.len() {
+                let (left, up_left) = if i >= bpp {
+                    (row[i - bpp], previous[i - bpp])
+                } else {
+                    (0, 0)
+                };
+                row[i] = row[i].wrapping_add(paeth(left, previous[i], up_left));
+            }
+        }
+        _ => return None,
+    }
+    Some(())
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = i16::from(a) + i16::from(b) - i16::from(c);
+    let (pa, pb, pc) = (
+        (p - i16::from(a)).abs(),
+        (p - i16::from(b)).abs(),
+        (p - i16::from(c)).abs(),
+    );
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+// Raw value of sample `index` in an unfiltered row.
+#[inline]
+fn sample(row: &[u8], index: usize, depth: u8) -> u16 {
+    match depth {
+        16 => u16::from_be_bytes([row[2 * index], row[2 * index + 1]]),
+        8 => u16::from(row[index]),
+        _ => {
+            let depth = usize::from(depth);
+            let bit = index * depth;
+            let shift = 8 - depth - bit % 8;
+            u16::from(row[bit / 8] >> shift) & ((1 << depth) - 1)
+        }
+    }
+}
+
+// Scales a raw sample to 0..=255.
+#[inline]
+fn to_u8(value: u16, depth: u8) -> u8 {
+    match depth {
+        16 => (value >> 8) as u8,
+        8 => value as u8,
+        _ => (u32::from(value) * 255 / ((1 << depth) - 1)) as u8,
+    }
+}
+
+fn to_rgba(
+    header: &Header,
+    row: &[u8],
+    x: usize,
+    palette: &[[u8; 4]],
+    transparency: &Transparency,
+) -> Result<[u8; 4], PngError> {
+    let depth = header.bit_depth;
+    let base = x * header.channels();
+    let s = |channel: usize| sample(row, base + channel, depth);
+    let keyed = |raw: [u16; 3]| {
+        if transparency.key == Some(raw) {
+            0
+        } else {
+            255
+        }
+    };
+    Ok(match header.color_type {
+        GRAY => {
+            let g = s(0);
+            let v = to_u8(g, depth);
+            [v, v, v, keyed([g; 3])]
+        }
+        RGB => {
+            let raw = [s(0), s(1), s(2)];
+            [
+                to_u8(raw[0], depth),
+                to_u8(raw[1], depth),
+                to_u8(raw[2], depth),
+                keyed(raw),
+            ]
+        }
+        INDEXED => {
+            let index = s(0) as u8;
+            *palette
+                .get(usize::from(index))
+                .ok_or(PngError::PaletteIndex {
+                    index,
+                    palette_len: palette.len(),
+                })?
+        }
+        GRAY_ALPHA => {
+            let v = to_u8(s(0), depth);
+            [v, v, v, to_u8(s(1), depth)]
+        }
+        _ => [s(0), s(1), s(2), s(3)].map(|v| to_u8(v, depth)),
+    })
+}
+
+const CRC_TABLE: [u32; 256] = crc_table();
+
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut c = n as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 {
+                0xEDB8_8320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+            k += 1;
+        }
+        table[n] = c;
+        n += 1;
+    }
+    table
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = u32::MAX;
+    for &byte in bytes {
+        crc = CRC_TABLE[((crc ^ u32::from(byte)) & 0xFF) as usize] ^ (crc >> 8);
+    }
+    !crc
+}
+
+/// Why `encode_apng` rejected its frames.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EncodeError {
+    /// An animated PNG needs at least one frame.
+    NoFrames,
+    DelayCount {
+        frames: usize,
+        delays: usize,
+    },
+    /// Frame `index` is not `width * height * 4` bytes of RGBA.
+    FrameSize {
+        index: usize,
+        len: usize,
+        width: u32,
+        height: u32,
+    },
+}
+
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EncodeError::NoFrames => write!(f, "an animated PNG needs at least one frame"),
+            EncodeError::DelayCount { frames, delays } => write!(
+                f,
+                "{frames} frames but {delays} delays, expected one delay per frame"
+            ),
+            EncodeError::FrameSize {
+                index,
+                len,
+                width,
+                height,
+            } => write!(
+                f,
+                "frame {index} has {len} bytes, expected {} for {width}x{height}",
+                *width as usize * *height as usize * 4
+            ),
+        }
+    }
+}
+
+impl core::error::Error for EncodeError {}
+
+/// Encodes tightly packed 8-bit RGBA pixels as a PNG file, with adaptive row filters and a
+/// fixed-Huffman deflate stream. Good enough for pixel art without pulling in a codec crate.
+///
+/// Panics when `rgba` holds fewer than `width * height` pixels.
+pub fn encode_png(width: u32, height: u32, rgba: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(rgba.len() / 2 + 64);
+    out.extend_from_slice(&SIGNATURE);
+    write_chunk(&mut out, b"IHDR", &rgba_header(width, height));
+    write_chunk(
+        &mut out,
+        b"IDAT",
+        &zlib_compress(&filter_rows(width, height, rgba)),
+    );
+    write_chunk(&mut out, b"IEND", &[]);
+    out
+}
+
+/// Encodes equally sized RGBA frames as an animated PNG that loops forever.
+///
+/// `delays_ms[i]` is how long frame `i` stays on screen. APNG stores delays as 16-bit fractions,
+/// so delays above 65535 ms are shortened to 65535 ms.
+pub fn encode_apng(
+    width: u32,
+    height: u32,
+    frames: &[&[u8]],
+    delays_ms: &[u32],
+) -> Result<Vec<u8>, EncodeError> {
+    if frames.is_empty() {
+        return Err(EncodeError::NoFrames);
+    }
+    if frames.len() != delays_ms.len() {
+        return Err(EncodeError::DelayCount {
+            frames: frames.len(),
+            delays: delays_ms.len(),
+        });
+    }
+    let expected_len = width as usize * height as usize * 4;
+    if let Some(index) = frames.iter().position(|rgba| rgba.len() != expected_len) {
+        return Err(EncodeError::FrameSize {
+            index,
+            len: frames[index].len(),
+            width,
+            height,
+        });
+    }
+
+    let mut out = Vec::new();
+    out.extend_from_slice(&SIGNATURE);
+    write_chunk(&mut out, b"IHDR", &rgba_header(width, height));
+
+    let mut actl = [0u8; 8];
+    actl[0..4].copy_from_slice(&(frames.len() as u32).to_be_bytes());
+    write_chunk(&mut out, b"acTL", &actl);
+
+    let mut sequence = 0u32;
+    for (index, (rgba, delay)) in frames.iter().zip(delays_ms).enumerate() {
+        let mut fctl = [0u8; 26];
+        fctl[0..4].copy_from_slice(&sequence.to_be_bytes());
+        fctl[4..8].copy_from_slice(&width.to_be_bytes());
+        fctl[8..12].copy_from_slice(&height.to_be_bytes());
+        // x/y offsets stay zero; the delay is a fraction of `delay` / 1000 seconds.
+        fctl[20..22].copy_from_slice(&((*delay).min(u32::from(u16::MAX)) as u16).to_be_bytes());
+        fctl[22..24].copy_from_slice(&1000u16.to_be_bytes());
+        write_chunk(&mut out, b"fcTL", &fctl);
+        sequence += 1;
+
+        let data = zlib_compress(&filter_rows(width, height, rgba));
+        if index == 0 {
+            write_chunk(&mut out, b"IDAT", &data);
+        } else {
+            let mut fdat = Vec::with_capacity(data.len() + 4);
+            fdat.extend_from_slice(&sequence.to_be_bytes());
+            fdat.extend_from_slice(&data);
+            write_chunk(&mut out, b"fdAT", &fdat);
+            sequence += 1;
+        }
+    }
+
+    write_chunk(&mut out, b"IEND", &[]);
+    Ok(out)
+}
+
+// IHDR for 8-bit RGBA without interlacing.
+fn rgba_header(width: u32, height: u32) -> [u8; 13] {
+    let mut header = [0u8; 13];
+    header[0..4].copy_from_slice(&width.to_be_bytes());
+    header[4..8].copy_from_slice(&height.to_be_bytes());
+    header[8] = 8;
+    header[9] = RGBA;
+    header
+}
+
+fn write_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = out.len();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    let crc = crc32(&out[start..]);
+    out.extend_from_slice(&crc.to_be_bytes());
+}
+
+// Picks the filter with the smallest sum of absolute residuals per row (the libpng heuristic).
+fn filter_rows(width: u32, height: u32, rgba: &[u8]) -> Vec<u8> {
+    let stride = width as usize * 4;
+    let height = height as usize;
+    assert!(
+        rgba.len() >= stride * height,
+        "pixel buffer is smaller than width * height * 4"
+    );
+
+    let zero_row = vec![0u8; stride];
+    let mut out = Vec::with_capacity((stride + 1) * height);
+    let mut candidate = vec![0u8; stride];
+    let mut best = vec![0u8; stride];
+
+    for y in 0..height {
+        let row = &rgba[y * stride..(y + 1) * stride];
+        let prev = if y == 0 {
+            &zero_row[..]
+        } else {
+            &rgba[(y - 1) * stride..y * stride]
+        };
+
+        let mut best_filter = 0u8;
+        let mut best_score = u64::MAX;
+        for filter in 0..5u8 {
+            for i in 0..stride {
+                let a = if i >= 4 { row[i - 4] } else { 0 };
+                let b = prev[i];
+                let c = if i >= 4 { prev[i - 4] } else { 0 };
+                let predicted = match filter {
+                    0 => 0,
+                    1 => a,
+                    2 => b,
+                    3 => ((u16::from(a) + u16::from(b)) / 2) as u8,
+                    _ => paeth(a, b, c),
+                };
+                candidate[i] = row[i].wrapping_sub(predicted);
+            }
+            let score: u64 = candidate
+                .iter()
+                .map(|&v| u64::from((v as i8).unsigned_abs()))
+                .sum();
+            if score < best_score {
+                best_score = score;
+                best_filter = filter;
+                best.copy_from_slice(&candidate);
+            }
+        }
+
+        out.push(best_filter);
+        out.extend_from_slice(&best);
+    }
+
+    out
+}
@@ -0,0 +1,239 @@
+use crate::{Canvas, Rgba};
+
+// Colours closer than this (RGBA distance) count as equal for the pixel-art scalers, so
+// compression noise does not break their edge detection.
+const SIMILAR_DISTANCE_SQ: u32 = 24 * 24;
+
+/// Scaling algorithm for `Canvas::resample`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Filter {
+    /// Nearest neighbour at any ratio, sampling source pixel centres.
+    #[default]
+    Nearest,
+    /// Nearest neighbour at a whole-number ratio: the largest integer upscale that fits, or the
+    /// smallest integer downscale. Every source pixel becomes the same number of pixels, at the
+    /// cost of not filling the target.
+    IntegerNearest,
+    /// Area average with premultiplied alpha. Meant for downscaling large art.
+    Box,
+    /// Scale2x (EPX) doubling while the result fits, then nearest for the rest.
+    Scale2x,
+    /// Like `Scale2x`, but corners are only rounded across weak diagonals and are blended
+    /// halfway instead of replaced, a reduced form of xBR.
+    XbrLite,
+}
+
+/// Where the scaled image sits inside the target when the aspect ratios differ.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    #[default]
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    // Offset of a `size`-wide span inside `room`, along x (`horizontal`) or y.
+    fn offset(self, room: u32, size: u32, horizontal: bool) -> u32 {
+        let slack = room.saturating_sub(size);
+        let (column, row) = match self {
+            Anchor::TopLeft => (0, 0),
+            Anchor::Top => (1, 0),
+            Anchor::TopRight => (2, 0),
+            Anchor::Left => (0, 1),
+            Anchor::Center => (1, 1),
+            Anchor::Right => (2, 1),
+            Anchor::BottomLeft => (0, 2),
+            Anchor::Bottom => (1, 2),
+            Anchor::BottomRight => (2, 2),
+        };
+        slack * if horizontal { column } else { row } / 2
+    }
+}
+
+impl Canvas {
+    /// Scales the image to fit inside `width` x `height` (each at least 1), keeping its aspect
+    /// ratio, and places it at `anchor`. Uncovered pixels are transparent.
+    pub fn resample(&self, width: u32, height: u32, filter: Filter, anchor: Anchor) -> Canvas {
+        let (width, height) = (width.max(1), height.max(1));
+        let mut out = Canvas::new(width, height);
+        if self.width == 0 || self.height == 0 {
+            return out;
+        }
+        let scaled = match filter {
+            Filter::Nearest => {
+                let (w, h) = fit(self.width, self.height, width, height);
+                self.resize_nearest(w, h)
+            }
+            Filter::IntegerNearest => self.integer_nearest(width, height),
+            Filter::Box => {
+                let (w, h) = fit(self.width, self.height, width, height);
+                self.resize_box(w, h)
+            }
+            Filter::Scale2x | Filter::XbrLite => {
+                let mut scaled = self.clone();
+                while scaled.width * 2 <= width && scaled.height * 2 <= height {
+                    scaled = scaled.double(filter == Filter::XbrLite);
+                }
+                let (w, h) = fit(scaled.width, scaled.height, width, height);
+                scaled.resize_nearest(w, h)
+            }
+        };
+
+        let x0 = anchor.offset(width, scaled.width, true);
+        let y0 = anchor.offset(height, scaled.height, false);
+        let len = (scaled.width * 4) as usize;
+        for y in 0..scaled.height {
+            let si = (y * scaled.width * 4) as usize;
+            let di = (((y0 + y) * width + x0) * 4) as usize;
+            out.pixels[di..di + len].copy_from_slice(&scaled.pixels[si..si + len]);
+        }
+        out
+    }
+
+    fn integer_nearest(&self, width: u32, height: u32) -> Canvas {
+        let (w, h) = (self.width, self.height);
+        if w <= width && h <= height {
+            let factor = (width / w).min(height / h);
+            return self.resize_nearest(w * factor, h * factor);
+        }
+        let divisor = w.div_ceil(width).max(h.div_ceil(height));
+        let (nw, nh) = (w.div_ceil(divisor), h.div_ceil(divisor));
+        let mut out = Canvas::new(nw, nh);
+        for y in 0..nh {
+            let sy = (y * divisor + divisor / 2).min(h - 1);
+            for x in 0..nw {
+                let sx = (x * divisor + divisor / 2).min(w - 1);
+                let si = ((sy * w + sx) * 4) as usize;
+                let di = ((y * nw + x) * 4) as usize;
+                out.pixels[di..di + 4].copy_from_slice(&self.pixels[si..si + 4]);
+            }
+        }
+        out
+    }
+
+    // Exact area coverage: source pixel `x` spans `x * width..(x + 1) * width` and target pixel
+    // `tx` spans `tx * self.width..(tx + 1) * self.width`, so overlaps are whole numbers.
+    fn resize_box(&self, width: u32, height: u32) -> Canvas {
+        let (w, h) = (self.width, self.height);
+        let mut out = Canvas::new(width, height);
+        for ty in 0..height {
+            let (top, bottom) = (ty * h, (ty + 1) * h);
+            for tx in 0..width {
+                let (left, right) = (tx * w, (tx + 1) * w);
+                // Premultiplied colour, alpha and coverage sums.
+                let mut color = [0u64; 3];
+                let (mut alpha, mut weight) = (0u64, 0u64);
+                for sy in top / height..bottom.div_ceil(height) {
+                    let cover_y = overlap(sy * height, (sy + 1) * height, top, bottom);
+                    for sx in left / width..right.div_ceil(width) {
+                        let cover = cover_y * overlap(sx * width, (sx + 1) * width, left, right);
+                        let i = ((sy * w + sx) * 4) as usize;
+                        let a = u64::from(self.pixels[i + 3]) * cover;
+                        for (sum, &v) in color.iter_mut().zip(&self.pixels[i..i + 3]) {
+                            *sum += u64::from(v) * a;
+                        }
+                        alpha += a;
+                        weight += cover;
+                    }
+                }
+                // Fully transparent areas stay transparent black.
+                if alpha == 0 {
+                    continue;
+                }
+                let di = ((ty * width + tx) * 4) as usize;
+                for (dst, sum) in out.pixels[di..di + 3].iter_mut().zip(color) {
+                    *dst = ((sum + alpha / 2) / alpha) as u8;
+                }
+                out.pixels[di + 3] = ((alpha + weight / 2) / weight) as u8;
+            }
+        }
+        out
+    }
+
+    // One Scale2x pass. Each pixel E with neighbours B (up), D (left), F (right) and H (down)
+    // becomes four, and a corner takes the colour of its two neighbours when they match each
+    // other but not the opposite sides.
+    fn double(&self, xbr: bool) -> Canvas {
+        let (w, h) = (self.width as i32, self.height as i32);
+        let mut out = Canvas::new(self.width * 2, self.height * 2);
+        let at = |x: i32, y: i32| -> Rgba {
+            let i = ((y.clamp(0, h - 1) * w + x.clamp(0, w - 1)) * 4) as usize;
+            [
+                self.pixels[i],
+                self.pixels[i + 1],
+                self.pixels[i + 2],
+                self.pixels[i + 3],
+            ]
+        };
+        for y in 0..h {
+            for x in 0..w {
+                let e = at(x, y);
+                let (b, d, f, hh) = (at(x, y - 1), at(x - 1, y), at(x + 1, y), at(x, y + 1));
+                // Per corner: the neighbours along y and x, the side each must differ from, and
+                // the diagonal pixel.
+                let corners = [
+                    (b, d, f, hh, at(x - 1, y - 1)),
+                    (b, f, d, hh, at(x + 1, y - 1)),
+                    (hh, d, f, b, at(x - 1, y + 1)),
+                    (hh, f, d, b, at(x + 1, y + 1)),
+                ];
+                for (k, (p, q, far_p, far_q, diagonal)) in corners.into_iter().enumerate() {
+                    let rounded = similar(p, q) && !similar(p, far_p) && !similar(q, far_q);
+                    let c = if !rounded {
+                        e
+                    } else if !xbr {
+                        p
+                    } else if !similar(e, p) && distance_sq(p, q) < distance_sq(e, diagonal) {
+                        mix(e, mix(p, q))
+                    } else {
+                        e
+                    };
+                    let (ox, oy) = (2 * x + (k as i32 & 1), 2 * y + (k as i32 >> 1));
+                    out.put(ox, oy, c);
+                }
+            }
+        }
+        out
+    }
+}
+
+// Largest size with the source aspect ratio inside `width` x `height`.
+fn fit(w: u32, h: u32, width: u32, height: u32) -> (u32, u32) {
+    let (w64, h64) = (u64::from(w), u64::from(h));
+    if w64 * u64::from(height) <= u64::from(width) * h64 {
+        let scaled = (w64 * u64::from(height) * 2 + h64) / (2 * h64);
+        ((scaled as u32).clamp(1, width), height)
+    } else {
+        let scaled = (h64 * u64::from(width) * 2 + w64) / (2 * w64);
+        (width, (scaled as u32).clamp(1, height))
+    }
+}
+
+fn overlap(a0: u32, a1: u32, b0: u32, b1: u32) -> u64 {
+    u64::from(a1.min(b1).saturating_sub(a0.max(b0)))
+}
+
+fn distance_sq(a: Rgba, b: Rgba) -> u32 {
+    a.iter()
+        .zip(&b)
+        .map(|(&a, &b)| {
+            let d = i32::from(a) - i32::from(b);
+            (d * d) as u32
+        })
+        .sum()
+}
+
+fn similar(a: Rgba, b: Rgba) -> bool {
+    distance_sq(a, b) <= SIMILAR_DISTANCE_SQ
+}
+
+fn mix(a: Rgba, b: Rgba) -> Rgba {
+    [0, 1, 2, 3].map(|i| (u16::from(a[i]) + u16::from(b[i])).div_ceil(2) as u8)
+}
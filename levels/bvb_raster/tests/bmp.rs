@@ -0,0 +1,151 @@
+use bvb_raster::{BmpError, decode_bmp};
+
+const ALIEN_BMP: &[u8] = include_bytes!("../../../assets/characters/alien_256.bmp");
+const DEMON_BMP: &[u8] = include_bytes!("../../../assets/characters/demon_256.bmp");
+
+// Builds a BMP with a `header_len`-byte info header; `extra` (masks or palette) follows the header.
+// Palettized images get `colors_used` from the palette length.
+fn bmp(
+    header_len: u32,
+    width: i32,
+    height: i32,
+    bpp: u16,
+    compression: u32,
+    extra: &[u8],
+    data: &[u8],
+) -> Vec<u8> {
+    let data_offset = 14 + header_len + extra.len() as u32;
+    let mut out = Vec::new();
+    out.extend_from_slice(b"BM");
+    out.extend_from_slice(&(data_offset + data.len() as u32).to_le_bytes());
+    out.extend_from_slice(&[0; 4]);
+    out.extend_from_slice(&data_offset.to_le_bytes());
+    let mut header = vec![0u8; header_len.max(40) as usize];
+    header[0..4].copy_from_slice(&header_len.to_le_bytes());
+    header[4..8].copy_from_slice(&width.to_le_bytes());
+    header[8..12].copy_from_slice(&height.to_le_bytes());
+    header[12..14].copy_from_slice(&1u16.to_le_bytes());
+    header[14..16].copy_from_slice(&bpp.to_le_bytes());
+    header[16..20].copy_from_slice(&compression.to_le_bytes());
+    if bpp <= 8 {
+        let colors_used = extra.len() as u32 / 4;
+        header[32..36].copy_from_slice(&colors_used.to_le_bytes());
+    }
+    out.extend_from_slice(&header[..header_len as usize]);
+    out.extend_from_slice(extra);
+    out.extend_from_slice(data);
+    out
+}
+
+// Palette of BGRX entries: index 0 black, 1 red, 2 green, 3 blue.
+const PALETTE: [u8; 16] = [0, 0, 0, 0, 0, 0, 255, 0, 0, 255, 0, 0, 255, 0, 0, 0];
+const BLACK: [u8; 4] = [0, 0, 0, 255];
+const RED: [u8; 4] = [255, 0, 0, 255];
+const GREEN: [u8; 4] = [0, 255, 0, 255];
+const BLUE: [u8; 4] = [0, 0, 255, 255];
+
+fn pixels(canvas: &bvb_raster::Canvas) -> Vec<[u8; 4]> {
+    canvas
+        .pixels
+        .chunks_exact(4)
+        .map(|p| [p[0], p[1], p[2], p[3]])
+        .collect()
+}
+
+#[test]
+fn decodes_the_shipped_character_art() {
+    let alien = decode_bmp(ALIEN_BMP).expect("24-bit BMP");
+    assert_eq!((alien.width, alien.height), (256, 256));
+    let demon = decode_bmp(DEMON_BMP).expect("32-bit V5 BMP");
+    assert_eq!((demon.width, demon.height), (256, 256));
+}
+
+#[test]
+fn decodes_palettized_rows() {
+    // 3x2, 8-bit, bottom-up, rows padded to 4 bytes.
+    let data = [3, 2, 1, 0, 0, 1, 2, 0];
+    let image = decode_bmp(&bmp(40, 3, 2, 8, 0, &PALETTE, &data)).unwrap();
+    assert_eq!(pixels(&image), [BLACK, RED, GREEN, BLUE, GREEN, RED]);
+
+    // 4x1 at 1 and 4 bits per pixel.
+    let one_bit = decode_bmp(&bmp(40, 4, 1, 1, 0, &PALETTE[..8], &[0b1010_0000, 0, 0, 0])).unwrap();
+    assert_eq!(pixels(&one_bit), [RED, BLACK, RED, BLACK]);
+    let four_bit = decode_bmp(&bmp(40, 4, 1, 4, 0, &PALETTE, &[0x12, 0x30, 0, 0])).unwrap();
+    assert_eq!(pixels(&four_bit), [RED, GREEN, BLUE, BLACK]);
+}
+
+#[test]
+fn decodes_rle8_and_rle4() {
+    // Bottom row: run of two reds, absolute run of green, blue, green (padded), end of line.
+    // Top row: delta skips one pixel, then one blue; end of bitmap.
+    let rle8 = [2, 1, 0, 3, 2, 3, 2, 0, 0, 0, 0, 2, 1, 0, 1, 3, 0, 1];
+    let image = decode_bmp(&bmp(40, 5, 2, 8, 1, &PALETTE, &rle8)).unwrap();
+    let clear = [0u8; 4];
+    assert_eq!(
+        pixels(&image),
+        [
+            clear, BLUE, clear, clear, clear, RED, RED, GREEN, BLUE, GREEN
+        ]
+    );
+
+    // Run of four alternating red/green nibbles, then absolute blue, red, blue.
+    let rle4 = [4, 0x12, 0, 3, 0x31, 0x30, 0, 1];
+    let image = decode_bmp(&bmp(40, 7, 1, 4, 2, &PALETTE, &rle4)).unwrap();
+    assert_eq!(pixels(&image), [RED, GREEN, RED, GREEN, BLUE, RED, BLUE]);
+}
+
+#[test]
+fn decodes_16_bit_and_header_masks() {
+    let masks_565: Vec<u8> = [0xF800u32, 0x07E0, 0x001F]
+        .iter()
+        .flat_map(|m| m.to_le_bytes())
+        .collect();
+    let data = [0x00, 0xF8, 0xE0, 0x07]; // red, green
+    let image = decode_bmp(&bmp(40, 2, 1, 16, 3, &masks_565, &data)).unwrap();
+    assert_eq!(pixels(&image), [RED, GREEN]);
+
+    // Without masks 16-bit is 5-5-5.
+    let image = decode_bmp(&bmp(40, 1, 1, 16, 0, &[], &[0x1F, 0x00, 0, 0])).unwrap();
+    assert_eq!(pixels(&image), [BLUE]);
+
+    // BITMAPV5 header carrying RGBA byte-order masks with half alpha.
+    let mut v5 = bmp(124, 1, 1, 32, 3, &[], &[10, 20, 30, 128]);
+    for (i, mask) in [0xFFu32, 0xFF00, 0xFF_0000, 0xFF00_0000].iter().enumerate() {
+        v5[54 + 4 * i..58 + 4 * i].copy_from_slice(&mask.to_le_bytes());
+    }
+    assert_eq!(pixels(&decode_bmp(&v5).unwrap()), [[10, 20, 30, 128]]);
+}
+
+#[test]
+fn reports_why_files_are_rejected() {
+    assert_eq!(decode_bmp(b"PNG"), Err(BmpError::NotBmp));
+    assert_eq!(
+        decode_bmp(&bmp(12, 1, 1, 24, 0, &[], &[0; 4])),
+        Err(BmpError::UnsupportedHeader { size: 12 })
+    );
+    assert_eq!(
+        decode_bmp(&bmp(40, 1, 1, 24, 4, &[], &[0; 4])),
+        Err(BmpError::UnsupportedFormat {
+            bits_per_pixel: 24,
+            compression: 4
+        })
+    );
+    assert_eq!(
+        decode_bmp(&bmp(40, 2, 2, 24, 0, &[], &[0; 8])),
+        Err(BmpError::Truncated { what: "pixel data" })
+    );
+    assert_eq!(
+        decode_bmp(&bmp(40, 1, 1, 8, 0, &PALETTE, &[9, 0, 0, 0])),
+        Err(BmpError::PaletteIndex {
+            index: 9,
+            palette_len: 4
+        })
+    );
+    assert_eq!(
+        decode_bmp(&bmp(40, 1, -1, 8, 1, &PALETTE, &[1, 1, 0, 1])),
+        Err(BmpError::TopDownRle)
+    );
+    let err = decode_bmp(&bmp(40, 1, 1, 8, 1, &PALETTE, &[3, 1])).unwrap_err();
+    assert_eq!(err, BmpError::InvalidRle { offset: 0 });
+    assert_eq!(err.to_string(), "invalid BMP RLE data at byte 0");
+}
@@ -0,0 +1,130 @@
+use bvb_raster::{Canvas, Pattern, Rotation, Transform, blend_pixel, rgba};
+
+const RED: [u8; 4] = rgba(255, 0, 0);
+const BLUE: [u8; 4] = rgba(0, 0, 255);
+
+// Rows of the canvas as characters: '#' for `c`, '.' otherwise.
+fn rows(canvas: &Canvas, c: [u8; 4]) -> Vec<String> {
+    (0..canvas.height)
+        .map(|y| {
+            (0..canvas.width)
+                .map(|x| {
+                    if canvas.pixel(x, y) == Some(c) {
+                        '#'
+                    } else {
+                        '.'
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn shapes_clip_to_the_canvas() {
+    let mut canvas = Canvas::new(4, 3);
+    canvas.fill_rect(-2, 1, 4, 10, RED);
+    canvas.fill_circle(3, 0, 1, BLUE);
+    assert_eq!(rows(&canvas, RED), ["....", "##..", "##.."]);
+    assert_eq!(rows(&canvas, BLUE), ["..##", "...#", "...."]);
+}
+
+#[test]
+fn lines_and_polygons() {
+    let mut canvas = Canvas::new(5, 5);
+    canvas.line(0, 0, 4, 2, RED);
+    assert_eq!(
+        rows(&canvas, RED),
+        ["#....", ".##..", "...##", ".....", "....."]
+    );
+
+    let mut canvas = Canvas::new(5, 5);
+    canvas.fill_polygon(&[(0, 0), (4, 0), (0, 4)], BLUE);
+    assert_eq!(
+        rows(&canvas, BLUE),
+        ["###..", "##...", "#....", ".....", "....."]
+    );
+}
+
+#[test]
+fn flood_fill_stops_at_borders() {
+    let mut canvas = Canvas::new(5, 3);
+    canvas.clear(BLUE);
+    canvas.fill_rect(2, 0, 1, 3, RED);
+    assert_eq!(canvas.flood_fill(0, 0, RED), 6);
+    assert_eq!(rows(&canvas, RED), ["###..", "###..", "###.."]);
+    assert_eq!(canvas.flood_fill(0, 0, RED), 0);
+}
+
+#[test]
+fn patterns_tile_in_canvas_coordinates() {
+    let mut canvas = Canvas::new(4, 4);
+    canvas.fill_rect_pattern(0, 0, 4, 4, &Pattern::Checker(RED, BLUE));
+    assert_eq!(rows(&canvas, RED), ["#.#.", ".#.#", "#.#.", ".#.#"]);
+
+    let half = Pattern::Bayer4 {
+        a: RED,
+        b: BLUE,
+        level: 8,
+    };
+    canvas.fill_rect_pattern(0, 0, 4, 4, &half);
+    assert_eq!(canvas.pixels.chunks(4).filter(|c| *c == BLUE).count(), 8);
+}
+
+#[test]
+fn blits_rotate_flip_and_blend() {
+    // 2x1 sprite: red, then half-transparent blue.
+    let sprite = Canvas::from_rgba(2, 1, [RED, [0, 0, 255, 128]].concat()).unwrap();
+    let mut canvas = Canvas::new(3, 3);
+    canvas.clear(rgba(0, 0, 0));
+
+    let rotated = Transform {
+        rotation: Rotation::Cw90,
+        ..Transform::IDENTITY
+    };
+    canvas.blit(&sprite, 1, 0, rotated);
+    assert_eq!(canvas.pixel(1, 0), Some(RED));
+    assert_eq!(canvas.pixel(1, 1), Some([0, 0, 128, 255]));
+
+    let flipped = Transform {
+        flip_x: true,
+        ..Transform::IDENTITY
+    };
+    canvas.blit(&sprite, 0, 2, flipped);
+    assert_eq!(canvas.pixel(1, 2), Some(RED));
+    assert_eq!(canvas.pixel(0, 2), Some([0, 0, 128, 255]));
+}
+
+#[test]
+fn blends_onto_transparent_pixels_without_darkening() {
+    let mut pixel = [0, 0, 0, 0];
+    blend_pixel(&mut pixel, [200, 100, 50, 128]);
+    assert_eq!(pixel, [200, 100, 50, 128]);
+
+    // Half-transparent blue over half-transparent red: 75% coverage, blue weighing twice as much.
+    let mut pixel = [255, 0, 0, 128];
+    blend_pixel(&mut pixel, [0, 0, 255, 128]);
+    assert_eq!(pixel, [84, 0, 170, 191]);
+
+    let mut canvas = Canvas::new(1, 1);
+    canvas.fill_rect(0, 0, 1, 1, [40, 80, 120, 64]);
+    assert_eq!(canvas.pixel(0, 0), Some([40, 80, 120, 64]));
+}
+
+#[test]
+fn scaled_canvases_draw_in_units() {
+    let mut canvas = Canvas::with_scale(4, 4, 2);
+    assert_eq!((canvas.width, canvas.height), (8, 8));
+    canvas.fill_rect(1, 1, 1, 1, RED);
+    assert_eq!(canvas.pixel(2, 2), Some(RED));
+    assert_eq!(canvas.pixel(3, 3), Some(RED));
+    assert_eq!(canvas.pixel(4, 4), Some([0; 4]));
+
+    // Circles gain detail rather than being blocky copies of the 1x shape.
+    let mut one = Canvas::new(9, 9);
+    one.fill_circle(4, 4, 4, RED);
+    let mut two = Canvas::with_scale(9, 9, 2);
+    two.fill_circle(4, 4, 4, RED);
+    let count = |c: &Canvas| c.pixels.chunks(4).filter(|p| *p == RED).count();
+    assert_ne!(count(&two), 4 * count(&one));
+}
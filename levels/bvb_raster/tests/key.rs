@@ -0,0 +1,74 @@
+use bvb_raster::{Canvas, KeyOptions, rgba};
+
+const GREEN: [u8; 4] = rgba(0, 200, 0);
+const RED: [u8; 4] = rgba(200, 0, 0);
+
+// A 7x7 green backdrop around a red ring with a green hole in the middle.
+fn ringed_sprite() -> Canvas {
+    let mut canvas = Canvas::new(7, 7);
+    canvas.fill_rect(0, 0, 7, 7, GREEN);
+    canvas.rect_outline(2, 2, 3, 3, RED);
+    canvas
+}
+
+fn alpha(canvas: &Canvas, x: u32, y: u32) -> u8 {
+    canvas.pixel(x, y).unwrap()[3]
+}
+
+#[test]
+fn connected_keying_keeps_enclosed_key_colour() {
+    let mut sprite = ringed_sprite();
+    let report = sprite.chroma_key(&KeyOptions::default());
+    assert_eq!(report.key, GREEN);
+    assert_eq!(report.tolerance, 30);
+    assert_eq!((report.keyed, report.softened, report.pixels), (40, 0, 49));
+    assert_eq!(alpha(&sprite, 0, 0), 0);
+    assert_eq!(alpha(&sprite, 2, 2), 255);
+    assert_eq!(alpha(&sprite, 3, 3), 255);
+
+    let mut sprite = ringed_sprite();
+    let everywhere = KeyOptions {
+        connected: false,
+        ..KeyOptions::default()
+    };
+    assert_eq!(sprite.chroma_key(&everywhere).keyed, 41);
+    assert_eq!(alpha(&sprite, 3, 3), 0);
+}
+
+#[test]
+fn soft_band_ramps_alpha_and_despills() {
+    // Middle column is halfway between the key and red: 50% foreground at distance 50.
+    let mut sprite = Canvas::new(3, 1);
+    sprite.put(0, 0, rgba(0, 0, 0));
+    sprite.put(1, 0, rgba(30, 40, 0));
+    sprite.put(2, 0, rgba(200, 0, 0));
+    let options = KeyOptions {
+        key: Some(rgba(0, 0, 0)),
+        tolerance: Some(0),
+        softness: 100,
+        ..KeyOptions::default()
+    };
+    let report = sprite.chroma_key(&options);
+    assert_eq!((report.keyed, report.softened, report.despilled), (1, 1, 1));
+    assert_eq!(sprite.pixel(1, 0), Some([60, 80, 0, 127]));
+    assert_eq!(sprite.pixel(2, 0), Some([200, 0, 0, 255]));
+
+    let mut hard = Canvas::new(3, 1);
+    hard.put(1, 0, rgba(30, 40, 0));
+    let options = KeyOptions {
+        softness: 0,
+        despill: false,
+        ..options
+    };
+    assert_eq!(hard.chroma_key(&options).softened, 0);
+    assert_eq!(hard.pixel(1, 0), Some([30, 40, 0, 255]));
+}
+
+#[test]
+fn transparent_border_keys_nothing() {
+    let mut sprite = Canvas::new(4, 4);
+    sprite.fill_rect(1, 1, 2, 2, RED);
+    let report = sprite.chroma_key(&KeyOptions::default());
+    assert_eq!((report.keyed, report.key[3]), (0, 0));
+    assert_eq!(report.keyed_fraction(), 0.0);
+}
@@ -0,0 +1,151 @@
+use bvb_raster::{Canvas, Pivot, Placement, Rotation, Shadow, Transform, rgba};
+
+const RED: [u8; 4] = rgba(255, 0, 0);
+const BLUE: [u8; 4] = rgba(0, 0, 255);
+const BLACK: [u8; 4] = rgba(0, 0, 0);
+const WHITE: [u8; 4] = rgba(255, 255, 255);
+
+// Rows of the canvas as characters: 'r' red, 'b' blue, 'o' `other`, '.' anything else.
+fn rows(canvas: &Canvas, other: [u8; 4]) -> Vec<String> {
+    (0..canvas.height)
+        .map(|y| {
+            (0..canvas.width)
+                .map(|x| match canvas.pixel(x, y) {
+                    Some(RED) => 'r',
+                    Some(BLUE) => 'b',
+                    Some(c) if c == other => 'o',
+                    _ => '.',
+                })
+                .collect()
+        })
+        .collect()
+}
+
+// 2x3 sprite: a red column with a blue foot on the right.
+fn figure() -> Canvas {
+    let clear = [0; 4];
+    Canvas::from_rgba(2, 3, [RED, clear, RED, clear, RED, BLUE].concat()).unwrap()
+}
+
+fn placed(x: i32, y: i32, placement: Placement) -> Vec<String> {
+    let mut canvas = Canvas::new(6, 6);
+    canvas.clear(BLACK);
+    canvas.place(&figure(), x, y, &placement);
+    rows(&canvas, WHITE)
+}
+
+#[test]
+fn pivots_put_the_chosen_point_on_the_position() {
+    let standing = placed(3, 4, Placement::default());
+    assert_eq!(
+        standing,
+        ["......", "..r...", "..r...", "..rb..", "......", "......"]
+    );
+    let centred = Placement {
+        pivot: Pivot::Center,
+        ..Placement::default()
+    };
+    assert_eq!(placed(3, 4, centred)[3..], ["..r...", "..r...", "..rb.."]);
+    // The foot's top-left corner.
+    let foot = Placement {
+        pivot: Pivot::At(1, 2),
+        ..Placement::default()
+    };
+    assert_eq!(placed(3, 4, foot)[2..5], ["..r...", "..r...", "..rb.."]);
+}
+
+#[test]
+fn custom_pivots_follow_flips_and_turns() {
+    let flipped = Placement {
+        pivot: Pivot::At(1, 2),
+        transform: Transform {
+            flip_x: true,
+            ..Transform::IDENTITY
+        },
+        ..Placement::default()
+    };
+    // Mirrored, the same corner is the top-right of the foot, so the foot lands left of it.
+    assert_eq!(placed(3, 4, flipped)[2..5], ["...r..", "...r..", "..br.."]);
+
+    let turned = Placement {
+        pivot: Pivot::At(2, 3),
+        transform: Transform {
+            rotation: Rotation::Cw90,
+            ..Transform::IDENTITY
+        },
+        ..Placement::default()
+    };
+    // The sprite's bottom-right corner turns into its bottom-left.
+    assert_eq!(placed(1, 5, turned)[3..5], [".rrr..", ".b...."]);
+}
+
+#[test]
+fn shadows_and_outlines_draw_under_the_sprite() {
+    let dot = Canvas::from_rgba(1, 1, RED.to_vec()).unwrap();
+    let mut canvas = Canvas::new(5, 5);
+    canvas.clear(WHITE);
+    let shadowed = Placement {
+        pivot: Pivot::Center,
+        shadow: Some(Shadow {
+            offset: (1, 1),
+            color: BLACK,
+        }),
+        ..Placement::default()
+    };
+    canvas.place(&dot, 1, 1, &shadowed);
+    assert_eq!(rows(&canvas, BLACK)[1..3], [".r...", "..o.."]);
+
+    let mut canvas = Canvas::new(5, 5);
+    canvas.clear(WHITE);
+    let outlined = Placement {
+        pivot: Pivot::Center,
+        outline: Some(BLACK),
+        ..Placement::default()
+    };
+    canvas.place(&dot, 2, 2, &outlined);
+    assert_eq!(
+        rows(&canvas, BLACK),
+        [".....", ".ooo.", ".oro.", ".ooo.", "....."]
+    );
+
+    // The default shadow is a soft offset copy.
+    let mut canvas = Canvas::new(5, 5);
+    canvas.clear(WHITE);
+    let soft = Placement {
+        pivot: Pivot::Center,
+        shadow: Some(Shadow::default()),
+        ..Placement::default()
+    };
+    canvas.place(&dot, 1, 1, &soft);
+    assert_eq!(canvas.pixel(2, 3), Some([159, 159, 159, 255]));
+}
+
+#[test]
+fn tints_multiply_by_their_strength() {
+    let dot = Canvas::from_rgba(1, 1, rgba(200, 100, 50).to_vec()).unwrap();
+    let tinted = |tint| {
+        let mut canvas = Canvas::new(1, 1);
+        let placement = Placement {
+            pivot: Pivot::At(0, 0),
+            tint: Some(tint),
+            ..Placement::default()
+        };
+        canvas.place(&dot, 0, 0, &placement);
+        canvas.pixel(0, 0).unwrap()
+    };
+    assert_eq!(tinted([255, 0, 255, 255]), [200, 0, 50, 255]);
+    assert_eq!(tinted([255, 0, 255, 0]), [200, 100, 50, 255]);
+    assert_eq!(tinted([0, 0, 0, 128]), [99, 49, 24, 255]);
+}
+
+#[test]
+fn positions_are_in_units_on_scaled_canvases() {
+    let mut canvas = Canvas::with_scale(4, 4, 2);
+    canvas.clear(BLACK);
+    canvas.place(&figure(), 2, 2, &Placement::default());
+    // Feet on image pixel (4, 4), the sprite itself unscaled.
+    assert_eq!(
+        rows(&canvas, WHITE)[1..4],
+        ["...r....", "...r....", "...rb..."]
+    );
+}
@@ -0,0 +1,289 @@
+use bvb_raster::{EncodeError, PngError, decode_png, encode_apng, encode_png};
+
+const ALIEN_PNG: &[u8] = include_bytes!("../../../assets/characters/alien.png");
+const DEMON_PNG: &[u8] = include_bytes!("../../../assets/characters/demon.png");
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = u32::MAX;
+    for &byte in bytes {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                0xEDB8_8320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = out.len();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    let crc = crc32(&out[start..]);
+    out.extend_from_slice(&crc.to_be_bytes());
+}
+
+fn adler32(data: &[u8]) -> [u8; 4] {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + u32::from(byte)) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16 | a).to_be_bytes()
+}
+
+// Wraps already filtered scanlines in a single stored (uncompressed) deflate block.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x78, 0x01, 1];
+    let len = data.len() as u16;
+    out.extend_from_slice(&len.to_le_bytes());
+    out.extend_from_slice(&(!len).to_le_bytes());
+    out.extend_from_slice(data);
+    out.extend_from_slice(&adler32(data));
+    out
+}
+
+// Deflate fields packed least significant bit first; Huffman codes go most significant bit first.
+#[derive(Default)]
+struct Bits {
+    bytes: Vec<u8>,
+    len: usize,
+}
+
+impl Bits {
+    fn push(&mut self, value: u32, n: usize) {
+        for i in 0..n {
+            if self.len.is_multiple_of(8) {
+                self.bytes.push(0);
+            }
+            self.bytes[self.len / 8] |= (((value >> i) & 1) as u8) << (self.len % 8);
+            self.len += 1;
+        }
+    }
+
+    fn code(&mut self, code: u32, n: usize) {
+        self.push(code.reverse_bits() >> (32 - n), n);
+    }
+}
+
+// Builds a PNG from IHDR fields, extra chunks placed before IDAT, and filtered scanlines.
+fn png(
+    width: u32,
+    height: u32,
+    bit_depth: u8,
+    color_type: u8,
+    interlace: u8,
+    extra: &[(&[u8; 4], &[u8])],
+    scanlines: &[u8],
+) -> Vec<u8> {
+    let header = (width, height, bit_depth, color_type, interlace);
+    png_with_idat(header, extra, &zlib_stored(scanlines))
+}
+
+fn png_with_idat(
+    (width, height, bit_depth, color_type, interlace): (u32, u32, u8, u8, u8),
+    extra: &[(&[u8; 4], &[u8])],
+    idat: &[u8],
+) -> Vec<u8> {
+    let mut out = vec![0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+    let mut ihdr = Vec::new();
+    ihdr.extend_from_slice(&width.to_be_bytes());
+    ihdr.extend_from_slice(&height.to_be_bytes());
+    ihdr.extend_from_slice(&[bit_depth, color_type, 0, 0, interlace]);
+    chunk(&mut out, b"IHDR", &ihdr);
+    for (kind, data) in extra {
+        chunk(&mut out, kind, data);
+    }
+    chunk(&mut out, b"IDAT", idat);
+    chunk(&mut out, b"IEND", &[]);
+    out
+}
+
+fn gray_values(canvas: &bvb_raster::Canvas) -> Vec<u8> {
+    canvas.pixels.chunks_exact(4).map(|p| p[0]).collect()
+}
+
+#[test]
+fn decodes_the_shipped_character_art() {
+    let alien = decode_png(ALIEN_PNG).expect("8-bit RGB PNG");
+    assert_eq!((alien.width, alien.height), (1024, 1024));
+    assert!(alien.pixels.chunks_exact(4).all(|p| p[3] == 255));
+
+    let demon = decode_png(DEMON_PNG).expect("8-bit RGBA PNG");
+    assert_eq!((demon.width, demon.height), (1024, 1024));
+    assert!(demon.pixels.chunks_exact(4).any(|p| p[3] == 255));
+}
+
+#[test]
+fn inflates_fixed_huffman_codes() {
+    // One final fixed-code block: literal 0 (8 bits), literal 200 (9 bits), then 3 bytes from 1
+    // back (7 + 5 bits). The end-of-block code starts on a byte boundary, so the checksum follows
+    // bytes the decoder has already looked ahead into.
+    let mut bits = Bits::default();
+    bits.push(1, 1);
+    bits.push(1, 2);
+    bits.code(0x30, 8);
+    bits.code(0x190 + (200 - 144), 9);
+    bits.code(257 - 256, 7);
+    bits.code(0, 5);
+    assert_eq!(bits.len, 32);
+    bits.code(0, 7);
+
+    let scanlines = [0, 200, 200, 200, 200];
+    let mut zlib = vec![0x78, 0x01];
+    zlib.extend_from_slice(&bits.bytes);
+    zlib.extend_from_slice(&adler32(&scanlines));
+    let image = decode_png(&png_with_idat((4, 1, 8, 0, 0), &[], &zlib)).unwrap();
+    assert_eq!(gray_values(&image), [200; 4]);
+}
+
+#[test]
+fn reverses_every_row_filter() {
+    let scanlines = [
+        0, 10, 20, 30, // None
+        1, 5, 1, 1, // Sub
+        2, 1, 1, 1, // Up
+        3, 10, 0, 0, // Average
+        4, 1, 2, 3, // Paeth
+    ];
+    let image = decode_png(&png(3, 5, 8, 0, 0, &[], &scanlines)).unwrap();
+    assert_eq!(
+        gray_values(&image),
+        [10, 20, 30, 5, 6, 7, 6, 7, 8, 13, 10, 9, 14, 12, 15]
+    );
+
+    let bad_filter = png(3, 1, 8, 0, 0, &[], &[5, 0, 0, 0]);
+    assert_eq!(
+        decode_png(&bad_filter).unwrap_err(),
+        PngError::InvalidFilter { row: 0, filter: 5 }
+    );
+}
+
+#[test]
+fn applies_palette_transparency_to_packed_indices() {
+    // 2-bit indices 0, 1, 2, 1 in one byte; entry 0 is half transparent and entry 2 has no alpha.
+    let palette = [0, 0, 0, 255, 0, 0, 0, 0, 255];
+    let image = png(
+        4,
+        1,
+        2,
+        3,
+        0,
+        &[(b"PLTE", &palette), (b"tRNS", &[128, 255])],
+        &[0, 0b0001_1001],
+    );
+    let image = decode_png(&image).unwrap();
+    assert_eq!(
+        image.pixels,
+        [0, 0, 0, 128, 255, 0, 0, 255, 0, 0, 255, 255, 255, 0, 0, 255]
+    );
+
+    let out_of_range = png(4, 1, 2, 3, 0, &[(b"PLTE", &palette)], &[0, 0b1100_0000]);
+    assert_eq!(
+        decode_png(&out_of_range).unwrap_err(),
+        PngError::PaletteIndex {
+            index: 3,
+            palette_len: 3
+        }
+    );
+}
+
+#[test]
+fn keys_16_bit_rgb_and_scales_low_bit_gray() {
+    let key = [0x12, 0x34, 0x56, 0x78, 0x9A, 0xBC];
+    let scanlines = [
+        0, 0x12, 0x34, 0x56, 0x78, 0x9A, 0xBC, 0xFF, 0x00, 0x80, 0x00, 0x00, 0xFF,
+    ];
+    let image = decode_png(&png(2, 1, 16, 2, 0, &[(b"tRNS", &key)], &scanlines)).unwrap();
+    assert_eq!(image.pixels, [0x12, 0x56, 0x9A, 0, 0xFF, 0x80, 0x00, 255]);
+
+    // 1-bit gray: 1, 0, 1 -> white, black, white.
+    let image = decode_png(&png(3, 1, 1, 0, 0, &[], &[0, 0b1010_0000])).unwrap();
+    assert_eq!(gray_values(&image), [255, 0, 255]);
+}
+
+#[test]
+fn deinterlaces_adam7_passes() {
+    // Pixel value is its row-major index; a 3x3 image only uses passes 1, 4, 5, 6 and 7.
+    let scanlines = [0, 0, 0, 2, 0, 6, 8, 0, 1, 0, 7, 0, 3, 4, 5];
+    let image = decode_png(&png(3, 3, 8, 0, 1, &[], &scanlines)).unwrap();
+    assert_eq!(gray_values(&image), [0, 1, 2, 3, 4, 5, 6, 7, 8]);
+}
+
+#[test]
+fn rejects_corrupt_files() {
+    assert_eq!(decode_png(b"GIF89a").unwrap_err(), PngError::NotPng);
+
+    let mut corrupt = png(1, 1, 8, 0, 0, &[], &[0, 7]);
+    corrupt[29] ^= 1;
+    assert_eq!(
+        decode_png(&corrupt).unwrap_err(),
+        PngError::ChunkCrc { chunk: *b"IHDR" }
+    );
+
+    let short = png(2, 1, 8, 0, 0, &[], &[0, 7]);
+    assert_eq!(
+        decode_png(&short).unwrap_err(),
+        PngError::ImageDataLength {
+            expected: 3,
+            actual: 2
+        }
+    );
+
+    let indexed = png(1, 1, 8, 3, 0, &[], &[0, 0]);
+    assert_eq!(
+        decode_png(&indexed).unwrap_err(),
+        PngError::MissingChunk { chunk: *b"PLTE" }
+    );
+
+    let truncated = &ALIEN_PNG[..ALIEN_PNG.len() / 2];
+    assert_eq!(
+        decode_png(truncated).unwrap_err(),
+        PngError::Truncated { what: "chunk list" }
+    );
+}
+
+#[test]
+fn decodes_what_it_encodes() {
+    // Flat runs, a gradient and varying alpha exercise matches, literals and every row filter.
+    let (width, height) = (37, 23);
+    let pixels: Vec<u8> = (0..width * height)
+        .flat_map(|i| {
+            let (x, y) = (i % width, i / width);
+            [
+                (x * 7) as u8,
+                (y * 11) as u8,
+                if x < 20 { 90 } else { 200 },
+                (x ^ y) as u8,
+            ]
+        })
+        .collect();
+    let image = decode_png(&encode_png(width, height, &pixels)).unwrap();
+    assert_eq!((image.width, image.height), (width, height));
+    assert_eq!(image.pixels, pixels);
+}
+
+#[test]
+fn apng_needs_frames_matching_the_size() {
+    assert_eq!(encode_apng(2, 2, &[], &[]), Err(EncodeError::NoFrames));
+    assert_eq!(
+        encode_apng(2, 2, &[&[0; 12]], &[16])
+            .unwrap_err()
+            .to_string(),
+        "frame 0 has 12 bytes, expected 16 for 2x2"
+    );
+    assert_eq!(
+        encode_apng(2, 2, &[&[0; 16]], &[16, 16]),
+        Err(EncodeError::DelayCount {
+            frames: 1,
+            delays: 2
+        })
+    );
+    // The first frame is the default image, so plain decoders show it.
+    let apng = encode_apng(2, 2, &[&[7; 16]], &[100_000]).unwrap();
+    assert_eq!(decode_png(&apng).unwrap().pixels, [7; 16]);
+}
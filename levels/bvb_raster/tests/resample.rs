@@ -0,0 +1,81 @@
+use bvb_raster::{Anchor, Canvas, Filter, rgba};
+
+const CLEAR: [u8; 4] = [0, 0, 0, 0];
+const BLACK: [u8; 4] = rgba(0, 0, 0);
+const WHITE: [u8; 4] = rgba(255, 255, 255);
+const RED: [u8; 4] = rgba(255, 0, 0);
+const BLUE: [u8; 4] = rgba(0, 0, 255);
+
+fn canvas(width: u32, rows: &[[u8; 4]]) -> Canvas {
+    let pixels = rows.iter().flatten().copied().collect();
+    Canvas::from_rgba(width, rows.len() as u32 / width, pixels).unwrap()
+}
+
+fn row(canvas: &Canvas, y: u32) -> Vec<[u8; 4]> {
+    (0..canvas.width)
+        .map(|x| canvas.pixel(x, y).unwrap())
+        .collect()
+}
+
+#[test]
+fn fits_inside_the_target_at_the_anchor() {
+    let source = canvas(2, &[RED, BLUE]);
+    let out = source.resample(4, 4, Filter::Nearest, Anchor::Bottom);
+    assert_eq!((out.width, out.height), (4, 4));
+    assert_eq!(row(&out, 1), [CLEAR; 4]);
+    assert_eq!(row(&out, 2), [RED, RED, BLUE, BLUE]);
+    assert_eq!(row(&out, 3), [RED, RED, BLUE, BLUE]);
+
+    let out = source.resample(3, 4, Filter::Nearest, Anchor::TopRight);
+    assert_eq!(row(&out, 0), [RED, RED, BLUE]);
+    assert_eq!(row(&out, 2), [CLEAR; 3]);
+}
+
+#[test]
+fn integer_nearest_uses_whole_ratios() {
+    let source = canvas(2, &[RED, BLUE, BLUE, RED]);
+    let up = source.resample(5, 7, Filter::IntegerNearest, Anchor::TopLeft);
+    assert_eq!(row(&up, 0), [RED, RED, BLUE, BLUE, CLEAR]);
+    assert_eq!(row(&up, 3), [BLUE, BLUE, RED, RED, CLEAR]);
+    assert_eq!(row(&up, 4), [CLEAR; 5]);
+
+    let big = source.resample(4, 4, Filter::IntegerNearest, Anchor::TopLeft);
+    let down = big.resample(3, 3, Filter::IntegerNearest, Anchor::TopLeft);
+    assert_eq!(row(&down, 0), [RED, BLUE, CLEAR]);
+    assert_eq!(row(&down, 1), [BLUE, RED, CLEAR]);
+}
+
+#[test]
+fn box_averages_with_premultiplied_alpha() {
+    let source = canvas(2, &[WHITE, CLEAR, CLEAR, WHITE]);
+    let out = source.resample(1, 1, Filter::Box, Anchor::Center);
+    assert_eq!(out.pixel(0, 0), Some([255, 255, 255, 128]));
+
+    let source = canvas(3, &[RED, RED, BLUE]);
+    let out = source.resample(2, 1, Filter::Box, Anchor::Center);
+    // The second target pixel covers half a red pixel and a whole blue one.
+    assert_eq!(row(&out, 0), [RED, [85, 0, 170, 255]]);
+}
+
+#[test]
+fn pixel_art_scalers_round_diagonal_steps() {
+    // A staircase edge: the centre pixel's top-left corner gets rounded off.
+    let source = canvas(
+        3,
+        &[
+            BLACK, BLACK, WHITE, BLACK, WHITE, WHITE, WHITE, WHITE, WHITE,
+        ],
+    );
+    let epx = source.resample(6, 6, Filter::Scale2x, Anchor::Center);
+    assert_eq!(row(&epx, 2)[2..4], [BLACK, WHITE]);
+    assert_eq!(row(&epx, 3)[2..4], [WHITE, WHITE]);
+
+    let xbr = source.resample(6, 6, Filter::XbrLite, Anchor::Center);
+    assert_eq!(xbr.pixel(2, 2), Some([128, 128, 128, 255]));
+    assert_eq!(xbr.pixel(3, 2), Some(WHITE));
+
+    // Doubles once, then nearest covers the remaining 1.5x.
+    let out = source.resample(9, 9, Filter::Scale2x, Anchor::Center);
+    assert_eq!((out.width, out.height), (9, 9));
+    assert_eq!(out.pixel(0, 0), Some(BLACK));
+}
@@ -0,0 +1,58 @@
+#!/usr/bin/env bash
+set -euo pipefail
+
+# Builds the level modules into assets/levels and writes assets/levels/levels.sha256, which
+# `LevelReloader` polls to hot-reload levels in a page opened with `?devreload=1`.
+#
+# Usage: ./scripts/build_levels.sh [--watch]
+#   --watch  rebuild whenever a file under levels/ changes
+
+ROOT_DIR="$(cd "$(dirname "${BASH_SOURCE[0]}")/.." && pwd)"
+LEVELS_DIR="${ROOT_DIR}/levels"
+OUT_DIR="${ROOT_DIR}/assets/levels"
+MANIFEST="${OUT_DIR}/levels.sha256"
+STAMP="${ROOT_DIR}/target/.levels_stamp"
+
+# macOS ships `shasum`, most Linux distributions only `sha256sum`; both print the same format.
+if command -v shasum >/dev/null; then
+  SHA256=(shasum -a 256)
+elif command -v sha256sum >/dev/null; then
+  SHA256=(sha256sum)
+else
+  echo "error: neither shasum nor sha256sum found." >&2
+  exit 1
+fi
+
+# crate name:output file
+LEVELS=(
+  "bvb_level_parking_lot:mall_parking_lot.wasm"
+  "bvb_level_demon_lair:demon_lair.wasm"
+  "bvb_level_alien_ship:alien_ship.wasm"
+)
+
+build() {
+  mkdir -p "${OUT_DIR}" "$(dirname "${STAMP}")"
+  touch "${STAMP}"
+  for level in "${LEVELS[@]}"; do
+    local crate="${level%%:*}"
+    local file="${level#*:}"
+    cargo build --release \
+      --manifest-path "${LEVELS_DIR}/${crate}/Cargo.toml" \
+      --target wasm32-unknown-unknown
+    cp "${LEVELS_DIR}/${crate}/target/wasm32-unknown-unknown/release/${crate}.wasm" \
+      "${OUT_DIR}/${file}.tmp"
+    mv "${OUT_DIR}/${file}.tmp" "${OUT_DIR}/${file}"
+  done
+  # Written last, so the page never sees a hash for a module that is still being copied.
+  (cd "${OUT_DIR}" && "${SHA256[@]}" ./*.wasm > "${MANIFEST}.tmp")
+  mv "${MANIFEST}.tmp" "${MANIFEST}"
+}
+
+build
+if [[ "${1:-}" == "--watch" ]]; then
+  while sleep 1; do
+    if [[ -n "$(find "${LEVELS_DIR}" -path '*/target' -prune -o -type f -newer "${STAMP}" -print -quit)" ]]; then
+      build || echo "level build failed; waiting for the next change" >&2
+    fi
+  done
+fi
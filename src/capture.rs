@@ -0,0 +1,62 @@
+// How long a download's object URL outlives the click that started it.
+#[cfg(target_arch = "wasm32")]
+const REVOKE_DELAY_MS: i32 = 10_000;
+
+/// RGBA pixels of one rendered frame, as returned by `Renderer::capture_frame()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FrameCapture {
+    pub width: u32,
+    pub height: u32,
+    /// Tightly packed 8-bit sRGB RGBA, top row first.
+    pub rgba: Vec<u8>,
+}
+
+impl FrameCapture {
+    pub fn to_png(&self) -> Vec<u8> {
+        bvb_raster::encode_png(self.width, self.height, &self.rgba)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save_png(&self, path: impl AsRef<std::path::Path>) -> Result<(), String> {
+        let path = path.as_ref();
+        std::fs::write(path, self.to_png())
+            .map_err(|err| format!("failed to write {}: {err}", path.display()))
+    }
+
+    /// Offers the frame to the user as a PNG download through a temporary object URL.
+    #[cfg(target_arch = "wasm32")]
+    pub fn download_png(&self, file_name: &str) -> Result<(), String> {
+        use wasm_bindgen::JsCast;
+
+        let png = self.to_png();
+        let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(png.as_slice()));
+        let options = web_sys::BlobPropertyBag::new();
+        options.set_type("image/png");
+        let blob = web_sys::Blob::new_with_u8_array_sequence_and_options(&parts, &options)
+            .map_err(|err| format!("failed to create PNG blob: {err:?}"))?;
+        let url = web_sys::Url::create_object_url_with_blob(&blob)
+            .map_err(|err| format!("failed to create object URL: {err:?}"))?;
+
+        let anchor = gloo::utils::document()
+            .create_element("a")
+            .map_err(|err| format!("failed to create download link: {err:?}"))?
+            .dyn_into::<web_sys::HtmlAnchorElement>()
+            .map_err(|_| "download link is not an anchor element".to_string())?;
+        anchor.set_href(&url);
+        anchor.set_download(file_name);
+        anchor.click();
+
+        // Revoking right after `click()` can cancel the download in Firefox, so the URL stays
+        // alive until the browser has started reading it.
+        let revoke = wasm_bindgen::closure::Closure::once_into_js(move || {
+            let _ = web_sys::Url::revoke_object_url(&url);
+        });
+        gloo::utils::window()
+            .set_timeout_with_callback_and_timeout_and_arguments_0(
+                revoke.unchecked_ref(),
+                REVOKE_DELAY_MS,
+            )
+            .map(|_| ())
+            .map_err(|err| format!("failed to schedule object URL revocation: {err:?}"))
+    }
+}
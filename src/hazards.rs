@@ -0,0 +1,213 @@
+use bvb_level_kit::{Hazard, HazardInfo, HazardState};
+use bvb_raster::{rgba, Canvas, Rgba};
+
+use crate::level::LevelModule;
+use crate::sprites::{AtlasLayout, SpriteInstance};
+
+/// Edge of one frame in the hazard atlas, in pixels.
+pub const HAZARD_FRAME_SIZE: u32 = 16;
+
+// Atlas rows, one per kind; columns are the drawn states in this order.
+const KINDS: [Hazard; 3] = [Hazard::Lava, Hazard::Turret, Hazard::Collapse];
+const DRAWN_STATES: [HazardState; 3] = [
+    HazardState::Warning,
+    HazardState::Active,
+    HazardState::Depleted,
+];
+// One warning flash, dim to bright and back.
+const WARNING_FLASH_MS: u64 = 600;
+const WARNING_MIN_ALPHA: f32 = 0.35;
+
+/// A level's hazards as the renderer draws them: one quad per hazard tile, showing its kind in its
+/// current state. Dormant hazards draw nothing, warnings flash, and active and depleted hazards
+/// are steady.
+///
+/// States change here rather than in the level image, so simulation code can trigger hazards every
+/// turn without re-uploading the level.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HazardOverlay {
+    hazards: Vec<HazardInfo>,
+    tile_size: u32,
+}
+
+impl HazardOverlay {
+    /// `tile_size` is the quad edge in level pixels, normally the level's tile size.
+    pub fn new(hazards: Vec<HazardInfo>, tile_size: u32) -> Self {
+        Self {
+            hazards,
+            tile_size: tile_size.max(1),
+        }
+    }
+
+    /// The level's hazards in their starting states.
+    pub fn from_level(level: &LevelModule) -> Self {
+        Self::new(
+            level.hazards.clone(),
+            level.metadata.tile_size.unwrap_or(HAZARD_FRAME_SIZE),
+        )
+    }
+
+    pub fn hazards(&self) -> &[HazardInfo] {
+        &self.hazards
+    }
+
+    /// Moves hazard `index`, in level table order, to `state`.
+    pub fn set_state(&mut self, index: usize, state: HazardState) -> Result<(), String> {
+        let count = self.hazards.len();
+        let hazard = self
+            .hazards
+            .get_mut(index)
+            .ok_or_else(|| format!("hazard {index} does not exist, the level has {count}"))?;
+        hazard.state = state;
+        Ok(())
+    }
+
+    /// Quads for every tile of every drawn hazard, `elapsed_ms` into the warning flash cycle.
+    /// Frames index `atlas()`.
+    pub fn sprites(&self, elapsed_ms: u64) -> Vec<SpriteInstance> {
+        let tile = self.tile_size;
+        let mut out = Vec::new();
+        for hazard in &self.hazards {
+            let Some(frame) = frame(hazard.kind, hazard.state) else {
+                continue;
+            };
+            let alpha = match hazard.state {
+                HazardState::Warning => warning_alpha(elapsed_ms),
+                _ => 1.0,
+            };
+            for ty in 0..hazard.height.div_ceil(tile) {
+                for tx in 0..hazard.width.div_ceil(tile) {
+                    out.push(SpriteInstance {
+                        center: [
+                            (hazard.x + tx * tile) as f32 + tile as f32 / 2.0,
+                            (hazard.y + ty * tile) as f32 + tile as f32 / 2.0,
+                        ],
+                        size: [tile as f32, tile as f32],
+                        frame,
+                        color: [1.0, 1.0, 1.0, alpha],
+                    });
+                }
+            }
+        }
+        out
+    }
+
+    /// The pixel art every hazard quad samples: a row per kind (lava, turret, collapse) and a
+    /// column per drawn state (warning, active, depleted), `HAZARD_FRAME_SIZE` pixels each.
+    pub fn atlas() -> (Vec<u8>, AtlasLayout) {
+        let size = HAZARD_FRAME_SIZE;
+        let columns = DRAWN_STATES.len() as u32;
+        let rows = KINDS.len() as u32;
+        let mut canvas = Canvas::new(size * columns, size * rows);
+        for (row, kind) in KINDS.into_iter().enumerate() {
+            for (column, state) in DRAWN_STATES.into_iter().enumerate() {
+                let origin = (column as i32 * size as i32, row as i32 * size as i32);
+                draw_frame(&mut canvas, origin, kind, state);
+            }
+        }
+        let layout = AtlasLayout {
+            width: canvas.width,
+            height: canvas.height,
+            frame_width: size,
+            frame_height: size,
+        };
+        (canvas.pixels, layout)
+    }
+}
+
+fn frame(kind: Hazard, state: HazardState) -> Option<u32> {
+    let row = KINDS.iter().position(|&k| k == kind)?;
+    let column = DRAWN_STATES.iter().position(|&s| s == state)?;
+    Some((row * DRAWN_STATES.len() + column) as u32)
+}
+
+// Triangle wave between `WARNING_MIN_ALPHA` and 1.
+fn warning_alpha(elapsed_ms: u64) -> f32 {
+    let half = WARNING_FLASH_MS / 2;
+    let phase = elapsed_ms % WARNING_FLASH_MS;
+    let rising = if phase < half {
+        phase
+    } else {
+        WARNING_FLASH_MS - phase
+    };
+    WARNING_MIN_ALPHA + (1.0 - WARNING_MIN_ALPHA) * rising as f32 / half as f32
+}
+
+fn with_alpha(c: Rgba, a: u8) -> Rgba {
+    [c[0], c[1], c[2], a]
+}
+
+// Draws one 16 x 16 frame with its top-left corner at `(x, y)`.
+fn draw_frame(canvas: &mut Canvas, (x, y): (i32, i32), kind: Hazard, state: HazardState) {
+    let (cx, cy) = (x + 7, y + 7);
+    match (kind, state) {
+        (Hazard::Lava, HazardState::Warning) => {
+            // Heat shimmer: an amber border with diagonal stripes.
+            let amber = rgba(240, 138, 58);
+            canvas.rect_outline(x, y, 16, 16, amber);
+            for offset in [0, 6, 12] {
+                canvas.line(
+                    x + offset,
+                    y + 15,
+                    x + offset + 3,
+                    y + 12,
+                    with_alpha(amber, 170),
+                );
+            }
+        }
+        (Hazard::Lava, HazardState::Active) => {
+            canvas.fill_rect(x, y, 16, 16, rgba(168, 50, 26));
+            canvas.fill_circle(x + 4, y + 4, 3, rgba(240, 138, 58));
+            canvas.fill_circle(x + 11, y + 10, 2, rgba(240, 138, 58));
+            canvas.fill_circle(x + 4, y + 4, 1, rgba(255, 210, 122));
+            canvas.put(x + 11, y + 10, rgba(255, 210, 122));
+        }
+        (Hazard::Lava, HazardState::Depleted) => {
+            // Cooled crust.
+            canvas.fill_rect(x, y, 16, 16, [58, 30, 30, 210]);
+            canvas.line(x + 2, y + 3, x + 9, y + 8, rgba(24, 12, 12));
+            canvas.line(x + 9, y + 8, x + 13, y + 14, rgba(24, 12, 12));
+            canvas.line(x + 9, y + 8, x + 14, y + 5, rgba(24, 12, 12));
+        }
+        (Hazard::Turret, HazardState::Warning) => {
+            // Target reticle on the tiles it is about to fire at.
+            let red = rgba(255, 64, 64);
+            canvas.fill_ring(cx, cy, 7, 6, red);
+            canvas.fill_rect(cx, y, 1, 4, red);
+            canvas.fill_rect(cx, y + 11, 1, 4, red);
+            canvas.fill_rect(x, cy, 4, 1, red);
+            canvas.fill_rect(x + 11, cy, 4, 1, red);
+        }
+        (Hazard::Turret, HazardState::Active) => {
+            canvas.fill_circle(cx, cy, 6, rgba(84, 96, 112));
+            canvas.fill_ring(cx, cy, 6, 6, rgba(172, 196, 214));
+            canvas.fill_rect(cx - 1, y, 3, 7, rgba(172, 196, 214));
+            canvas.fill_circle(cx, cy, 2, rgba(255, 64, 64));
+            canvas.put(cx, y, rgba(255, 230, 120));
+        }
+        (Hazard::Turret, HazardState::Depleted) => {
+            // Wrecked mount: dark, with a bent barrel.
+            canvas.fill_circle(cx, cy, 6, rgba(46, 50, 58));
+            canvas.line(cx, cy, cx + 5, cy - 4, rgba(96, 100, 108));
+            canvas.line(x + 3, y + 12, x + 9, y + 9, rgba(20, 22, 26));
+        }
+        (Hazard::Collapse, HazardState::Warning) => {
+            let crack = [20, 12, 10, 230];
+            canvas.line(x + 1, y + 2, x + 6, y + 7, crack);
+            canvas.line(x + 6, y + 7, x + 4, y + 13, crack);
+            canvas.line(x + 6, y + 7, x + 14, y + 9, crack);
+        }
+        (Hazard::Collapse, HazardState::Active) => {
+            // Open pit.
+            canvas.fill_rect(x, y, 16, 16, rgba(6, 4, 6));
+            canvas.rect_outline(x, y, 16, 16, rgba(90, 70, 60));
+        }
+        (Hazard::Collapse, HazardState::Depleted) => {
+            canvas.fill_rect(x, y, 16, 16, [40, 34, 32, 230]);
+            for (dx, dy) in [(2, 3), (9, 2), (5, 9), (12, 11)] {
+                canvas.fill_rect(x + dx, y + dy, 3, 2, rgba(96, 86, 76));
+            }
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,1270 @@
+use std::time::Duration;
+
+use bvb_level_kit::{
+    hazards_from_bytes, layers_from_bytes, HazardInfo, LayerKind, LevelLayer, LevelParams,
+    SemanticGrid, ABI_VERSION,
+};
+
+/// Largest level edge accepted, the 2D texture size every WebGL2 device supports.
+pub const MAX_LEVEL_DIMENSION: u32 = 2048;
+const MAX_TEXT_LEN: u32 = 256;
+const MAX_LAYERS: usize = 16;
+const MAX_HAZARDS: usize = 256;
+
+const REQUIRED_EXPORTS: [&str; 5] = [
+    "bvb_level_init",
+    "bvb_level_width",
+    "bvb_level_height",
+    "bvb_level_pixels_ptr",
+    "bvb_level_pixels_len",
+];
+
+/// Metadata a level module exports. Everything after `abi_version` is optional, as version 0 modules
+/// predate it.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LevelMetadata {
+    pub abi_version: u32,
+    pub name: Option<String>,
+    pub author: Option<String>,
+    pub tile_size: Option<u32>,
+    pub grid_width: Option<u32>,
+    pub grid_height: Option<u32>,
+    pub content_hash: Option<u64>,
+}
+
+/// How `LevelModule::load_with` asks a module to generate its level.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LevelOptions {
+    pub seed: u32,
+    /// Params for `bvb_level_init_with`. With `None` and seed 0 the module's plain
+    /// `bvb_level_init` runs, so older modules still load.
+    pub params: Option<LevelParams>,
+    /// Image pixels per level pixel, rendered by the module through `bvb_level_render`.
+    pub scale: u32,
+}
+
+impl Default for LevelOptions {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            params: None,
+            scale: 1,
+        }
+    }
+}
+
+/// What a level module may use. Shared maps are untrusted code, so every instance runs within
+/// limits. The defaults leave room for a 2048 x 2048 image while stopping a module that loops
+/// forever within about a second in release builds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LevelLimits {
+    /// Largest linear memory in bytes. Modules whose memory section declares more fail to load
+    /// before they are compiled, and growing past it traps.
+    pub memory_bytes: usize,
+    /// Largest table in elements. Rust modules have one table of function pointers, a few hundred
+    /// entries long.
+    pub table_elements: u32,
+    /// Fuel for each export call, roughly one unit per Wasm instruction; running out traps.
+    /// Native builds only.
+    pub fuel_per_call: u64,
+    /// Wall-clock time for each export call and the start function in wasm32 builds, which run
+    /// modules in a Worker and terminate it once a call runs over.
+    pub time_per_call: Duration,
+}
+
+impl Default for LevelLimits {
+    fn default() -> Self {
+        Self {
+            memory_bytes: 128 << 20,
+            table_elements: 1 << 16,
+            fuel_per_call: 500_000_000,
+            time_per_call: Duration::from_secs(2),
+        }
+    }
+}
+
+/// A level module's output, copied out of its linear memory after validation.
+///
+/// The module instance is dropped once loading finishes, so a `LevelModule` is plain data.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LevelModule {
+    /// Image size in pixels, `scale` times the level size.
+    pub width: u32,
+    pub height: u32,
+    /// RGBA8, row-major.
+    pub pixels: Vec<u8>,
+    /// Image pixels per level pixel. Sprite, light and semantic coordinates stay in level pixels.
+    pub scale: u32,
+    pub metadata: LevelMetadata,
+    pub semantics: Option<SemanticGrid>,
+    /// Ground first, then animated and foreground layers in draw order. Empty when the module
+    /// exports only the flattened `pixels`.
+    pub layers: Vec<LevelLayer>,
+    /// Hazards with the state each starts in, in level pixels. Their tiles carry the same kind in
+    /// `semantics`.
+    pub hazards: Vec<HazardInfo>,
+}
+
+impl LevelModule {
+    /// Instantiates `wasm` with wasmi and reads the level out of it.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load(wasm: &[u8]) -> Result<Self, String> {
+        Self::load_with(wasm, &LevelOptions::default())
+    }
+
+    /// Like `load`, but generates a seeded variant and/or renders at a higher scale.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load_with(wasm: &[u8], options: &LevelOptions) -> Result<Self, String> {
+        LevelInstance::new(wasm)?.generate(options)
+    }
+
+    /// Like `load_with`, within `limits` instead of the default ones.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load_limited(
+        wasm: &[u8],
+        options: &LevelOptions,
+        limits: LevelLimits,
+    ) -> Result<Self, String> {
+        LevelInstance::with_limits(wasm, limits)?.generate(options)
+    }
+
+    /// Instantiates `wasm` in a Worker and reads the level out of it.
+    #[cfg(target_arch = "wasm32")]
+    pub async fn load(wasm: &[u8]) -> Result<Self, String> {
+        Self::load_with(wasm, &LevelOptions::default()).await
+    }
+
+    /// Like `load`, but generates a seeded variant and/or renders at a higher scale.
+    #[cfg(target_arch = "wasm32")]
+    pub async fn load_with(wasm: &[u8], options: &LevelOptions) -> Result<Self, String> {
+        LevelInstance::new(wasm).await?.generate(options).await
+    }
+
+    /// Like `load_with`, within `limits` instead of the default ones.
+    #[cfg(target_arch = "wasm32")]
+    pub async fn load_limited(
+        wasm: &[u8],
+        options: &LevelOptions,
+        limits: LevelLimits,
+    ) -> Result<Self, String> {
+        LevelInstance::with_limits(wasm, limits)
+            .await?
+            .generate(options)
+            .await
+    }
+
+    /// Fetches a level module, e.g. `assets/levels/mall_parking_lot.wasm`, and loads it.
+    #[cfg(target_arch = "wasm32")]
+    pub async fn fetch(url: &str) -> Result<Self, String> {
+        let bytes = browser::fetch_bytes(url).await?;
+        Self::load(&bytes).await
+    }
+
+    /// The module's display name, or `fallback` for modules that do not export one.
+    pub fn name_or<'a>(&'a self, fallback: &'a str) -> &'a str {
+        self.metadata.name.as_deref().unwrap_or(fallback)
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub(crate) use browser::fetch_bytes;
+
+#[cfg(not(target_arch = "wasm32"))]
+type Exports = native::WasmiExports;
+#[cfg(target_arch = "wasm32")]
+type Exports = browser::WorkerExports;
+
+// Native exports never suspend, so the shared async code finishes on its first poll.
+#[cfg(not(target_arch = "wasm32"))]
+fn finish<T>(future: impl std::future::Future<Output = T>) -> T {
+    use std::task::{Context, Poll, Waker};
+
+    let mut future = std::pin::pin!(future);
+    match future
+        .as_mut()
+        .poll(&mut Context::from_waker(Waker::noop()))
+    {
+        Poll::Ready(value) => value,
+        Poll::Pending => unreachable!("native level exports never suspend"),
+    }
+}
+
+/// A level module kept instantiated, so the host can regenerate, re-render and free its level
+/// without instantiating the module again.
+///
+/// `LevelModule::load` is a one-shot `LevelInstance`; keep an instance when the level changes at
+/// runtime, such as a seed picker or hot reload.
+pub struct LevelInstance {
+    exports: Exports,
+    abi_version: u32,
+    generated: bool,
+    limits: LevelLimits,
+}
+
+impl LevelInstance {
+    /// Instantiates `wasm` with wasmi and checks its imports, exports and ABI version.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn new(wasm: &[u8]) -> Result<Self, String> {
+        Self::with_limits(wasm, LevelLimits::default())
+    }
+
+    /// Like `new`, within `limits` instead of the default ones.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_limits(wasm: &[u8], limits: LevelLimits) -> Result<Self, String> {
+        let exports = native::WasmiExports::instantiate(wasm, limits)?;
+        finish(Self::validate(exports, limits))
+    }
+
+    /// Instantiates `wasm` in a Worker and checks its imports, exports and ABI version.
+    #[cfg(target_arch = "wasm32")]
+    pub async fn new(wasm: &[u8]) -> Result<Self, String> {
+        Self::with_limits(wasm, LevelLimits::default()).await
+    }
+
+    /// Like `new`, within `limits` instead of the default ones. `time_per_call` stands in for
+    /// fuel: a call running longer terminates the Worker, and the instance with it.
+    #[cfg(target_arch = "wasm32")]
+    pub async fn with_limits(wasm: &[u8], limits: LevelLimits) -> Result<Self, String> {
+        let exports = browser::WorkerExports::instantiate(wasm, &limits).await?;
+        Self::validate(exports, limits).await
+    }
+
+    async fn validate(mut exports: Exports, limits: LevelLimits) -> Result<Self, String> {
+        check_memory(&exports, &limits).await?;
+        for name in REQUIRED_EXPORTS {
+            if !exports.has(name) {
+                return Err(format!("level module is missing export `{name}`"));
+            }
+        }
+        let abi_version = optional_u32(&mut exports, "bvb_level_abi_version")
+            .await?
+            .unwrap_or(0);
+        if abi_version > ABI_VERSION {
+            return Err(format!(
+                "level module uses ABI version {abi_version}, this host supports up to {ABI_VERSION}"
+            ));
+        }
+        Ok(Self {
+            exports,
+            abi_version,
+            generated: false,
+            limits,
+        })
+    }
+
+    pub fn abi_version(&self) -> u32 {
+        self.abi_version
+    }
+
+    /// Generates the level for `options` and copies it out, replacing the module's previous level.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn generate(&mut self, options: &LevelOptions) -> Result<LevelModule, String> {
+        finish(self.generate_level(options))
+    }
+
+    /// Generates the level for `options` and copies it out, replacing the module's previous level.
+    #[cfg(target_arch = "wasm32")]
+    pub async fn generate(&mut self, options: &LevelOptions) -> Result<LevelModule, String> {
+        self.generate_level(options).await
+    }
+
+    /// Frees the module's level buffers through `bvb_level_release` (ABI v2). Older modules keep
+    /// theirs until the next `generate`, so this does nothing for them.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn release(&mut self) -> Result<(), String> {
+        finish(self.release_level())
+    }
+
+    /// Frees the module's level buffers through `bvb_level_release` (ABI v2). Older modules keep
+    /// theirs until the next `generate`, so this does nothing for them.
+    #[cfg(target_arch = "wasm32")]
+    pub async fn release(&mut self) -> Result<(), String> {
+        self.release_level().await
+    }
+
+    async fn generate_level(&mut self, options: &LevelOptions) -> Result<LevelModule, String> {
+        if options.scale == 0 {
+            return Err("level scale must be at least 1".to_string());
+        }
+        let exports = &mut self.exports;
+        // `bvb_level_init` is idempotent, so going back to the default level after a variant
+        // needs `bvb_level_init_with` too.
+        let reset = self.generated && exports.has("bvb_level_init_with");
+        if options.seed != 0 || options.params.is_some() || reset {
+            let params = options.params.unwrap_or_default();
+            init_with(exports, options.seed, &params).await?;
+        } else {
+            exports.call("bvb_level_init").await?;
+        }
+        self.generated = true;
+        let current_scale = module_scale(exports).await?;
+        if current_scale != options.scale {
+            render_scaled(exports, options.scale).await?;
+        }
+        check_memory(exports, &self.limits).await?;
+        read_level(exports, self.abi_version, options.scale).await
+    }
+
+    async fn release_level(&mut self) -> Result<(), String> {
+        if self.exports.has("bvb_level_release") {
+            self.exports.call("bvb_level_release").await?;
+            self.generated = false;
+        }
+        Ok(())
+    }
+}
+
+// The calls `LevelInstance` needs from an instantiated module, implemented per runtime.
+trait LevelExports {
+    fn has(&self, name: &str) -> bool;
+    async fn call(&mut self, name: &str) -> Result<(), String>;
+    async fn call_u32(&mut self, name: &str) -> Result<u32, String>;
+    async fn call_u64(&mut self, name: &str) -> Result<u64, String>;
+    // For exports taking i32 arguments and returning nothing.
+    async fn call_with(&mut self, name: &str, args: &[u32]) -> Result<(), String>;
+    // For exports taking i32 arguments and returning an i32.
+    async fn call_u32_with(&mut self, name: &str, args: &[u32]) -> Result<u32, String>;
+    async fn memory_len(&self) -> Result<usize, String>;
+    // Only called with ranges already checked against `memory_len`.
+    async fn copy_memory(&self, start: usize, len: usize) -> Result<Vec<u8>, String>;
+    async fn write_memory(&mut self, start: usize, bytes: &[u8]) -> Result<(), String>;
+}
+
+// Copies out the generated level, which must be rendered at `expected_scale`.
+async fn read_level(
+    exports: &mut impl LevelExports,
+    abi_version: u32,
+    expected_scale: u32,
+) -> Result<LevelModule, String> {
+    let scale = module_scale(exports).await?;
+    if scale != expected_scale {
+        return Err(format!(
+            "level module reports scale {scale}, expected {expected_scale}"
+        ));
+    }
+
+    let width = exports.call_u32("bvb_level_width").await?;
+    let height = exports.call_u32("bvb_level_height").await?;
+    if width == 0 || height == 0 || width > MAX_LEVEL_DIMENSION || height > MAX_LEVEL_DIMENSION {
+        return Err(format!(
+            "level module has invalid dimensions {width}x{height}"
+        ));
+    }
+    if width % scale != 0 || height % scale != 0 {
+        return Err(format!(
+            "level image {width}x{height} is not a multiple of its scale {scale}"
+        ));
+    }
+    let expected_len = 4 * width * height;
+    let pixels_len = exports.call_u32("bvb_level_pixels_len").await?;
+    if pixels_len != expected_len {
+        return Err(format!(
+            "level pixel buffer is {pixels_len} bytes, expected {expected_len} for {width}x{height}"
+        ));
+    }
+    let pixels_ptr = exports.call_u32("bvb_level_pixels_ptr").await?;
+    let pixels = read_memory(exports, pixels_ptr, pixels_len, "pixel buffer").await?;
+
+    let metadata = LevelMetadata {
+        abi_version,
+        name: read_text(exports, "bvb_level_name").await?,
+        author: read_text(exports, "bvb_level_author").await?,
+        tile_size: optional_u32(exports, "bvb_level_tile_size").await?,
+        grid_width: optional_u32(exports, "bvb_level_grid_width").await?,
+        grid_height: optional_u32(exports, "bvb_level_grid_height").await?,
+        content_hash: if exports.has("bvb_level_content_hash") {
+            Some(exports.call_u64("bvb_level_content_hash").await?)
+        } else {
+            None
+        },
+    };
+
+    let semantics = read_semantics(exports, &metadata).await?;
+    let layers = read_layers(exports, width / scale, height / scale, scale).await?;
+    let hazards = read_hazards(exports, width / scale, height / scale, semantics.as_ref()).await?;
+
+    Ok(LevelModule {
+        width,
+        height,
+        pixels,
+        scale,
+        metadata,
+        semantics,
+        layers,
+        hazards,
+    })
+}
+
+// Passes `params` to `bvb_level_init_with` in a buffer from `bvb_alloc`, or in the ABI v1
+// scratch buffer for older modules.
+async fn init_with(
+    exports: &mut impl LevelExports,
+    seed: u32,
+    params: &LevelParams,
+) -> Result<(), String> {
+    if !exports.has("bvb_level_init_with") {
+        return Err(
+            "level module does not support generation parameters (missing `bvb_level_init_with`)"
+                .to_string(),
+        );
+    }
+    let bytes = params.to_bytes();
+    let len = bytes.len() as u32;
+    let status = if exports.has("bvb_alloc") && exports.has("bvb_free") {
+        let ptr = exports.call_u32_with("bvb_alloc", &[len]).await?;
+        let status = match write_params(exports, ptr, &bytes).await {
+            Ok(()) => {
+                exports
+                    .call_u32_with("bvb_level_init_with", &[seed, ptr, len])
+                    .await
+            }
+            Err(err) => Err(err),
+        };
+        exports.call_with("bvb_free", &[ptr, len]).await?;
+        status?
+    } else if exports.has("bvb_level_params_ptr") && exports.has("bvb_level_params_capacity") {
+        let capacity = exports.call_u32("bvb_level_params_capacity").await?;
+        if capacity < len {
+            return Err(format!(
+                "level params buffer is {capacity} bytes, {len} are needed"
+            ));
+        }
+        let ptr = exports.call_u32("bvb_level_params_ptr").await?;
+        write_params(exports, ptr, &bytes).await?;
+        exports
+            .call_u32_with("bvb_level_init_with", &[seed, ptr, len])
+            .await?
+    } else {
+        return Err(
+            "level module has no params buffer (missing `bvb_alloc` or `bvb_level_params_ptr`)"
+                .to_string(),
+        );
+    };
+    if status != 0 {
+        return Err(format!(
+            "level module rejected params {params:?} (status {status})"
+        ));
+    }
+    Ok(())
+}
+
+async fn write_params(
+    exports: &mut impl LevelExports,
+    ptr: u32,
+    bytes: &[u8],
+) -> Result<(), String> {
+    // Validates the range before writing to it.
+    read_memory(exports, ptr, bytes.len() as u32, "params buffer").await?;
+    exports.write_memory(ptr as usize, bytes).await
+}
+
+async fn render_scaled(exports: &mut impl LevelExports, scale: u32) -> Result<(), String> {
+    if scale == 0 {
+        return Err("level scale must be at least 1".to_string());
+    }
+    if !exports.has("bvb_level_render") {
+        return Err(format!(
+            "level module cannot render at {scale}x (missing `bvb_level_render`)"
+        ));
+    }
+    let status = exports.call_u32_with("bvb_level_render", &[scale]).await?;
+    if status != 0 {
+        return Err(format!(
+            "level module does not support {scale}x rendering (status {status})"
+        ));
+    }
+    Ok(())
+}
+
+const WASM_PAGE_BYTES: u64 = 64 << 10;
+const TABLE_SECTION: u8 = 4;
+const MEMORY_SECTION: u8 = 5;
+const FUNCREF: u8 = 0x70;
+const EXTERNREF: u8 = 0x6f;
+
+/// Reads the table and memory sections of `wasm` and rejects more than one of either, or one
+/// whose initial size exceeds `limits`, before the module is compiled and any of it is reserved.
+fn check_declared_sizes(wasm: &[u8], limits: &LevelLimits) -> Result<(), String> {
+    let mut module = WasmReader { bytes: wasm };
+    if module.take(8)? != b"\0asm\x01\0\0\0" {
+        return Err("invalid level module: not a WebAssembly 1.0 binary".to_string());
+    }
+    while !module.bytes.is_empty() {
+        let id = module.byte()?;
+        let len = module.leb()?;
+        let body = module.take(usize::try_from(len).unwrap_or(usize::MAX))?;
+        let what = match id {
+            TABLE_SECTION => "tables",
+            MEMORY_SECTION => "memories",
+            _ => continue,
+        };
+        let mut section = WasmReader { bytes: body };
+        let count = section.leb()?;
+        if count > 1 {
+            return Err(format!(
+                "level module declares {count} {what}, level modules may have one"
+            ));
+        }
+        if count == 0 {
+            continue;
+        }
+        if id == TABLE_SECTION && !matches!(section.byte()?, FUNCREF | EXTERNREF) {
+            return Err("level module declares a table of an unsupported type".to_string());
+        }
+        let flags = section.byte()?;
+        let initial = section.leb()?;
+        // A declared maximum only lowers the limit, so it is read past but not checked.
+        if flags & 1 != 0 {
+            section.leb()?;
+        }
+        if id == TABLE_SECTION {
+            if initial > u64::from(limits.table_elements) {
+                return Err(format!(
+                    "level module declares a table of {initial} elements, more than the {} \
+                     allowed",
+                    limits.table_elements
+                ));
+            }
+            continue;
+        }
+        let bytes = initial.saturating_mul(WASM_PAGE_BYTES);
+        if bytes > limits.memory_bytes as u64 {
+            return Err(format!(
+                "level module declares {initial} pages ({bytes} bytes) of memory, more than \
+                 the {} bytes allowed",
+                limits.memory_bytes
+            ));
+        }
+    }
+    Ok(())
+}
+
+struct WasmReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> WasmReader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        if len > self.bytes.len() {
+            return Err("invalid level module: unexpected end of the binary".to_string());
+        }
+        let (head, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(head)
+    }
+
+    fn byte(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    // Unsigned LEB128, wide enough for memory64 page counts.
+    fn leb(&mut self) -> Result<u64, String> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err("invalid level module: malformed LEB128 integer".to_string())
+    }
+}
+
+// wasmi enforces the limit as memory grows; this also catches browser modules after the fact.
+async fn check_memory(exports: &impl LevelExports, limits: &LevelLimits) -> Result<(), String> {
+    let memory_len = exports.memory_len().await?;
+    if memory_len > limits.memory_bytes {
+        return Err(format!(
+            "level module uses {memory_len} bytes of memory, at most {} are allowed",
+            limits.memory_bytes
+        ));
+    }
+    Ok(())
+}
+
+async fn read_memory(
+    exports: &impl LevelExports,
+    ptr: u32,
+    len: u32,
+    what: &str,
+) -> Result<Vec<u8>, String> {
+    if ptr == 0 {
+        return Err(format!("level {what} pointer is null"));
+    }
+    let start = ptr as usize;
+    let end = start
+        .checked_add(len as usize)
+        .ok_or_else(|| format!("level {what} range overflows"))?;
+    let memory_len = exports.memory_len().await?;
+    if end > memory_len {
+        return Err(format!(
+            "level {what} {start:#x}..{end:#x} lies outside linear memory ({memory_len} bytes)"
+        ));
+    }
+    exports.copy_memory(start, len as usize).await
+}
+
+// The scale the module last rendered at, 1 for modules that cannot re-render.
+async fn module_scale(exports: &mut impl LevelExports) -> Result<u32, String> {
+    match optional_u32(exports, "bvb_level_scale").await? {
+        Some(0) => Err("level module reports scale 0, scales start at 1".to_string()),
+        scale => Ok(scale.unwrap_or(1)),
+    }
+}
+
+async fn optional_u32(exports: &mut impl LevelExports, name: &str) -> Result<Option<u32>, String> {
+    if exports.has(name) {
+        exports.call_u32(name).await.map(Some)
+    } else {
+        Ok(None)
+    }
+}
+
+// Reads a `<prefix>_ptr` / `<prefix>_len` UTF-8 string pair when both are exported.
+async fn read_text(
+    exports: &mut impl LevelExports,
+    prefix: &str,
+) -> Result<Option<String>, String> {
+    let ptr_name = format!("{prefix}_ptr");
+    let len_name = format!("{prefix}_len");
+    if !exports.has(&ptr_name) || !exports.has(&len_name) {
+        return Ok(None);
+    }
+    let len = exports.call_u32(&len_name).await?;
+    if len > MAX_TEXT_LEN {
+        return Err(format!(
+            "level `{prefix}` is {len} bytes, at most {MAX_TEXT_LEN} are allowed"
+        ));
+    }
+    if len == 0 {
+        return Ok(Some(String::new()));
+    }
+    let ptr = exports.call_u32(&ptr_name).await?;
+    let bytes = read_memory(exports, ptr, len, prefix).await?;
+    String::from_utf8(bytes)
+        .map(Some)
+        .map_err(|_| format!("level `{prefix}` is not valid UTF-8"))
+}
+
+async fn read_semantics(
+    exports: &mut impl LevelExports,
+    metadata: &LevelMetadata,
+) -> Result<Option<SemanticGrid>, String> {
+    if !exports.has("bvb_level_semantics_ptr") || !exports.has("bvb_level_semantics_len") {
+        return Ok(None);
+    }
+    let (Some(grid_width), Some(grid_height)) = (metadata.grid_width, metadata.grid_height) else {
+        return Err("level exports semantics without grid dimensions".to_string());
+    };
+    if grid_width > MAX_LEVEL_DIMENSION || grid_height > MAX_LEVEL_DIMENSION {
+        return Err(format!(
+            "level grid {grid_width}x{grid_height} exceeds {MAX_LEVEL_DIMENSION} tiles"
+        ));
+    }
+    let len = exports.call_u32("bvb_level_semantics_len").await?;
+    let ptr = exports.call_u32("bvb_level_semantics_ptr").await?;
+    let bytes = read_memory(exports, ptr, len, "semantic grid").await?;
+    let tile_size = metadata.tile_size.unwrap_or(1);
+    SemanticGrid::from_bytes(grid_width, grid_height, tile_size, &bytes).map(Some)
+}
+
+// Reads the optional layer table and every layer's pixels. The level is `level_width` x
+// `level_height` level pixels.
+async fn read_layers(
+    exports: &mut impl LevelExports,
+    level_width: u32,
+    level_height: u32,
+    scale: u32,
+) -> Result<Vec<LevelLayer>, String> {
+    let names = [
+        "bvb_level_layers_ptr",
+        "bvb_level_layers_len",
+        "bvb_level_layer_pixels_ptr",
+        "bvb_level_layer_pixels_len",
+    ];
+    if !names.iter().any(|name| exports.has(name)) {
+        return Ok(Vec::new());
+    }
+    if let Some(missing) = names.iter().find(|name| !exports.has(name)) {
+        return Err(format!("level exports layers without `{missing}`"));
+    }
+    let len = exports.call_u32("bvb_level_layers_len").await?;
+    if len == 0 {
+        return Ok(Vec::new());
+    }
+    let ptr = exports.call_u32("bvb_level_layers_ptr").await?;
+    let infos = layers_from_bytes(&read_memory(exports, ptr, len, "layer table").await?)?;
+    if infos.len() > MAX_LAYERS {
+        return Err(format!(
+            "level has {} layers, at most {MAX_LAYERS} are allowed",
+            infos.len()
+        ));
+    }
+
+    let mut layers = Vec::with_capacity(infos.len());
+    for (index, info) in (0u32..).zip(infos) {
+        let whole_level = info.x == 0
+            && info.y == 0
+            && info.width == level_width
+            && info.height == level_height
+            && info.frames == 1;
+        if (index == 0) != (info.kind == LayerKind::Ground) || (index == 0 && !whole_level) {
+            return Err(format!(
+                "level layer {index}: only the first layer is the ground, which must cover the \
+                 whole level in one frame"
+            ));
+        }
+        let fits = u64::from(info.x) + u64::from(info.width) <= u64::from(level_width)
+            && u64::from(info.y) + u64::from(info.height) <= u64::from(level_height);
+        if !fits {
+            return Err(format!(
+                "level layer {index} at {},{} ({}x{}) lies outside the {level_width}x{level_height} level",
+                info.x, info.y, info.width, info.height
+            ));
+        }
+        // Frames stack vertically in one texture.
+        if u64::from(info.height) * u64::from(scale) * u64::from(info.frames)
+            > u64::from(MAX_LEVEL_DIMENSION)
+        {
+            return Err(format!(
+                "level layer {index} stacks {} frames {}px high, more than {MAX_LEVEL_DIMENSION}px",
+                info.frames,
+                info.height * scale
+            ));
+        }
+        let expected_len = info.pixels_len(scale);
+        let pixels_len = exports
+            .call_u32_with("bvb_level_layer_pixels_len", &[index])
+            .await?;
+        if pixels_len as usize != expected_len {
+            return Err(format!(
+                "level layer {index} is {pixels_len} bytes, expected {expected_len}"
+            ));
+        }
+        let pixels_ptr = exports
+            .call_u32_with("bvb_level_layer_pixels_ptr", &[index])
+            .await?;
+        let pixels = read_memory(exports, pixels_ptr, pixels_len, "layer pixels").await?;
+        layers.push(LevelLayer { info, pixels });
+    }
+    Ok(layers)
+}
+
+// Reads the optional hazard table. Hazards must lie inside the level and, when the module exports
+// a grid, cover whole tiles that carry their kind.
+async fn read_hazards(
+    exports: &mut impl LevelExports,
+    level_width: u32,
+    level_height: u32,
+    grid: Option<&SemanticGrid>,
+) -> Result<Vec<HazardInfo>, String> {
+    let has_ptr = exports.has("bvb_level_hazards_ptr");
+    let has_len = exports.has("bvb_level_hazards_len");
+    if !has_ptr && !has_len {
+        return Ok(Vec::new());
+    }
+    if has_ptr != has_len {
+        return Err(
+            "level exports only one of `bvb_level_hazards_ptr` and `bvb_level_hazards_len`"
+                .to_string(),
+        );
+    }
+    let len = exports.call_u32("bvb_level_hazards_len").await?;
+    if len == 0 {
+        return Ok(Vec::new());
+    }
+    let ptr = exports.call_u32("bvb_level_hazards_ptr").await?;
+    let hazards = hazards_from_bytes(&read_memory(exports, ptr, len, "hazard table").await?)?;
+    if hazards.len() > MAX_HAZARDS {
+        return Err(format!(
+            "level has {} hazards, at most {MAX_HAZARDS} are allowed",
+            hazards.len()
+        ));
+    }
+
+    for (index, hazard) in hazards.iter().enumerate() {
+        let fits = u64::from(hazard.x) + u64::from(hazard.width) <= u64::from(level_width)
+            && u64::from(hazard.y) + u64::from(hazard.height) <= u64::from(level_height);
+        if !fits {
+            return Err(format!(
+                "level hazard {index} at {},{} ({}x{}) lies outside the {level_width}x{level_height} level",
+                hazard.x, hazard.y, hazard.width, hazard.height
+            ));
+        }
+        let Some(grid) = grid else {
+            continue;
+        };
+        let tile = grid.tile_size.max(1);
+        let aligned = [hazard.x, hazard.y, hazard.width, hazard.height]
+            .iter()
+            .all(|n| n % tile == 0);
+        if !aligned {
+            return Err(format!(
+                "level hazard {index} does not cover whole {tile} px tiles"
+            ));
+        }
+        for ty in hazard.y / tile..(hazard.y + hazard.height) / tile {
+            for tx in hazard.x / tile..(hazard.x + hazard.width) / tile {
+                if grid.get(tx, ty).map(|t| t.hazard) != Some(hazard.kind) {
+                    return Err(format!(
+                        "level hazard {index} is {:?}, but tile {tx},{ty} of the semantic grid is not",
+                        hazard.kind
+                    ));
+                }
+            }
+        }
+    }
+    Ok(hazards)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod native {
+    use wasmi::core::TrapCode;
+    use wasmi::{StoreLimits, StoreLimitsBuilder};
+
+    use super::{check_declared_sizes, LevelExports, LevelLimits};
+
+    pub(super) struct WasmiExports {
+        store: wasmi::Store<StoreLimits>,
+        instance: wasmi::Instance,
+        memory: wasmi::Memory,
+        limits: LevelLimits,
+    }
+
+    impl WasmiExports {
+        pub(super) fn instantiate(wasm: &[u8], limits: LevelLimits) -> Result<Self, String> {
+            check_declared_sizes(wasm, &limits)?;
+            let mut config = wasmi::Config::default();
+            config.consume_fuel(true);
+            let engine = wasmi::Engine::new(&config);
+            let module = wasmi::Module::new(&engine, wasm)
+                .map_err(|err| format!("invalid level module: {err}"))?;
+            // Level modules import nothing. An empty linker would reject them too, but without
+            // naming the import.
+            if let Some(import) = module.imports().next() {
+                return Err(format!(
+                    "level module imports `{}.{}`, level modules may not import anything",
+                    import.module(),
+                    import.name()
+                ));
+            }
+
+            let store_limits = StoreLimitsBuilder::new()
+                .memory_size(limits.memory_bytes)
+                .table_elements(limits.table_elements)
+                .instances(1)
+                .memories(1)
+                .tables(1)
+                .trap_on_grow_failure(true)
+                .build();
+            let mut store = wasmi::Store::new(&engine, store_limits);
+            store.limiter(|store_limits| store_limits);
+            set_fuel(&mut store, &limits)?;
+            let linker = wasmi::Linker::<StoreLimits>::new(&engine);
+            let instance = linker
+                .instantiate(&mut store, &module)
+                .and_then(|pre| pre.start(&mut store))
+                .map_err(|err| match err.as_trap_code() {
+                    Some(TrapCode::OutOfFuel) => format!(
+                        "level module start ran out of fuel ({} units)",
+                        limits.fuel_per_call
+                    ),
+                    _ => format!(
+                        "failed to instantiate level module within {} bytes of memory: {err}",
+                        limits.memory_bytes
+                    ),
+                })?;
+            let memory = instance
+                .get_memory(&store, "memory")
+                .ok_or_else(|| "level module does not export its memory".to_string())?;
+            Ok(Self {
+                store,
+                instance,
+                memory,
+                limits,
+            })
+        }
+
+        fn typed<R: wasmi::WasmResults>(
+            &mut self,
+            name: &str,
+        ) -> Result<wasmi::TypedFunc<(), R>, String> {
+            set_fuel(&mut self.store, &self.limits)?;
+            self.instance
+                .get_typed_func::<(), R>(&self.store, name)
+                .map_err(|err| format!("level export `{name}` has an unexpected signature: {err}"))
+        }
+
+        fn func(&mut self, name: &str) -> Result<wasmi::Func, String> {
+            set_fuel(&mut self.store, &self.limits)?;
+            self.instance
+                .get_func(&self.store, name)
+                .ok_or_else(|| format!("level module is missing export `{name}`"))
+        }
+
+        fn trapped(&self, name: &str, err: wasmi::Error) -> String {
+            match err.as_trap_code() {
+                Some(TrapCode::OutOfFuel) => format!(
+                    "level export `{name}` ran out of fuel ({} units)",
+                    self.limits.fuel_per_call
+                ),
+                Some(TrapCode::GrowthOperationLimited) => format!(
+                    "level export `{name}` tried to grow memory past {} bytes",
+                    self.limits.memory_bytes
+                ),
+                _ => format!("level export `{name}` trapped: {err}"),
+            }
+        }
+    }
+
+    // Every call starts with a full tank, so the limit is per call rather than per instance.
+    fn set_fuel(store: &mut wasmi::Store<StoreLimits>, limits: &LevelLimits) -> Result<(), String> {
+        store
+            .set_fuel(limits.fuel_per_call)
+            .map_err(|err| format!("failed to meter level module: {err}"))
+    }
+
+    impl LevelExports for WasmiExports {
+        fn has(&self, name: &str) -> bool {
+            self.instance.get_func(&self.store, name).is_some()
+        }
+
+        async fn call(&mut self, name: &str) -> Result<(), String> {
+            self.typed::<()>(name)?
+                .call(&mut self.store, ())
+                .map_err(|err| self.trapped(name, err))
+        }
+
+        async fn call_u32(&mut self, name: &str) -> Result<u32, String> {
+            self.typed::<u32>(name)?
+                .call(&mut self.store, ())
+                .map_err(|err| self.trapped(name, err))
+        }
+
+        async fn call_u64(&mut self, name: &str) -> Result<u64, String> {
+            self.typed::<u64>(name)?
+                .call(&mut self.store, ())
+                .map_err(|err| self.trapped(name, err))
+        }
+
+        async fn call_with(&mut self, name: &str, args: &[u32]) -> Result<(), String> {
+            let func = self.func(name)?;
+            let inputs: Vec<wasmi::Val> = args
+                .iter()
+                .map(|&arg| wasmi::Val::I32(arg as i32))
+                .collect();
+            func.call(&mut self.store, &inputs, &mut [])
+                .map_err(|err| self.trapped(name, err))
+        }
+
+        async fn call_u32_with(&mut self, name: &str, args: &[u32]) -> Result<u32, String> {
+            let func = self.func(name)?;
+            let inputs: Vec<wasmi::Val> = args
+                .iter()
+                .map(|&arg| wasmi::Val::I32(arg as i32))
+                .collect();
+            let mut outputs = [wasmi::Val::I32(0)];
+            func.call(&mut self.store, &inputs, &mut outputs)
+                .map_err(|err| self.trapped(name, err))?;
+            match outputs[0] {
+                wasmi::Val::I32(value) => Ok(value as u32),
+                _ => Err(format!("level export `{name}` did not return an i32")),
+            }
+        }
+
+        async fn memory_len(&self) -> Result<usize, String> {
+            Ok(self.memory.data(&self.store).len())
+        }
+
+        async fn copy_memory(&self, start: usize, len: usize) -> Result<Vec<u8>, String> {
+            Ok(self.memory.data(&self.store)[start..start + len].to_vec())
+        }
+
+        async fn write_memory(&mut self, start: usize, bytes: &[u8]) -> Result<(), String> {
+            self.memory.data_mut(&mut self.store)[start..start + bytes.len()]
+                .copy_from_slice(bytes);
+            Ok(())
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod browser {
+    use std::cell::Cell;
+
+    use js_sys::{Array, Object, Promise, Reflect, Uint8Array, WebAssembly};
+    use wasm_bindgen::{JsCast, JsValue};
+    use wasm_bindgen_futures::JsFuture;
+    use web_sys::{Blob, BlobPropertyBag, ErrorEvent, MessageEvent, Url, Worker};
+
+    use super::{check_declared_sizes, LevelExports, LevelLimits};
+
+    const WORKER_SCRIPT: &str = include_str!("level_worker.js");
+
+    // A level module instantiated in its own Worker, so a call that never returns can be stopped
+    // by terminating the Worker. Every call is one request and reply.
+    pub(super) struct WorkerExports {
+        worker: Worker,
+        functions: Vec<String>,
+        timeout_ms: i32,
+        stopped: Cell<bool>,
+    }
+
+    fn js_error(context: &str, err: JsValue) -> String {
+        match err.as_string() {
+            Some(message) => format!("{context}: {message}"),
+            None => format!("{context}: {err:?}"),
+        }
+    }
+
+    pub(crate) async fn fetch_bytes(url: &str) -> Result<Vec<u8>, String> {
+        let window = gloo::utils::window();
+        let response = JsFuture::from(window.fetch_with_str(url))
+            .await
+            .map_err(|err| js_error(&format!("failed to fetch {url}"), err))?
+            .dyn_into::<web_sys::Response>()
+            .map_err(|_| format!("fetch of {url} did not return a response"))?;
+        if !response.ok() {
+            return Err(format!(
+                "fetch of {url} failed with HTTP {}",
+                response.status()
+            ));
+        }
+        let buffer = response
+            .array_buffer()
+            .map_err(|err| js_error(&format!("failed to read {url}"), err))?;
+        let buffer = JsFuture::from(buffer)
+            .await
+            .map_err(|err| js_error(&format!("failed to read {url}"), err))?;
+        Ok(Uint8Array::new(&buffer).to_vec())
+    }
+
+    fn field(object: &JsValue, key: &str) -> Option<JsValue> {
+        Reflect::get(object, &key.into()).ok()
+    }
+
+    fn text_field(object: &JsValue, key: &str) -> String {
+        field(object, key)
+            .and_then(|value| value.as_string())
+            .unwrap_or_default()
+    }
+
+    fn request(op: &str, fields: &[(&str, JsValue)]) -> Object {
+        let message = Object::new();
+        let _ = Reflect::set(&message, &"op".into(), &op.into());
+        for (key, value) in fields {
+            let _ = Reflect::set(&message, &(*key).into(), value);
+        }
+        message
+    }
+
+    fn spawn_worker() -> Result<(Worker, String), String> {
+        let parts = Array::of1(&WORKER_SCRIPT.into());
+        let options = BlobPropertyBag::new();
+        options.set_type("text/javascript");
+        let blob = Blob::new_with_str_sequence_and_options(&parts, &options)
+            .map_err(|err| js_error("failed to create the level worker script", err))?;
+        let url = Url::create_object_url_with_blob(&blob)
+            .map_err(|err| js_error("failed to create the level worker URL", err))?;
+        match Worker::new(&url) {
+            Ok(worker) => Ok((worker, url)),
+            Err(err) => {
+                let _ = Url::revoke_object_url(&url);
+                Err(js_error("failed to start the level worker", err))
+            }
+        }
+    }
+
+    impl WorkerExports {
+        pub(super) async fn instantiate(wasm: &[u8], limits: &LevelLimits) -> Result<Self, String> {
+            check_declared_sizes(wasm, limits)?;
+            // Compiling runs none of the module's code, so it can stay on this thread.
+            let module: WebAssembly::Module =
+                JsFuture::from(WebAssembly::compile(&Uint8Array::from(wasm).into()))
+                    .await
+                    .and_then(|module| module.dyn_into())
+                    .map_err(|err| js_error("invalid level module", err))?;
+            // Level modules import nothing. An empty import object would reject them too, but
+            // without naming the import.
+            if let Some(import) = WebAssembly::Module::imports(&module).iter().next() {
+                return Err(format!(
+                    "level module imports `{}.{}`, level modules may not import anything",
+                    text_field(&import, "module"),
+                    text_field(&import, "name")
+                ));
+            }
+            let exports = WebAssembly::Module::exports(&module);
+            let exported = |name: &str, kind: &str| {
+                exports.iter().any(|export| {
+                    text_field(&export, "name") == name && text_field(&export, "kind") == kind
+                })
+            };
+            if !exported("memory", "memory") {
+                return Err("level module does not export its memory".to_string());
+            }
+            let functions = exports
+                .iter()
+                .filter(|export| text_field(export, "kind") == "function")
+                .map(|export| text_field(&export, "name"))
+                .collect();
+
+            let (worker, url) = spawn_worker()?;
+            let instance = Self {
+                worker,
+                functions,
+                timeout_ms: i32::try_from(limits.time_per_call.as_millis()).unwrap_or(i32::MAX),
+                stopped: Cell::new(false),
+            };
+            // The start function runs here, within the same time limit as every export.
+            let started = instance
+                .send(
+                    "level module start",
+                    &request("instantiate", &[("module", module.into())]),
+                )
+                .await;
+            // The worker has loaded its script once it replies, or it never will.
+            let _ = Url::revoke_object_url(&url);
+            started?;
+            Ok(instance)
+        }
+
+        // Posts `message` and waits for the reply, terminating the worker when none arrives in
+        // time. `what` names the request in errors.
+        async fn send(&self, what: &str, message: &Object) -> Result<JsValue, String> {
+            if self.stopped.get() {
+                return Err(format!(
+                    "{what} cannot run, the level module was stopped after running over its time limit"
+                ));
+            }
+            let window = gloo::utils::window();
+            let mut timer = Ok(0);
+            // A timeout rejects with `undefined`; worker errors reject with an `ErrorEvent`.
+            let reply = Promise::new(&mut |resolve, reject| {
+                self.worker.set_onmessage(Some(&resolve));
+                self.worker.set_onerror(Some(&reject));
+                timer = window.set_timeout_with_callback_and_timeout_and_arguments_0(
+                    &reject,
+                    self.timeout_ms,
+                );
+            });
+            let timer = timer.map_err(|err| js_error("failed to start the level timer", err))?;
+            if let Err(err) = self.worker.post_message(message) {
+                window.clear_timeout_with_handle(timer);
+                return Err(js_error(
+                    &format!("failed to send {what} to the level worker"),
+                    err,
+                ));
+            }
+            let reply = JsFuture::from(reply).await;
+            window.clear_timeout_with_handle(timer);
+
+            match reply {
+                Ok(event) => {
+                    let data = event.unchecked_into::<MessageEvent>().data();
+                    if field(&data, "ok").and_then(|ok| ok.as_bool()) == Some(true) {
+                        Ok(field(&data, "value").unwrap_or(JsValue::UNDEFINED))
+                    } else {
+                        Err(format!("{what} trapped: {}", text_field(&data, "error")))
+                    }
+                }
+                Err(err) if err.is_undefined() => {
+                    self.worker.terminate();
+                    self.stopped.set(true);
+                    Err(format!(
+                        "{what} ran over its time limit ({} ms)",
+                        self.timeout_ms
+                    ))
+                }
+                Err(err) => match err.dyn_ref::<ErrorEvent>() {
+                    Some(event) => Err(format!("{what} failed: {}", event.message())),
+                    None => Err(js_error(&format!("{what} failed"), err)),
+                },
+            }
+        }
+
+        async fn invoke(&self, name: &str, args: &[u32]) -> Result<JsValue, String> {
+            if !self.has(name) {
+                return Err(format!("level module is missing export `{name}`"));
+            }
+            let args: Array = args.iter().map(|&arg| JsValue::from(arg)).collect();
+            let message = request("call", &[("name", name.into()), ("args", args.into())]);
+            self.send(&format!("level export `{name}`"), &message).await
+        }
+    }
+
+    impl Drop for WorkerExports {
+        fn drop(&mut self) {
+            self.worker.terminate();
+        }
+    }
+
+    impl LevelExports for WorkerExports {
+        fn has(&self, name: &str) -> bool {
+            self.functions.iter().any(|function| function == name)
+        }
+
+        async fn call(&mut self, name: &str) -> Result<(), String> {
+            self.invoke(name, &[]).await.map(|_| ())
+        }
+
+        async fn call_u32(&mut self, name: &str) -> Result<u32, String> {
+            self.call_u32_with(name, &[]).await
+        }
+
+        async fn call_u64(&mut self, name: &str) -> Result<u64, String> {
+            // i64 results arrive as signed BigInts.
+            let value = self.invoke(name, &[]).await?;
+            i64::try_from(value)
+                .map(|value| value as u64)
+                .map_err(|_| format!("level export `{name}` did not return a 64-bit integer"))
+        }
+
+        async fn call_with(&mut self, name: &str, args: &[u32]) -> Result<(), String> {
+            self.invoke(name, args).await.map(|_| ())
+        }
+
+        async fn call_u32_with(&mut self, name: &str, args: &[u32]) -> Result<u32, String> {
+            // i32 results arrive as JS numbers, possibly negative for values above i32::MAX.
+            self.invoke(name, args)
+                .await?
+                .as_f64()
+                .map(|value| value as i32 as u32)
+                .ok_or_else(|| format!("level export `{name}` did not return a number"))
+        }
+
+        async fn memory_len(&self) -> Result<usize, String> {
+            self.send("level memory size", &request("memory_len", &[]))
+                .await?
+                .as_f64()
+                .map(|len| len as usize)
+                .ok_or_else(|| "level worker did not report the memory size".to_string())
+        }
+
+        async fn copy_memory(&self, start: usize, len: usize) -> Result<Vec<u8>, String> {
+            let message = request(
+                "read",
+                &[
+                    ("start", (start as u32).into()),
+                    ("len", (len as u32).into()),
+                ],
+            );
+            let bytes = self.send("level memory read", &message).await?;
+            bytes
+                .dyn_into::<Uint8Array>()
+                .map(|bytes| bytes.to_vec())
+                .map_err(|_| "level worker did not return memory contents".to_string())
+        }
+
+        async fn write_memory(&mut self, start: usize, bytes: &[u8]) -> Result<(), String> {
+            let message = request(
+                "write",
+                &[
+                    ("start", (start as u32).into()),
+                    ("bytes", Uint8Array::from(bytes).into()),
+                ],
+            );
+            self.send("level memory write", &message).await.map(|_| ())
+        }
+    }
+}
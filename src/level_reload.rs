@@ -0,0 +1,143 @@
+/// File name of the manifest `scripts/build_levels.sh` writes next to the level modules.
+pub const LEVEL_MANIFEST_FILE: &str = "levels.sha256";
+
+/// Content hashes of the level modules in a directory, in `shasum -a 256` output format: one
+/// `<hash>  <file>` line per module.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LevelManifest {
+    entries: Vec<(String, String)>,
+}
+
+impl LevelManifest {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut entries = Vec::new();
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let Some((hash, file)) = line.split_once(char::is_whitespace) else {
+                return Err(format!(
+                    "level manifest line {} has no file name",
+                    index + 1
+                ));
+            };
+            if !hash.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+                return Err(format!(
+                    "level manifest line {} has an invalid hash `{hash}`",
+                    index + 1
+                ));
+            }
+            // `*` marks binary mode in shasum output.
+            let file = file.trim_start().trim_start_matches('*');
+            let file = file.rsplit('/').next().unwrap_or(file);
+            entries.push((file.to_string(), hash.to_ascii_lowercase()));
+        }
+        Ok(Self { entries })
+    }
+
+    /// The hash listed for `file`, matched by file name.
+    pub fn hash(&self, file: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(name, _)| name == file)
+            .map(|(_, hash)| hash.as_str())
+    }
+}
+
+// Splits a module URL such as `assets/levels/mall_parking_lot.wasm?v=abi2` into its manifest URL
+// and file name.
+#[cfg(target_arch = "wasm32")]
+fn manifest_location(wasm_url: &str) -> (String, &str) {
+    let path = wasm_url.split(['?', '#']).next().unwrap_or(wasm_url);
+    match path.rsplit_once('/') {
+        Some((dir, file)) => (format!("{dir}/{LEVEL_MANIFEST_FILE}"), file),
+        None => (LEVEL_MANIFEST_FILE.to_string(), path),
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub use browser::LevelReloader;
+
+#[cfg(target_arch = "wasm32")]
+mod browser {
+    use super::{manifest_location, LevelManifest};
+    use crate::level::{fetch_bytes, LevelInstance, LevelModule, LevelOptions};
+
+    /// Dev-only level hot reload: polls the level manifest and re-instantiates the module when its
+    /// hash changes.
+    ///
+    /// The reloader only produces `LevelModule`s; passing them to `Renderer::upload_level` swaps
+    /// the level image and leaves sprites, lights, stats and any recording as they are.
+    pub struct LevelReloader {
+        wasm_url: String,
+        manifest_url: String,
+        file: String,
+        options: LevelOptions,
+        hash: Option<String>,
+    }
+
+    impl LevelReloader {
+        /// Watches the module at `wasm_url`, regenerating it with `options` on every reload.
+        pub fn new(wasm_url: &str, options: LevelOptions) -> Self {
+            let (manifest_url, file) = manifest_location(wasm_url);
+            Self {
+                wasm_url: wasm_url.to_string(),
+                file: file.to_string(),
+                manifest_url,
+                options,
+                hash: None,
+            }
+        }
+
+        /// Whether the page was opened with `?devreload=1`.
+        pub fn requested() -> bool {
+            let search = gloo::utils::window()
+                .location()
+                .search()
+                .unwrap_or_default();
+            search
+                .trim_start_matches('?')
+                .split('&')
+                .any(|pair| pair == "devreload=1")
+        }
+
+        /// Options for later reloads, e.g. after the seed picker changes.
+        pub fn set_options(&mut self, options: LevelOptions) {
+            self.options = options;
+        }
+
+        /// Checks the manifest once and returns the regenerated level if the module changed.
+        ///
+        /// The first poll only records the current hash. A module that fails to load is not
+        /// retried until its hash changes again. Polls must not overlap.
+        pub async fn poll(&mut self) -> Result<Option<LevelModule>, String> {
+            // Unique query strings skip the HTTP cache and the service worker's precache.
+            let bust = js_sys::Date::now() as u64;
+            let text = fetch_bytes(&format!("{}?t={bust}", self.manifest_url)).await?;
+            let text = String::from_utf8(text)
+                .map_err(|_| format!("{} is not valid UTF-8", self.manifest_url))?;
+            let manifest = LevelManifest::parse(&text)?;
+            let hash = manifest
+                .hash(&self.file)
+                .ok_or_else(|| format!("{} does not list {}", self.manifest_url, self.file))?;
+            let previous = self.hash.replace(hash.to_string());
+            if previous.is_none() || previous.as_deref() == Some(hash) {
+                return Ok(None);
+            }
+
+            let separator = if self.wasm_url.contains('?') {
+                '&'
+            } else {
+                '?'
+            };
+            let url = format!("{}{separator}reload={hash}", self.wasm_url);
+            let wasm = fetch_bytes(&url).await?;
+            let level = LevelInstance::new(&wasm)
+                .await?
+                .generate(&self.options)
+                .await?;
+            Ok(Some(level))
+        }
+    }
+}
@@ -0,0 +1,34 @@
+// Runs one level module for `LevelInstance` in the browser. The page sends one request at a time
+// and terminates the worker when a reply takes longer than `LevelLimits::time_per_call`.
+let exports = null;
+
+function run(request) {
+  switch (request.op) {
+    case "instantiate":
+      exports = new WebAssembly.Instance(request.module, {}).exports;
+      return undefined;
+    case "call":
+      return exports[request.name](...request.args);
+    case "memory_len":
+      return exports.memory.buffer.byteLength;
+    case "read":
+      return new Uint8Array(exports.memory.buffer, request.start, request.len).slice();
+    case "write":
+      new Uint8Array(exports.memory.buffer).set(request.bytes, request.start);
+      return undefined;
+    default:
+      throw new Error(`unknown level worker request ${request.op}`);
+  }
+}
+
+self.onmessage = (event) => {
+  let value;
+  try {
+    value = run(event.data);
+  } catch (error) {
+    self.postMessage({ ok: false, error: String(error) });
+    return;
+  }
+  const transfer = value instanceof Uint8Array ? [value.buffer] : [];
+  self.postMessage({ ok: true, value }, transfer);
+};
@@ -1,8 +1,66 @@
+mod capture;
+mod hazards;
+mod level;
+mod level_reload;
+#[cfg(target_arch = "wasm32")]
+mod light_layer;
+mod lighting;
+mod particles;
+mod recorder;
+mod replay;
+#[cfg(target_arch = "wasm32")]
+mod sprite_batch;
+mod sprites;
+mod stats;
+
+pub use bvb_level_kit::{
+    ExitSide, FlowField, Hazard, HazardInfo, HazardState, LaneCrossing, LayerInfo, LayerKind,
+    LevelLayer, LevelParams, Marker, NavGraph, Regions, SemanticGrid, SemanticTile, Terrain,
+};
+pub use bvb_raster::{decode_png, encode_apng, encode_png, Canvas, EncodeError, PngError};
+pub use capture::FrameCapture;
+pub use hazards::{HazardOverlay, HAZARD_FRAME_SIZE};
+pub use level::{
+    LevelInstance, LevelLimits, LevelMetadata, LevelModule, LevelOptions, MAX_LEVEL_DIMENSION,
+};
+#[cfg(target_arch = "wasm32")]
+pub use level_reload::LevelReloader;
+pub use level_reload::{LevelManifest, LEVEL_MANIFEST_FILE};
+pub use lighting::{Lighting, Occluder, PointLight, MAX_OCCLUDERS};
+pub use particles::{Curve, Emission, EmitterConfig, Lerp, ParticleEmitter, ParticleSystem};
+pub use recorder::{DrawCommand, FrameLog, RecordedFrame, RecordedImage};
+pub use sprites::{AtlasLayout, SpriteInstance};
+pub use stats::RendererStats;
+
 #[cfg(target_arch = "wasm32")]
 mod wasm_impl {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    use bvb_raster::decode_png;
     use gloo::utils::window;
     use web_sys::HtmlCanvasElement;
 
+    use crate::capture::FrameCapture;
+    use crate::hazards::HazardOverlay;
+    use crate::level::LevelModule;
+    use crate::light_layer::LightLayer;
+    use crate::lighting::Lighting;
+    use crate::recorder::{DrawCommand, FrameLog, FrameRecorder};
+    use crate::sprite_batch::SpriteBatch;
+    use crate::sprites::{AtlasLayout, SpriteInstance};
+    use crate::stats::{backend_label, FrameClock, RendererStats};
+    use crate::HAZARD_FRAME_SIZE;
+    use crate::{HazardState, LayerInfo, LayerKind, LevelLayer};
+
+    const CLEAR_COLOR: wgpu::Color = wgpu::Color {
+        r: 0.95,
+        g: 0.91,
+        b: 0.84,
+        a: 1.0,
+    };
+    const DEFAULT_IMAGE: [u8; 4] = [244, 231, 208, 255];
+
     pub struct Renderer {
         canvas: HtmlCanvasElement,
         backend: wgpu::Backend,
@@ -17,6 +75,34 @@ mod wasm_impl {
         texture_bind_group: wgpu::BindGroup,
         texture_width: u32,
         texture_height: u32,
+        // Image pixels per world (level) pixel; levels rendered at 2x keep 1x world coordinates.
+        level_scale: u32,
+        // CPU copies of the current image and atlas, so a recording started mid-session can include them.
+        image_rgba: Vec<u8>,
+        atlas_rgba: Vec<u8>,
+        sprite_batch: SpriteBatch,
+        sprites: Vec<SpriteInstance>,
+        // A level's animated and foreground layers, each a one-quad batch whose atlas holds its
+        // frames. `layer_batches` is never shorter than `layers`.
+        layers: Vec<LayerInfo>,
+        layer_rgba: Vec<Vec<u8>>,
+        layer_batches: Vec<SpriteBatch>,
+        layer_quads: Vec<SpriteInstance>,
+        layers_shown_ms: f64,
+        // Drawn over the animated layers and under the sprites.
+        hazards: HazardOverlay,
+        hazard_batch: SpriteBatch,
+        hazard_quads: Vec<SpriteInstance>,
+        light_layer: LightLayer,
+        lighting: Option<Lighting>,
+        gpu_timer: Option<GpuTimer>,
+        frame_clock: FrameClock,
+        stats: RendererStats,
+        recorder: Option<FrameRecorder>,
+        recorded_image: u32,
+        recorded_atlas: u32,
+        recorded_layers: Vec<u32>,
+        recorded_hazards: u32,
     }
 
     impl Renderer {
@@ -39,15 +125,18 @@ mod wasm_impl {
                 .await
                 .ok_or_else(|| "failed to request WebGL2 adapter".to_string())?;
 
-            let backend = adapter.get_info().backend;
+            let adapter_info = adapter.get_info();
+            let backend = adapter_info.backend;
 
             let required_limits =
                 wgpu::Limits::downlevel_webgl2_defaults().using_resolution(adapter.limits());
+            // WebGL2 never reports timestamp queries; native and WebGPU adapters may.
+            let required_features = adapter.features() & wgpu::Features::TIMESTAMP_QUERY;
             let (device, queue) = adapter
                 .request_device(
                     &wgpu::DeviceDescriptor {
                         label: Some("blob2d-renderer-device"),
-                        required_features: wgpu::Features::empty(),
+                        required_features,
                         required_limits,
                     },
                     None,
@@ -158,11 +247,48 @@ mod wasm_impl {
                 &queue,
                 &texture_bind_group_layout,
                 &sampler,
-                &[244, 231, 208, 255],
+                &DEFAULT_IMAGE,
                 1,
                 1,
             );
 
+            let sprite_batch = SpriteBatch::new(
+                &device,
+                &queue,
+                format,
+                &texture_bind_group_layout,
+                &sampler,
+            );
+
+            let mut hazard_batch = SpriteBatch::new(
+                &device,
+                &queue,
+                format,
+                &texture_bind_group_layout,
+                &sampler,
+            );
+            let (hazard_atlas, hazard_layout) = HazardOverlay::atlas();
+            hazard_batch.upload_atlas(
+                &device,
+                &queue,
+                &texture_bind_group_layout,
+                &sampler,
+                &hazard_atlas,
+                hazard_layout,
+            );
+
+            let light_layer = LightLayer::new(&device, format, &texture_bind_group_layout);
+
+            let gpu_timer = GpuTimer::new(&device, &queue);
+            let stats = RendererStats {
+                texture_bytes: 4 + sprite_batch.atlas_bytes() + hazard_batch.atlas_bytes(),
+                surface_reconfigures: 1,
+                surface_size: (config.width, config.height),
+                adapter: Some(adapter_info),
+                limits: Some(device.limits()),
+                ..Default::default()
+            };
+
             Ok(Self {
                 canvas,
                 backend,
@@ -177,18 +303,39 @@ mod wasm_impl {
                 texture_bind_group,
                 texture_width: 1,
                 texture_height: 1,
+                level_scale: 1,
+                image_rgba: DEFAULT_IMAGE.to_vec(),
+                atlas_rgba: vec![255; 4],
+                sprite_batch,
+                sprites: Vec::new(),
+                layers: Vec::new(),
+                layer_rgba: Vec::new(),
+                layer_batches: Vec::new(),
+                layer_quads: Vec::new(),
+                layers_shown_ms: 0.0,
+                hazards: HazardOverlay::default(),
+                hazard_batch,
+                hazard_quads: Vec::new(),
+                light_layer,
+                lighting: None,
+                gpu_timer,
+                frame_clock: FrameClock::default(),
+                stats,
+                recorder: None,
+                recorded_image: 0,
+                recorded_atlas: 0,
+                recorded_layers: Vec::new(),
+                recorded_hazards: 0,
             })
         }
 
         pub fn backend_label(&self) -> &'static str {
-            match self.backend {
-                wgpu::Backend::Empty => "Empty",
-                wgpu::Backend::Vulkan => "Vulkan",
-                wgpu::Backend::Metal => "Metal",
-                wgpu::Backend::Dx12 => "DirectX12",
-                wgpu::Backend::Gl => "WebGL2",
-                wgpu::Backend::BrowserWebGpu => "BrowserWebGPU",
-            }
+            backend_label(self.backend)
+        }
+
+        /// Returns a snapshot of frame timing, draw counters and adapter details.
+        pub fn stats(&self) -> RendererStats {
+            self.stats.clone()
         }
 
         pub fn canvas_size(&self) -> (u32, u32) {
@@ -210,7 +357,13 @@ mod wasm_impl {
             self.canvas.set_height(height);
             self.config.width = width;
             self.config.height = height;
+            self.configure_surface();
+        }
+
+        fn configure_surface(&mut self) {
             self.surface.configure(&self.device, &self.config);
+            self.stats.surface_reconfigures += 1;
+            self.stats.surface_size = (self.config.width, self.config.height);
         }
 
         pub fn upload_image(&mut self, rgba: &[u8], width: u32, height: u32) -> Result<(), String> {
@@ -218,6 +371,15 @@ mod wasm_impl {
                 return Err("pasted image has invalid dimensions".to_string());
             }
 
+            self.level_scale = 1;
+            self.set_layers(&[], 1);
+            self.hazards = HazardOverlay::default();
+            self.image_rgba.clear();
+            self.image_rgba.extend_from_slice(rgba);
+            if let Some(recorder) = self.recorder.as_mut() {
+                self.recorded_image = recorder.add_image(rgba, width, height);
+            }
+
             if self.texture_width != width || self.texture_height != height {
                 let (texture, texture_bind_group) = create_texture_and_bind_group(
                     &self.device,
@@ -233,6 +395,7 @@ mod wasm_impl {
                 self.texture_bind_group = texture_bind_group;
                 self.texture_width = width;
                 self.texture_height = height;
+                self.update_texture_bytes();
                 return Ok(());
             }
 
@@ -259,11 +422,216 @@ mod wasm_impl {
             Ok(())
         }
 
+        /// Decodes a PNG file and uploads it as the level image.
+        pub fn upload_png(&mut self, png: &[u8]) -> Result<(), String> {
+            let image = decode_png(png).map_err(|err| err.to_string())?;
+            self.upload_image(&image.pixels, image.width, image.height)
+        }
+
+        /// Uploads a loaded level module's pixels as the level image.
+        ///
+        /// Sprites and lights stay in level pixels for levels rendered at a higher scale. A level
+        /// with layers shows its ground as the image, animates its animated layers under the
+        /// sprites and draws its foreground layers over them.
+        pub fn upload_level(&mut self, level: &LevelModule) -> Result<(), String> {
+            match level.layers.split_first() {
+                Some((ground, layers)) => {
+                    self.upload_image(&ground.pixels, level.width, level.height)?;
+                    self.set_layers(layers, level.scale.max(1));
+                }
+                None => self.upload_image(&level.pixels, level.width, level.height)?,
+            }
+            self.level_scale = level.scale.max(1);
+            self.hazards = HazardOverlay::from_level(level);
+            Ok(())
+        }
+
+        /// Moves hazard `index` of the current level to `state`; the next frame draws it.
+        pub fn set_hazard_state(&mut self, index: usize, state: HazardState) -> Result<(), String> {
+            self.hazards.set_state(index, state)
+        }
+
+        // Replaces the layers drawn with the level image and restarts their animations.
+        fn set_layers(&mut self, layers: &[LevelLayer], scale: u32) {
+            self.layers.clear();
+            self.layer_rgba.clear();
+            self.recorded_layers.clear();
+            self.layer_batches.truncate(layers.len());
+            for (index, layer) in layers.iter().enumerate() {
+                let info = layer.info;
+                if index == self.layer_batches.len() {
+                    self.layer_batches.push(SpriteBatch::new(
+                        &self.device,
+                        &self.queue,
+                        self.config.format,
+                        &self.texture_bind_group_layout,
+                        &self.sampler,
+                    ));
+                }
+                let layout = AtlasLayout {
+                    width: info.width * scale,
+                    height: info.height * scale * info.frames,
+                    frame_width: info.width * scale,
+                    frame_height: info.height * scale,
+                };
+                self.layer_batches[index].upload_atlas(
+                    &self.device,
+                    &self.queue,
+                    &self.texture_bind_group_layout,
+                    &self.sampler,
+                    &layer.pixels,
+                    layout,
+                );
+                if let Some(recorder) = self.recorder.as_mut() {
+                    self.recorded_layers.push(recorder.add_image(
+                        &layer.pixels,
+                        layout.width,
+                        layout.height,
+                    ));
+                }
+                self.layers.push(info);
+                self.layer_rgba.push(layer.pixels.clone());
+            }
+            self.layers_shown_ms = now_ms();
+            self.update_texture_bytes();
+        }
+
+        /// Replaces the sprite atlas; sprites pick cells of `frame_width` x `frame_height` by index.
+        pub fn upload_sprite_atlas(
+            &mut self,
+            rgba: &[u8],
+            width: u32,
+            height: u32,
+            frame_width: u32,
+            frame_height: u32,
+        ) -> Result<(), String> {
+            if width == 0 || height == 0 || frame_width == 0 || frame_height == 0 {
+                return Err("sprite atlas has invalid dimensions".to_string());
+            }
+            let expected_len = (width as usize)
+                .checked_mul(height as usize)
+                .and_then(|pixels| pixels.checked_mul(4));
+            if expected_len != Some(rgba.len()) {
+                return Err("sprite atlas size does not match its dimensions".to_string());
+            }
+
+            self.sprite_batch.upload_atlas(
+                &self.device,
+                &self.queue,
+                &self.texture_bind_group_layout,
+                &self.sampler,
+                rgba,
+                AtlasLayout {
+                    width,
+                    height,
+                    frame_width,
+                    frame_height,
+                },
+            );
+            self.atlas_rgba.clear();
+            self.atlas_rgba.extend_from_slice(rgba);
+            if let Some(recorder) = self.recorder.as_mut() {
+                self.recorded_atlas = recorder.add_image(rgba, width, height);
+            }
+            self.update_texture_bytes();
+            Ok(())
+        }
+
+        /// Sets the sprites drawn over the level image, in level pixel coordinates.
+        ///
+        /// The list is retained and drawn every frame until replaced, e.g. with
+        /// `ParticleSystem::sprites()` after each simulation step.
+        pub fn set_sprites(&mut self, sprites: &[SpriteInstance]) {
+            self.sprites.clear();
+            self.sprites.extend_from_slice(sprites);
+        }
+
+        /// Enables the light layer, multiplied over the level and sprites; `None` turns it off.
+        ///
+        /// Like sprites, the lighting is retained until replaced.
+        pub fn set_lighting(&mut self, lighting: Option<Lighting>) -> Result<(), String> {
+            if let Some(lighting) = lighting.as_ref() {
+                lighting.validate()?;
+            }
+            self.lighting = lighting;
+            Ok(())
+        }
+
+        fn update_texture_bytes(&mut self) {
+            self.stats.texture_bytes =
+                4 * u64::from(self.texture_width) * u64::from(self.texture_height)
+                    + self.sprite_batch.atlas_bytes()
+                    + self.hazard_batch.atlas_bytes()
+                    + self
+                        .layer_batches
+                        .iter()
+                        .map(SpriteBatch::atlas_bytes)
+                        .sum::<u64>()
+                    + self.light_layer.target_bytes();
+        }
+
+        // Uploads per-frame sprite and light data; returns the number of sprites batched.
+        fn prepare_scene(&mut self, world_size: [f32; 2]) -> u32 {
+            let sprites =
+                self.sprite_batch
+                    .prepare(&self.device, &self.queue, &self.sprites, world_size);
+            let elapsed_ms = (now_ms() - self.layers_shown_ms).max(0.0) as u64;
+            self.layer_quads.clear();
+            for (info, batch) in self.layers.iter().zip(&mut self.layer_batches) {
+                let quad = SpriteInstance {
+                    center: [
+                        info.x as f32 + info.width as f32 / 2.0,
+                        info.y as f32 + info.height as f32 / 2.0,
+                    ],
+                    size: [info.width as f32, info.height as f32],
+                    frame: info.frame_at(elapsed_ms),
+                    color: [1.0; 4],
+                };
+                batch.prepare(&self.device, &self.queue, &[quad], world_size);
+                self.layer_quads.push(quad);
+            }
+            self.hazard_quads = self.hazards.sprites(now_ms().max(0.0) as u64);
+            self.hazard_batch
+                .prepare(&self.device, &self.queue, &self.hazard_quads, world_size);
+            self.light_layer.prepare(
+                &self.device,
+                &self.queue,
+                &self.texture_bind_group_layout,
+                &self.sampler,
+                self.lighting.as_ref(),
+                world_size,
+            );
+            self.update_texture_bytes();
+            sprites
+        }
+
+        fn world_size(&self) -> [f32; 2] {
+            let scale = self.level_scale as f32;
+            [
+                self.texture_width as f32 / scale,
+                self.texture_height as f32 / scale,
+            ]
+        }
+
         pub fn render(&mut self) -> Result<(), String> {
+            self.frame_clock.begin(&mut self.stats, now_ms());
+            let result = self.render_frame();
+            self.frame_clock.end(&mut self.stats, now_ms());
+            result
+        }
+
+        fn render_frame(&mut self) -> Result<(), String> {
+            if let Some(timer) = self.gpu_timer.as_mut() {
+                self.device.poll(wgpu::Maintain::Poll);
+                if let Some(ms) = timer.read_back() {
+                    self.stats.gpu_frame_ms = Some(ms);
+                }
+            }
+
             let frame = match self.surface.get_current_texture() {
                 Ok(frame) => frame,
                 Err(wgpu::SurfaceError::Outdated | wgpu::SurfaceError::Lost) => {
-                    self.surface.configure(&self.device, &self.config);
+                    self.configure_surface();
                     self.surface
                         .get_current_texture()
                         .map_err(|err| format!("failed to recover swap chain frame: {err}"))?
@@ -279,46 +647,383 @@ mod wasm_impl {
             let view = frame
                 .texture
                 .create_view(&wgpu::TextureViewDescriptor::default());
+            let world_size = self.world_size();
+            self.stats.sprites_batched = self.prepare_scene(world_size);
             let mut encoder = self
                 .device
                 .create_command_encoder(&wgpu::CommandEncoderDescriptor {
                     label: Some("blob2d-renderer-render-encoder"),
                 });
+            let timestamp_writes = self
+                .gpu_timer
+                .as_ref()
+                .and_then(GpuTimer::pass_timestamp_writes);
+            let timing_frame = timestamp_writes.is_some();
 
-            {
-                let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                    label: Some("blob2d-renderer-render-pass"),
-                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                        view: &view,
-                        resolve_target: None,
-                        ops: wgpu::Operations {
-                            load: wgpu::LoadOp::Clear(wgpu::Color {
-                                r: 0.95,
-                                g: 0.91,
-                                b: 0.84,
-                                a: 1.0,
-                            }),
-                            store: wgpu::StoreOp::Store,
-                        },
-                    })],
-                    depth_stencil_attachment: None,
-                    occlusion_query_set: None,
-                    timestamp_writes: None,
-                });
+            let draw_calls = self.encode_scene(&mut encoder, &view, timestamp_writes);
+            self.stats.draw_calls += draw_calls;
 
-                render_pass.set_pipeline(&self.render_pipeline);
-                render_pass.set_bind_group(0, &self.texture_bind_group, &[]);
-                render_pass.draw(0..3, 0..1);
+            if timing_frame {
+                if let Some(timer) = self.gpu_timer.as_ref() {
+                    timer.resolve(&mut encoder);
+                }
             }
 
             self.queue.submit(Some(encoder.finish()));
             frame.present();
 
+            if self.recorder.is_some() {
+                let mut commands = vec![
+                    DrawCommand::Clear([
+                        CLEAR_COLOR.r as f32,
+                        CLEAR_COLOR.g as f32,
+                        CLEAR_COLOR.b as f32,
+                        CLEAR_COLOR.a as f32,
+                    ]),
+                    DrawCommand::FullscreenImage {
+                        image: self.recorded_image,
+                    },
+                ];
+                commands.extend(self.layer_commands(LayerKind::Animated, world_size));
+                if !self.hazard_quads.is_empty() {
+                    commands.push(DrawCommand::Sprites {
+                        atlas: self.recorded_hazards,
+                        frame_size: [HAZARD_FRAME_SIZE, HAZARD_FRAME_SIZE],
+                        world_size,
+                        sprites: self.hazard_quads.clone(),
+                    });
+                }
+                if !self.sprites.is_empty() {
+                    let layout = self.sprite_batch.layout;
+                    commands.push(DrawCommand::Sprites {
+                        atlas: self.recorded_atlas,
+                        frame_size: [layout.frame_width, layout.frame_height],
+                        world_size,
+                        sprites: self.sprites.clone(),
+                    });
+                }
+                commands.extend(self.layer_commands(LayerKind::Foreground, world_size));
+                if let Some(lighting) = self.lighting.as_ref() {
+                    commands.push(DrawCommand::Lighting {
+                        world_size,
+                        lighting: lighting.clone(),
+                    });
+                }
+                if let Some(recorder) = self.recorder.as_mut() {
+                    recorder.push_frame(now_ms(), self.config.width, self.config.height, commands);
+                }
+            }
+
+            if timing_frame {
+                if let Some(timer) = self.gpu_timer.as_mut() {
+                    timer.request_read_back();
+                }
+            }
+
             Ok(())
         }
+
+        /// Starts recording the draw commands of every rendered frame, dropping any previous log.
+        pub fn start_recording(&mut self) {
+            let mut recorder = FrameRecorder::default();
+            self.recorded_image =
+                recorder.add_image(&self.image_rgba, self.texture_width, self.texture_height);
+            let layout = self.sprite_batch.layout;
+            self.recorded_atlas = recorder.add_image(&self.atlas_rgba, layout.width, layout.height);
+            let (hazard_atlas, hazard_layout) = HazardOverlay::atlas();
+            self.recorded_hazards =
+                recorder.add_image(&hazard_atlas, hazard_layout.width, hazard_layout.height);
+            self.recorded_layers = self
+                .layer_rgba
+                .iter()
+                .zip(&self.layer_batches)
+                .map(|(rgba, batch)| {
+                    recorder.add_image(rgba, batch.layout.width, batch.layout.height)
+                })
+                .collect();
+            self.recorder = Some(recorder);
+        }
+
+        // The recorded form of this frame's layers of `kind`, one command per layer.
+        fn layer_commands(&self, kind: LayerKind, world_size: [f32; 2]) -> Vec<DrawCommand> {
+            self.layers
+                .iter()
+                .zip(&self.layer_batches)
+                .zip(self.recorded_layers.iter().zip(&self.layer_quads))
+                .filter(|((info, _), _)| info.kind == kind)
+                .map(|((_, batch), (&atlas, &quad))| DrawCommand::Sprites {
+                    atlas,
+                    frame_size: [batch.layout.frame_width, batch.layout.frame_height],
+                    world_size,
+                    sprites: vec![quad],
+                })
+                .collect()
+        }
+
+        pub fn is_recording(&self) -> bool {
+            self.recorder.is_some()
+        }
+
+        /// Stops recording and returns the log, or `None` if no recording was running.
+        pub fn stop_recording(&mut self) -> Option<FrameLog> {
+            self.recorder.take().map(FrameRecorder::finish)
+        }
+
+        /// Renders the current scene offscreen at the canvas backing size and reads it back.
+        ///
+        /// The capture goes through the same pipeline and viewport as `render`, so it matches
+        /// what is presented on screen, including how the image is scaled to the canvas.
+        pub fn capture_frame(&mut self) -> Result<FrameCapture, String> {
+            let width = self.config.width;
+            let height = self.config.height;
+            let format = self.config.format;
+            let size = wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            };
+
+            let target = self.device.create_texture(&wgpu::TextureDescriptor {
+                label: Some("blob2d-renderer-capture-target"),
+                size,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format,
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+                view_formats: &[],
+            });
+            let view = target.create_view(&wgpu::TextureViewDescriptor::default());
+
+            let unpadded_row = 4 * width;
+            let padded_row = unpadded_row.div_ceil(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT)
+                * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+            let buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("blob2d-renderer-capture-buffer"),
+                size: u64::from(padded_row) * u64::from(height),
+                usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+
+            let mut encoder = self
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("blob2d-renderer-capture-encoder"),
+                });
+            let world_size = self.world_size();
+            self.prepare_scene(world_size);
+            self.encode_scene(&mut encoder, &view, None);
+            encoder.copy_texture_to_buffer(
+                wgpu::ImageCopyTexture {
+                    texture: &target,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d::ZERO,
+                    aspect: wgpu::TextureAspect::All,
+                },
+                wgpu::ImageCopyBuffer {
+                    buffer: &buffer,
+                    layout: wgpu::ImageDataLayout {
+                        offset: 0,
+                        bytes_per_row: Some(padded_row),
+                        rows_per_image: Some(height),
+                    },
+                },
+                size,
+            );
+            self.queue.submit(Some(encoder.finish()));
+
+            // The GL backend maps synchronously inside `poll`, so the callback has run afterwards.
+            let (sender, receiver) = std::sync::mpsc::channel();
+            buffer
+                .slice(..)
+                .map_async(wgpu::MapMode::Read, move |result| {
+                    let _ = sender.send(result);
+                });
+            self.device.poll(wgpu::Maintain::Wait);
+            receiver
+                .try_recv()
+                .map_err(|_| "frame capture read back did not complete".to_string())?
+                .map_err(|err| format!("failed to map frame capture buffer: {err}"))?;
+
+            let swap_red_blue = matches!(
+                format,
+                wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb
+            );
+            let mut rgba = Vec::with_capacity((unpadded_row * height) as usize);
+            {
+                let mapped = buffer.slice(..).get_mapped_range();
+                for row in mapped.chunks_exact(padded_row as usize) {
+                    rgba.extend_from_slice(&row[..unpadded_row as usize]);
+                }
+            }
+            buffer.unmap();
+
+            if swap_red_blue {
+                for px in rgba.chunks_exact_mut(4) {
+                    px.swap(0, 2);
+                }
+            }
+
+            Ok(FrameCapture {
+                width,
+                height,
+                rgba,
+            })
+        }
+
+        fn encode_scene(
+            &self,
+            encoder: &mut wgpu::CommandEncoder,
+            view: &wgpu::TextureView,
+            timestamp_writes: Option<wgpu::RenderPassTimestampWrites<'_>>,
+        ) -> u32 {
+            let light_draws = self.light_layer.encode_lights(encoder);
+
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("blob2d-renderer-render-pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(CLEAR_COLOR),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                occlusion_query_set: None,
+                timestamp_writes,
+            });
+
+            render_pass.set_pipeline(&self.render_pipeline);
+            render_pass.set_bind_group(0, &self.texture_bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+            let mut layer_draws = 0;
+            for (info, batch) in self.layers.iter().zip(&self.layer_batches) {
+                if info.kind == LayerKind::Animated {
+                    layer_draws += batch.draw(&mut render_pass);
+                }
+            }
+            let hazard_draws = self.hazard_batch.draw(&mut render_pass);
+            let sprite_draws = self.sprite_batch.draw(&mut render_pass);
+            for (info, batch) in self.layers.iter().zip(&self.layer_batches) {
+                if info.kind == LayerKind::Foreground {
+                    layer_draws += batch.draw(&mut render_pass);
+                }
+            }
+            let composite_draws = self.light_layer.draw_composite(&mut render_pass);
+            light_draws + 1 + layer_draws + hazard_draws + sprite_draws + composite_draws
+        }
+    }
+
+    fn now_ms() -> f64 {
+        window()
+            .performance()
+            .map(|performance| performance.now())
+            .unwrap_or(0.0)
+    }
+
+    // Two timestamps bracketing the main render pass, read back one or more frames later.
+    struct GpuTimer {
+        query_set: wgpu::QuerySet,
+        resolve_buffer: wgpu::Buffer,
+        read_buffer: wgpu::Buffer,
+        period_ns: f32,
+        pending: bool,
+        mapped: Arc<AtomicBool>,
+    }
+
+    impl GpuTimer {
+        const QUERY_BYTES: u64 = 2 * wgpu::QUERY_SIZE as u64;
+
+        fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Option<Self> {
+            if !device.features().contains(wgpu::Features::TIMESTAMP_QUERY) {
+                return None;
+            }
+
+            let query_set = device.create_query_set(&wgpu::QuerySetDescriptor {
+                label: Some("blob2d-renderer-timestamp-queries"),
+                ty: wgpu::QueryType::Timestamp,
+                count: 2,
+            });
+            let resolve_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("blob2d-renderer-timestamp-resolve"),
+                size: Self::QUERY_BYTES,
+                usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            });
+            let read_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("blob2d-renderer-timestamp-read"),
+                size: Self::QUERY_BYTES,
+                usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+
+            Some(Self {
+                query_set,
+                resolve_buffer,
+                read_buffer,
+                period_ns: queue.get_timestamp_period(),
+                pending: false,
+                mapped: Arc::new(AtomicBool::new(false)),
+            })
+        }
+
+        // Only time a frame once the previous read back finished, so the buffer is never in use twice.
+        fn pass_timestamp_writes(&self) -> Option<wgpu::RenderPassTimestampWrites<'_>> {
+            if self.pending {
+                return None;
+            }
+            Some(wgpu::RenderPassTimestampWrites {
+                query_set: &self.query_set,
+                beginning_of_pass_write_index: Some(0),
+                end_of_pass_write_index: Some(1),
+            })
+        }
+
+        fn resolve(&self, encoder: &mut wgpu::CommandEncoder) {
+            encoder.resolve_query_set(&self.query_set, 0..2, &self.resolve_buffer, 0);
+            encoder.copy_buffer_to_buffer(
+                &self.resolve_buffer,
+                0,
+                &self.read_buffer,
+                0,
+                Self::QUERY_BYTES,
+            );
+        }
+
+        fn request_read_back(&mut self) {
+            let mapped = Arc::clone(&self.mapped);
+            self.read_buffer
+                .slice(..)
+                .map_async(wgpu::MapMode::Read, move |result| {
+                    if result.is_ok() {
+                        mapped.store(true, Ordering::Release);
+                    }
+                });
+            self.pending = true;
+        }
+
+        fn read_back(&mut self) -> Option<f64> {
+            if !self.pending || !self.mapped.swap(false, Ordering::Acquire) {
+                return None;
+            }
+
+            let ticks = {
+                let view = self.read_buffer.slice(..).get_mapped_range();
+                let mut start = [0u8; 8];
+                let mut end = [0u8; 8];
+                start.copy_from_slice(&view[0..8]);
+                end.copy_from_slice(&view[8..16]);
+                u64::from_le_bytes(end).saturating_sub(u64::from_le_bytes(start))
+            };
+            self.read_buffer.unmap();
+            self.pending = false;
+
+            Some(ticks as f64 * f64::from(self.period_ns) / 1_000_000.0)
+        }
     }
 
-    fn create_texture_and_bind_group(
+    pub(crate) fn create_texture_and_bind_group(
         device: &wgpu::Device,
         queue: &wgpu::Queue,
         layout: &wgpu::BindGroupLayout,
@@ -385,8 +1090,17 @@ pub use wasm_impl::Renderer;
 
 #[cfg(not(target_arch = "wasm32"))]
 mod native_stub {
+    use bvb_raster::decode_png;
     use web_sys::HtmlCanvasElement;
 
+    use crate::capture::FrameCapture;
+    use crate::level::LevelModule;
+    use crate::lighting::Lighting;
+    use crate::recorder::FrameLog;
+    use crate::sprites::SpriteInstance;
+    use crate::stats::RendererStats;
+    use crate::HazardState;
+
     pub struct Renderer;
 
     impl Renderer {
@@ -398,6 +1112,10 @@ mod native_stub {
             "Unavailable"
         }
 
+        pub fn stats(&self) -> RendererStats {
+            RendererStats::default()
+        }
+
         pub fn canvas_size(&self) -> (u32, u32) {
             (0, 0)
         }
@@ -413,9 +1131,60 @@ mod native_stub {
             Ok(())
         }
 
+        pub fn upload_png(&mut self, png: &[u8]) -> Result<(), String> {
+            decode_png(png).map_err(|err| err.to_string())?;
+            Ok(())
+        }
+
+        pub fn upload_level(&mut self, _level: &LevelModule) -> Result<(), String> {
+            Ok(())
+        }
+
+        pub fn set_hazard_state(
+            &mut self,
+            _index: usize,
+            _state: HazardState,
+        ) -> Result<(), String> {
+            Ok(())
+        }
+
         pub fn render(&mut self) -> Result<(), String> {
             Ok(())
         }
+
+        pub fn capture_frame(&mut self) -> Result<FrameCapture, String> {
+            Err("frame capture is only available on wasm32 targets".to_string())
+        }
+
+        pub fn upload_sprite_atlas(
+            &mut self,
+            _rgba: &[u8],
+            _width: u32,
+            _height: u32,
+            _frame_width: u32,
+            _frame_height: u32,
+        ) -> Result<(), String> {
+            Ok(())
+        }
+
+        pub fn set_sprites(&mut self, _sprites: &[SpriteInstance]) {}
+
+        pub fn set_lighting(&mut self, lighting: Option<Lighting>) -> Result<(), String> {
+            match lighting {
+                Some(lighting) => lighting.validate(),
+                None => Ok(()),
+            }
+        }
+
+        pub fn start_recording(&mut self) {}
+
+        pub fn is_recording(&self) -> bool {
+            false
+        }
+
+        pub fn stop_recording(&mut self) -> Option<FrameLog> {
+            None
+        }
     }
 }
 
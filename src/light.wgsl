@@ -0,0 +1,133 @@
+// Keep in sync with MAX_OCCLUDERS in lighting.rs.
+const MAX_OCCLUDERS: u32 = 16u;
+
+struct LightView {
+  world_size: vec2<f32>,
+  occluder_count: u32,
+  padding: u32,
+  // center.xy, half_size.zw per occluder.
+  occluders: array<vec4<f32>, 16>,
+}
+
+struct LightIn {
+  @location(0) position_radius_intensity: vec4<f32>,
+  @location(1) color: vec4<f32>,
+}
+
+struct LightOut {
+  @builtin(position) position: vec4<f32>,
+  @location(0) world: vec2<f32>,
+  @location(1) light: vec4<f32>,
+  @location(2) color: vec3<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> view: LightView;
+
+@vertex
+fn vs_light(@builtin(vertex_index) vertex_index: u32, light: LightIn) -> LightOut {
+  var corners = array<vec2<f32>, 6>(
+    vec2<f32>(-1.0, -1.0),
+    vec2<f32>(1.0, -1.0),
+    vec2<f32>(-1.0, 1.0),
+    vec2<f32>(-1.0, 1.0),
+    vec2<f32>(1.0, -1.0),
+    vec2<f32>(1.0, 1.0),
+  );
+  let center = light.position_radius_intensity.xy;
+  let radius = light.position_radius_intensity.z;
+  let world = center + corners[vertex_index] * radius;
+
+  var output: LightOut;
+  output.position = vec4<f32>(
+    world.x / view.world_size.x * 2.0 - 1.0,
+    1.0 - world.y / view.world_size.y * 2.0,
+    0.0,
+    1.0,
+  );
+  output.world = world;
+  output.light = light.position_radius_intensity;
+  output.color = light.color.rgb;
+  return output;
+}
+
+fn occluder_blocks(occluder: vec4<f32>, origin: vec2<f32>, light_center: vec2<f32>) -> bool {
+  let lo = occluder.xy - occluder.zw;
+  let hi = occluder.xy + occluder.zw;
+  if (all(origin >= lo) && all(origin <= hi)) {
+    return false;
+  }
+
+  var t_min = 0.0;
+  var t_max = 1.0;
+  let delta = light_center - origin;
+  for (var axis = 0; axis < 2; axis++) {
+    if (abs(delta[axis]) < 1e-6) {
+      if (origin[axis] < lo[axis] || origin[axis] > hi[axis]) {
+        return false;
+      }
+      continue;
+    }
+    let t0 = (lo[axis] - origin[axis]) / delta[axis];
+    let t1 = (hi[axis] - origin[axis]) / delta[axis];
+    t_min = max(t_min, min(t0, t1));
+    t_max = min(t_max, max(t0, t1));
+  }
+  return t_min <= t_max;
+}
+
+@fragment
+fn fs_light(input: LightOut) -> @location(0) vec4<f32> {
+  let center = input.light.xy;
+  let radius = input.light.z;
+  let distance = length(input.world - center);
+  if (radius <= 0.0 || distance >= radius) {
+    discard;
+  }
+
+  let count = min(view.occluder_count, MAX_OCCLUDERS);
+  for (var i = 0u; i < count; i++) {
+    if (occluder_blocks(view.occluders[i], input.world, center)) {
+      discard;
+    }
+  }
+
+  let falloff = 1.0 - distance / radius;
+  let amount = falloff * falloff * max(input.light.w, 0.0);
+  return vec4<f32>(input.color * amount, 0.0);
+}
+
+struct CompositeOut {
+  @builtin(position) position: vec4<f32>,
+  @location(0) uv: vec2<f32>,
+}
+
+@vertex
+fn vs_composite(@builtin(vertex_index) vertex_index: u32) -> CompositeOut {
+  var positions = array<vec2<f32>, 3>(
+    vec2<f32>(-1.0, -3.0),
+    vec2<f32>(3.0, 1.0),
+    vec2<f32>(-1.0, 1.0),
+  );
+  var uvs = array<vec2<f32>, 3>(
+    vec2<f32>(0.0, 2.0),
+    vec2<f32>(2.0, 0.0),
+    vec2<f32>(0.0, 0.0),
+  );
+
+  var output: CompositeOut;
+  output.position = vec4<f32>(positions[vertex_index], 0.0, 1.0);
+  output.uv = uvs[vertex_index];
+  return output;
+}
+
+@group(0) @binding(0)
+var light_texture: texture_2d<f32>;
+
+@group(0) @binding(1)
+var light_sampler: sampler;
+
+@fragment
+fn fs_composite(input: CompositeOut) -> @location(0) vec4<f32> {
+  return vec4<f32>(textureSample(light_texture, light_sampler, input.uv).rgb, 1.0);
+}
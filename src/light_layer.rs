@@ -0,0 +1,367 @@
+use crate::lighting::{Lighting, MAX_OCCLUDERS};
+
+// position (2) + radius + intensity + colour (3) + padding floats.
+const INSTANCE_FLOATS: usize = 8;
+const INSTANCE_BYTES: u64 = (INSTANCE_FLOATS * 4) as u64;
+// world size (2 floats) + occluder count + padding, then one vec4 per occluder.
+const VIEW_BYTES: u64 = 16 + 16 * MAX_OCCLUDERS as u64;
+// Rgba8Unorm is renderable and filterable on every WebGL2 context, unlike float formats.
+const LIGHT_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+
+// Light accumulation target at level resolution, multiplied over the scene after sprites.
+pub(crate) struct LightLayer {
+    light_pipeline: wgpu::RenderPipeline,
+    composite_pipeline: wgpu::RenderPipeline,
+    view_buffer: wgpu::Buffer,
+    view_bind_group: wgpu::BindGroup,
+    instance_buffer: wgpu::Buffer,
+    instance_capacity: u64,
+    instance_count: u32,
+    target: Option<LightTarget>,
+    ambient: wgpu::Color,
+    active: bool,
+}
+
+struct LightTarget {
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+    bind_group: wgpu::BindGroup,
+}
+
+impl LightLayer {
+    pub(crate) fn new(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        texture_layout: &wgpu::BindGroupLayout,
+    ) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("blob2d-renderer-light-shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("light.wgsl").into()),
+        });
+
+        let view_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("blob2d-renderer-light-view-layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let view_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("blob2d-renderer-light-view"),
+            size: VIEW_BYTES,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let view_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("blob2d-renderer-light-view-bind-group"),
+            layout: &view_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: view_buffer.as_entire_binding(),
+            }],
+        });
+
+        let light_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("blob2d-renderer-light-pipeline-layout"),
+            bind_group_layouts: &[&view_layout],
+            push_constant_ranges: &[],
+        });
+        let additive = wgpu::BlendComponent {
+            src_factor: wgpu::BlendFactor::One,
+            dst_factor: wgpu::BlendFactor::One,
+            operation: wgpu::BlendOperation::Add,
+        };
+        let light_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("blob2d-renderer-light-pipeline"),
+            layout: Some(&light_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_light",
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: INSTANCE_BYTES,
+                    step_mode: wgpu::VertexStepMode::Instance,
+                    attributes: &wgpu::vertex_attr_array![0 => Float32x4, 1 => Float32x4],
+                }],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_light",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: LIGHT_FORMAT,
+                    blend: Some(wgpu::BlendState {
+                        color: additive,
+                        alpha: additive,
+                    }),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        let composite_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("blob2d-renderer-light-composite-layout"),
+            bind_group_layouts: &[texture_layout],
+            push_constant_ranges: &[],
+        });
+        // scene * light; the scene's alpha is kept as is.
+        let multiply = wgpu::BlendState {
+            color: wgpu::BlendComponent {
+                src_factor: wgpu::BlendFactor::Dst,
+                dst_factor: wgpu::BlendFactor::Zero,
+                operation: wgpu::BlendOperation::Add,
+            },
+            alpha: wgpu::BlendComponent {
+                src_factor: wgpu::BlendFactor::Zero,
+                dst_factor: wgpu::BlendFactor::One,
+                operation: wgpu::BlendOperation::Add,
+            },
+        };
+        let composite_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("blob2d-renderer-light-composite-pipeline"),
+            layout: Some(&composite_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_composite",
+                buffers: &[],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_composite",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(multiply),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        let instance_capacity = 16;
+        let instance_buffer = create_instance_buffer(device, instance_capacity);
+
+        Self {
+            light_pipeline,
+            composite_pipeline,
+            view_buffer,
+            view_bind_group,
+            instance_buffer,
+            instance_capacity,
+            instance_count: 0,
+            target: None,
+            ambient: wgpu::Color::WHITE,
+            active: false,
+        }
+    }
+
+    pub(crate) fn target_bytes(&self) -> u64 {
+        self.target.as_ref().map_or(0, |target| {
+            let size = target.texture.size();
+            4 * u64::from(size.width) * u64::from(size.height)
+        })
+    }
+
+    // Uploads lights and occluders; with `None` the layer is skipped entirely.
+    pub(crate) fn prepare(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        texture_layout: &wgpu::BindGroupLayout,
+        sampler: &wgpu::Sampler,
+        lighting: Option<&Lighting>,
+        world_size: [f32; 2],
+    ) {
+        let Some(lighting) = lighting else {
+            self.active = false;
+            return;
+        };
+        self.active = true;
+
+        let width = (world_size[0].round() as u32).max(1);
+        let height = (world_size[1].round() as u32).max(1);
+        let resized = self.target.as_ref().is_none_or(|target| {
+            let size = target.texture.size();
+            size.width != width || size.height != height
+        });
+        if resized {
+            self.target = Some(LightTarget::new(
+                device,
+                texture_layout,
+                sampler,
+                width,
+                height,
+            ));
+        }
+
+        self.ambient = wgpu::Color {
+            r: f64::from(lighting.ambient[0]),
+            g: f64::from(lighting.ambient[1]),
+            b: f64::from(lighting.ambient[2]),
+            a: 1.0,
+        };
+
+        let mut view = Vec::with_capacity(VIEW_BYTES as usize);
+        view.extend_from_slice(&world_size[0].max(1.0).to_le_bytes());
+        view.extend_from_slice(&world_size[1].max(1.0).to_le_bytes());
+        let occluder_count = lighting.occluders.len().min(MAX_OCCLUDERS);
+        view.extend_from_slice(&(occluder_count as u32).to_le_bytes());
+        view.extend_from_slice(&0u32.to_le_bytes());
+        for slot in 0..MAX_OCCLUDERS {
+            let rect = lighting.occluders.get(slot).map_or([0.0; 4], |occluder| {
+                [
+                    occluder.center[0],
+                    occluder.center[1],
+                    occluder.size[0] * 0.5,
+                    occluder.size[1] * 0.5,
+                ]
+            });
+            for value in rect {
+                view.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        queue.write_buffer(&self.view_buffer, 0, &view);
+
+        self.instance_count = lighting.lights.len() as u32;
+        if lighting.lights.is_empty() {
+            return;
+        }
+        let needed = lighting.lights.len() as u64;
+        if needed > self.instance_capacity {
+            self.instance_capacity = needed.next_power_of_two();
+            self.instance_buffer = create_instance_buffer(device, self.instance_capacity);
+        }
+        let mut bytes = Vec::with_capacity(lighting.lights.len() * INSTANCE_FLOATS * 4);
+        for light in &lighting.lights {
+            let floats = [
+                light.position[0],
+                light.position[1],
+                light.radius,
+                light.intensity,
+                light.color[0],
+                light.color[1],
+                light.color[2],
+                0.0,
+            ];
+            for value in floats {
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        queue.write_buffer(&self.instance_buffer, 0, &bytes);
+    }
+
+    // Clears the light target to ambient and adds every light; returns the draw calls issued.
+    pub(crate) fn encode_lights(&self, encoder: &mut wgpu::CommandEncoder) -> u32 {
+        let Some(target) = self.target.as_ref().filter(|_| self.active) else {
+            return 0;
+        };
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("blob2d-renderer-light-pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &target.view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(self.ambient),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+        if self.instance_count == 0 {
+            return 0;
+        }
+        render_pass.set_pipeline(&self.light_pipeline);
+        render_pass.set_bind_group(0, &self.view_bind_group, &[]);
+        render_pass.set_vertex_buffer(
+            0,
+            self.instance_buffer
+                .slice(..u64::from(self.instance_count) * INSTANCE_BYTES),
+        );
+        render_pass.draw(0..6, 0..self.instance_count);
+        1
+    }
+
+    pub(crate) fn draw_composite<'pass>(
+        &'pass self,
+        render_pass: &mut wgpu::RenderPass<'pass>,
+    ) -> u32 {
+        let Some(target) = self.target.as_ref().filter(|_| self.active) else {
+            return 0;
+        };
+        render_pass.set_pipeline(&self.composite_pipeline);
+        render_pass.set_bind_group(0, &target.bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+        1
+    }
+}
+
+impl LightTarget {
+    fn new(
+        device: &wgpu::Device,
+        texture_layout: &wgpu::BindGroupLayout,
+        sampler: &wgpu::Sampler,
+        width: u32,
+        height: u32,
+    ) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("blob2d-renderer-light-target"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: LIGHT_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("blob2d-renderer-light-bind-group"),
+            layout: texture_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+            ],
+        });
+
+        Self {
+            texture,
+            view,
+            bind_group,
+        }
+    }
+}
+
+fn create_instance_buffer(device: &wgpu::Device, capacity: u64) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("blob2d-renderer-light-instances"),
+        size: capacity * INSTANCE_BYTES,
+        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
@@ -0,0 +1,120 @@
+use crate::sprites::SpriteInstance;
+
+/// Occluders are uploaded as one uniform array, which keeps the shadow test WebGL2-compatible.
+pub const MAX_OCCLUDERS: usize = 16;
+
+/// A light with a smooth quadratic falloff that reaches zero at `radius` (level pixels).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PointLight {
+    pub position: [f32; 2],
+    pub radius: f32,
+    /// Linear RGB colour.
+    pub color: [f32; 3],
+    pub intensity: f32,
+}
+
+/// Axis-aligned rectangle, in level pixels, that blocks light from reaching what lies behind it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Occluder {
+    pub center: [f32; 2],
+    pub size: [f32; 2],
+}
+
+impl Occluder {
+    /// Uses the sprite's quad as its shadow shape.
+    pub fn from_sprite(sprite: &SpriteInstance) -> Self {
+        Self {
+            center: sprite.center,
+            size: sprite.size,
+        }
+    }
+
+    fn contains(&self, point: [f32; 2]) -> bool {
+        (point[0] - self.center[0]).abs() <= self.size[0] * 0.5
+            && (point[1] - self.center[1]).abs() <= self.size[1] * 0.5
+    }
+
+    // Slab test of the segment `from -> to` against the rectangle.
+    fn blocks(&self, from: [f32; 2], to: [f32; 2]) -> bool {
+        let mut t_min = 0.0f32;
+        let mut t_max = 1.0f32;
+        for axis in 0..2 {
+            let lo = self.center[axis] - self.size[axis] * 0.5;
+            let hi = self.center[axis] + self.size[axis] * 0.5;
+            let delta = to[axis] - from[axis];
+            if delta.abs() < 1e-6 {
+                if from[axis] < lo || from[axis] > hi {
+                    return false;
+                }
+                continue;
+            }
+            let t0 = (lo - from[axis]) / delta;
+            let t1 = (hi - from[axis]) / delta;
+            t_min = t_min.max(t0.min(t1));
+            t_max = t_max.min(t0.max(t1));
+            if t_min > t_max {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Light layer multiplied over the scene: ambient light plus point lights, with optional shadows.
+///
+/// The accumulated light is clamped to 1.0 per channel, so lighting only ever darkens or tints the scene.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Lighting {
+    /// Linear RGB light everywhere, before point lights are added.
+    pub ambient: [f32; 3],
+    pub lights: Vec<PointLight>,
+    /// At most `MAX_OCCLUDERS`; occluders are lit themselves but shadow everything behind them.
+    pub occluders: Vec<Occluder>,
+}
+
+impl Default for Lighting {
+    fn default() -> Self {
+        Self {
+            ambient: [1.0; 3],
+            lights: Vec::new(),
+            occluders: Vec::new(),
+        }
+    }
+}
+
+impl Lighting {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.occluders.len() > MAX_OCCLUDERS {
+            return Err(format!(
+                "lighting has {} occluders, at most {MAX_OCCLUDERS} are supported",
+                self.occluders.len()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Light reaching `point` (level pixels). This is the same model the GPU light pass evaluates.
+    pub fn light_at(&self, point: [f32; 2]) -> [f32; 3] {
+        let mut light = self.ambient;
+        for source in &self.lights {
+            let dx = point[0] - source.position[0];
+            let dy = point[1] - source.position[1];
+            let distance = (dx * dx + dy * dy).sqrt();
+            if source.radius <= 0.0 || distance >= source.radius {
+                continue;
+            }
+            let shadowed = self.occluders.iter().any(|occluder| {
+                !occluder.contains(point) && occluder.blocks(point, source.position)
+            });
+            if shadowed {
+                continue;
+            }
+            let falloff = 1.0 - distance / source.radius;
+            let amount = falloff * falloff * source.intensity.max(0.0);
+            for (channel, color) in light.iter_mut().zip(source.color) {
+                *channel += color * amount;
+            }
+        }
+        light.map(|channel| channel.clamp(0.0, 1.0))
+    }
+}
@@ -0,0 +1,381 @@
+use bvb_level_kit::LevelRng;
+
+use crate::sprites::SpriteInstance;
+
+/// How an emitter spawns particles.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Emission {
+    /// Spawns `count` particles on the first update, then finishes once they die.
+    Burst { count: u32 },
+    /// Spawns `rate` particles per second for `duration` seconds (`None` runs until stopped).
+    Continuous { rate: f32, duration: Option<f32> },
+}
+
+/// Piecewise-linear curve over a particle's normalised age (0.0 at birth, 1.0 at death).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Curve<T> {
+    pub keys: Vec<(f32, T)>,
+}
+
+impl<T: Copy + Lerp> Curve<T> {
+    pub fn constant(value: T) -> Self {
+        Self {
+            keys: vec![(0.0, value)],
+        }
+    }
+
+    pub fn linear(from: T, to: T) -> Self {
+        Self {
+            keys: vec![(0.0, from), (1.0, to)],
+        }
+    }
+
+    /// Keys must be sorted by age; ages outside the keys clamp to the first or last value.
+    pub fn sample(&self, t: f32) -> T {
+        let Some(&(first_t, first)) = self.keys.first() else {
+            return T::zero();
+        };
+        if t <= first_t {
+            return first;
+        }
+        for pair in self.keys.windows(2) {
+            let (t0, v0) = pair[0];
+            let (t1, v1) = pair[1];
+            if t <= t1 {
+                let span = (t1 - t0).max(f32::EPSILON);
+                return v0.lerp(v1, (t - t0) / span);
+            }
+        }
+        self.keys[self.keys.len() - 1].1
+    }
+}
+
+pub trait Lerp {
+    fn lerp(self, to: Self, t: f32) -> Self;
+    fn zero() -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        self + (to - self) * t
+    }
+
+    fn zero() -> Self {
+        0.0
+    }
+}
+
+impl Lerp for [f32; 4] {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        [
+            self[0].lerp(to[0], t),
+            self[1].lerp(to[1], t),
+            self[2].lerp(to[2], t),
+            self[3].lerp(to[3], t),
+        ]
+    }
+
+    fn zero() -> Self {
+        [0.0; 4]
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct EmitterConfig {
+    pub emission: Emission,
+    /// Lifetime range in seconds.
+    pub lifetime: (f32, f32),
+    /// Initial speed range in level pixels per second.
+    pub speed: (f32, f32),
+    /// Emission direction in degrees (0 = right, 90 = down, since level y grows downwards).
+    pub direction: f32,
+    /// Total cone width in degrees around `direction`; 360 emits in all directions.
+    pub spread: f32,
+    /// Particles spawn uniformly inside this radius around the emitter.
+    pub spawn_radius: f32,
+    /// Acceleration in level pixels per second squared.
+    pub gravity: [f32; 2],
+    pub color: Curve<[f32; 4]>,
+    pub size: Curve<f32>,
+    /// Atlas frame drawn for each particle.
+    pub frame: u32,
+    pub max_particles: usize,
+}
+
+impl EmitterConfig {
+    /// Short radial dust burst for push impacts.
+    pub fn push_impact() -> Self {
+        Self {
+            emission: Emission::Burst { count: 18 },
+            lifetime: (0.25, 0.5),
+            speed: (40.0, 110.0),
+            direction: 0.0,
+            spread: 360.0,
+            spawn_radius: 2.0,
+            gravity: [0.0, 60.0],
+            color: Curve::linear([0.93, 0.86, 0.72, 1.0], [0.55, 0.5, 0.45, 0.0]),
+            size: Curve::linear(3.0, 1.0),
+            frame: 0,
+            max_particles: 32,
+        }
+    }
+
+    /// Slow upward swirl for burrow mouths and alien sigils.
+    pub fn portal(color: [f32; 4]) -> Self {
+        let mut faded = color;
+        faded[3] = 0.0;
+        Self {
+            emission: Emission::Continuous {
+                rate: 24.0,
+                duration: None,
+            },
+            lifetime: (0.8, 1.4),
+            speed: (8.0, 20.0),
+            direction: 270.0,
+            spread: 70.0,
+            spawn_radius: 10.0,
+            gravity: [0.0, -6.0],
+            color: Curve {
+                keys: vec![(0.0, faded), (0.2, color), (1.0, faded)],
+            },
+            size: Curve::linear(2.0, 1.0),
+            frame: 0,
+            max_particles: 64,
+        }
+    }
+
+    /// One-shot ring of sparks when a human wave spawns.
+    pub fn wave_spawn() -> Self {
+        Self {
+            emission: Emission::Burst { count: 40 },
+            lifetime: (0.5, 0.9),
+            speed: (30.0, 60.0),
+            direction: 0.0,
+            spread: 360.0,
+            spawn_radius: 6.0,
+            gravity: [0.0, 0.0],
+            color: Curve::linear([1.0, 0.85, 0.35, 1.0], [0.9, 0.3, 0.1, 0.0]),
+            size: Curve::linear(2.0, 2.0),
+            frame: 0,
+            max_particles: 64,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if let Emission::Continuous { rate, .. } = self.emission {
+            if !rate.is_finite() {
+                return Err(format!("emitter rate {rate} is not finite"));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Particle {
+    position: [f32; 2],
+    velocity: [f32; 2],
+    age: f32,
+    lifetime: f32,
+}
+
+/// A single particle source. Identical seeds and update steps produce identical particles.
+#[derive(Clone, Debug)]
+pub struct ParticleEmitter {
+    config: EmitterConfig,
+    position: [f32; 2],
+    rng: LevelRng,
+    particles: Vec<Particle>,
+    elapsed: f32,
+    spawn_debt: f32,
+    burst_done: bool,
+    stopped: bool,
+}
+
+impl ParticleEmitter {
+    pub fn new(config: EmitterConfig, position: [f32; 2], seed: u64) -> Result<Self, String> {
+        config.validate()?;
+        Ok(Self {
+            particles: Vec::with_capacity(config.max_particles.min(1024)),
+            config,
+            position,
+            rng: LevelRng::from_u64(seed),
+            elapsed: 0.0,
+            spawn_debt: 0.0,
+            burst_done: false,
+            stopped: false,
+        })
+    }
+
+    pub fn set_position(&mut self, position: [f32; 2]) {
+        self.position = position;
+    }
+
+    /// Stops spawning; existing particles live out their lifetime.
+    pub fn stop(&mut self) {
+        self.stopped = true;
+    }
+
+    pub fn particle_count(&self) -> usize {
+        self.particles.len()
+    }
+
+    /// True once the emitter will never spawn again and all its particles are dead.
+    pub fn is_finished(&self) -> bool {
+        let spawning_over = self.stopped
+            || match self.config.emission {
+                Emission::Burst { .. } => self.burst_done,
+                Emission::Continuous { duration, .. } => {
+                    duration.is_some_and(|duration| self.elapsed >= duration)
+                }
+            };
+        spawning_over && self.particles.is_empty()
+    }
+
+    /// Advances the simulation by `dt` seconds. Use a fixed step for replay-identical output.
+    pub fn update(&mut self, dt: f32) {
+        let dt = dt.max(0.0);
+        let gravity = self.config.gravity;
+        self.particles.retain_mut(|p| {
+            p.age += dt;
+            if p.age >= p.lifetime {
+                return false;
+            }
+            p.velocity[0] += gravity[0] * dt;
+            p.velocity[1] += gravity[1] * dt;
+            p.position[0] += p.velocity[0] * dt;
+            p.position[1] += p.velocity[1] * dt;
+            true
+        });
+
+        if !self.stopped {
+            match self.config.emission {
+                Emission::Burst { count } => {
+                    if !self.burst_done {
+                        self.burst_done = true;
+                        for _ in 0..count {
+                            self.spawn();
+                        }
+                    }
+                }
+                Emission::Continuous { rate, duration } => {
+                    let active =
+                        duration.map_or(dt, |duration| (duration - self.elapsed).clamp(0.0, dt));
+                    self.spawn_debt += rate.max(0.0) * active;
+                    // Particles past the cap would be dropped anyway, so their debt goes too.
+                    let due = self.spawn_debt.floor();
+                    self.spawn_debt -= due;
+                    let room = self
+                        .config
+                        .max_particles
+                        .saturating_sub(self.particles.len());
+                    for _ in 0..(due as usize).min(room) {
+                        self.spawn();
+                    }
+                }
+            }
+        }
+        self.elapsed += dt;
+    }
+
+    fn spawn(&mut self) {
+        if self.particles.len() >= self.config.max_particles {
+            return;
+        }
+        let config = &self.config;
+        let rng = &mut self.rng;
+
+        let offset_angle = rng.range_f32(0.0, std::f32::consts::TAU);
+        let offset = config.spawn_radius * rng.next_f32().sqrt();
+        let angle = (config.direction + rng.range_f32(-0.5, 0.5) * config.spread).to_radians();
+        let speed = rng.range_f32(config.speed.0, config.speed.1);
+        let lifetime = rng
+            .range_f32(config.lifetime.0, config.lifetime.1)
+            .max(1e-3);
+
+        self.particles.push(Particle {
+            position: [
+                self.position[0] + offset_angle.cos() * offset,
+                self.position[1] + offset_angle.sin() * offset,
+            ],
+            velocity: [angle.cos() * speed, angle.sin() * speed],
+            age: 0.0,
+            lifetime,
+        });
+    }
+
+    /// Appends one sprite per live particle.
+    pub fn write_sprites(&self, out: &mut Vec<SpriteInstance>) {
+        out.extend(self.particles.iter().map(|p| {
+            let t = p.age / p.lifetime;
+            let size = self.config.size.sample(t).max(0.0);
+            SpriteInstance {
+                center: p.position,
+                size: [size, size],
+                frame: self.config.frame,
+                color: self.config.color.sample(t),
+            }
+        }));
+    }
+}
+
+/// Owns a set of emitters and drops them once they finish.
+#[derive(Clone, Debug, Default)]
+pub struct ParticleSystem {
+    emitters: Vec<(u32, ParticleEmitter)>,
+    next_id: u32,
+}
+
+impl ParticleSystem {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an emitter and returns its id, used to move or stop it later.
+    pub fn spawn(
+        &mut self,
+        config: EmitterConfig,
+        position: [f32; 2],
+        seed: u64,
+    ) -> Result<u32, String> {
+        let emitter = ParticleEmitter::new(config, position, seed)?;
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+        self.emitters.push((id, emitter));
+        Ok(id)
+    }
+
+    pub fn emitter_mut(&mut self, id: u32) -> Option<&mut ParticleEmitter> {
+        self.emitters
+            .iter_mut()
+            .find(|(emitter_id, _)| *emitter_id == id)
+            .map(|(_, emitter)| emitter)
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        for (_, emitter) in &mut self.emitters {
+            emitter.update(dt);
+        }
+        self.emitters.retain(|(_, emitter)| !emitter.is_finished());
+    }
+
+    pub fn emitter_count(&self) -> usize {
+        self.emitters.len()
+    }
+
+    pub fn particle_count(&self) -> usize {
+        self.emitters
+            .iter()
+            .map(|(_, emitter)| emitter.particle_count())
+            .sum()
+    }
+
+    /// Sprites for every live particle, in emitter spawn order.
+    pub fn sprites(&self) -> Vec<SpriteInstance> {
+        let mut out = Vec::with_capacity(self.particle_count());
+        for (_, emitter) in &self.emitters {
+            emitter.write_sprites(&mut out);
+        }
+        out
+    }
+}
@@ -0,0 +1,498 @@
+#[cfg(target_arch = "wasm32")]
+use std::collections::HashMap;
+
+use crate::level::MAX_LEVEL_DIMENSION;
+use crate::lighting::{Lighting, Occluder, PointLight};
+use crate::sprites::SpriteInstance;
+
+const MAGIC: &[u8; 4] = b"B2DR";
+const FORMAT_VERSION: u8 = 1;
+
+const TAG_CLEAR: u8 = 0;
+const TAG_FULLSCREEN_IMAGE: u8 = 1;
+const TAG_SPRITES: u8 = 2;
+const TAG_LIGHTING: u8 = 3;
+
+// Decoded image bytes a log may hold in total. Pixel runs make images nearly free to store, so
+// without this a tiny file could ask for gigabytes.
+const MAX_DECODED_IMAGE_BYTES: usize = 128 << 20;
+// Commands, sprites, lights and occluders a log may hold across all frames. A repeated frame costs
+// one byte in the file but clones the previous frame's commands.
+const MAX_DECODED_ITEMS: usize = 1 << 22;
+
+/// One draw command as issued by `Renderer::render`, in submission order.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DrawCommand {
+    /// Clears the target to a linear RGBA colour.
+    Clear([f32; 4]),
+    /// Samples an image from the log (nearest filtering) stretched over the whole target.
+    FullscreenImage { image: u32 },
+    /// Alpha-blended sprite quads; `world_size` maps level pixels onto the whole target.
+    Sprites {
+        atlas: u32,
+        frame_size: [u32; 2],
+        world_size: [f32; 2],
+        sprites: Vec<SpriteInstance>,
+    },
+    /// Light layer multiplied over everything drawn before it, in the same world space as `Sprites`.
+    Lighting {
+        world_size: [f32; 2],
+        lighting: Lighting,
+    },
+}
+
+/// An image uploaded while recording, referenced by index from `DrawCommand`s.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RecordedImage {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecordedFrame {
+    /// Milliseconds since recording started.
+    pub time_ms: u32,
+    pub width: u32,
+    pub height: u32,
+    pub commands: Vec<DrawCommand>,
+}
+
+/// Draw-command log of a recording session, produced by `Renderer::stop_recording()`.
+///
+/// Images are stored once and referenced by id, so a match recording holds the level image a single
+/// time. `encode`/`decode` give a compact binary form for sharing replays.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FrameLog {
+    pub images: Vec<RecordedImage>,
+    pub frames: Vec<RecordedFrame>,
+}
+
+impl FrameLog {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(MAGIC);
+        out.push(FORMAT_VERSION);
+
+        write_varint(&mut out, self.images.len() as u64);
+        for image in &self.images {
+            write_varint(&mut out, u64::from(image.width));
+            write_varint(&mut out, u64::from(image.height));
+            write_pixel_runs(&mut out, &image.rgba);
+        }
+
+        write_varint(&mut out, self.frames.len() as u64);
+        let mut last_time = 0;
+        let mut last_commands: Option<&[DrawCommand]> = None;
+        for frame in &self.frames {
+            write_varint(&mut out, u64::from(frame.time_ms.saturating_sub(last_time)));
+            write_varint(&mut out, u64::from(frame.width));
+            write_varint(&mut out, u64::from(frame.height));
+            last_time = frame.time_ms;
+
+            // A zero count repeats the previous frame's commands, which covers idle stretches.
+            if last_commands == Some(&frame.commands[..]) {
+                write_varint(&mut out, 0);
+                continue;
+            }
+            write_varint(&mut out, frame.commands.len() as u64 + 1);
+            for command in &frame.commands {
+                write_command(&mut out, command);
+            }
+            last_commands = Some(&frame.commands);
+        }
+
+        out
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, String> {
+        let mut reader = Reader { bytes, at: 0 };
+        if reader.take(4)? != MAGIC {
+            return Err("not a frame log (bad magic)".to_string());
+        }
+        let version = reader.byte()?;
+        if version != FORMAT_VERSION {
+            return Err(format!("unsupported frame log version {version}"));
+        }
+
+        let image_count = reader.varint_usize()?;
+        let mut images = Vec::with_capacity(image_count.min(64));
+        let mut decoded_bytes = 0usize;
+        for index in 0..image_count {
+            let width = reader.varint_u32()?;
+            let height = reader.varint_u32()?;
+            check_dimensions(&format!("image {index}"), width, height)?;
+            let pixel_count = width as usize * height as usize;
+            decoded_bytes += pixel_count * 4;
+            if decoded_bytes > MAX_DECODED_IMAGE_BYTES {
+                return Err(format!(
+                    "frame log images exceed {MAX_DECODED_IMAGE_BYTES} decoded bytes"
+                ));
+            }
+            let rgba = reader.pixel_runs(pixel_count)?;
+            images.push(RecordedImage {
+                width,
+                height,
+                rgba,
+            });
+        }
+
+        let frame_count = reader.varint_usize()?;
+        let mut frames: Vec<RecordedFrame> = Vec::with_capacity(frame_count.min(4096));
+        let mut time_ms = 0u32;
+        let mut decoded_items = 0usize;
+        for index in 0..frame_count {
+            time_ms = time_ms.saturating_add(reader.varint_u32()?);
+            let width = reader.varint_u32()?;
+            let height = reader.varint_u32()?;
+            check_dimensions(&format!("frame {index}"), width, height)?;
+            let count = reader.varint_usize()?;
+            let commands = if count == 0 {
+                frames
+                    .last()
+                    .map(|frame| frame.commands.clone())
+                    .ok_or_else(|| "first frame cannot repeat a previous frame".to_string())?
+            } else {
+                let mut commands = Vec::with_capacity((count - 1).min(1024));
+                for _ in 1..count {
+                    commands.push(reader.command(images.len())?);
+                }
+                commands
+            };
+            decoded_items += commands.iter().map(item_count).sum::<usize>();
+            if decoded_items > MAX_DECODED_ITEMS {
+                return Err(format!(
+                    "frame log frames exceed {MAX_DECODED_ITEMS} decoded commands and instances"
+                ));
+            }
+            frames.push(RecordedFrame {
+                time_ms,
+                width,
+                height,
+                commands,
+            });
+        }
+
+        if reader.at != bytes.len() {
+            return Err("trailing bytes after frame log".to_string());
+        }
+
+        Ok(Self { images, frames })
+    }
+}
+
+// Recorded images and frames are at most level-sized, so anything larger is a corrupt or hostile log.
+pub(crate) fn check_dimensions(what: &str, width: u32, height: u32) -> Result<(), String> {
+    if width > MAX_LEVEL_DIMENSION || height > MAX_LEVEL_DIMENSION {
+        return Err(format!(
+            "{what} is {width}x{height}, at most {MAX_LEVEL_DIMENSION}x{MAX_LEVEL_DIMENSION} is allowed"
+        ));
+    }
+    Ok(())
+}
+
+fn item_count(command: &DrawCommand) -> usize {
+    1 + match command {
+        DrawCommand::Sprites { sprites, .. } => sprites.len(),
+        DrawCommand::Lighting { lighting, .. } => lighting.lights.len() + lighting.occluders.len(),
+        DrawCommand::Clear(_) | DrawCommand::FullscreenImage { .. } => 0,
+    }
+}
+
+// Builds a `FrameLog` while the renderer is recording.
+#[cfg(target_arch = "wasm32")]
+#[derive(Default)]
+pub(crate) struct FrameRecorder {
+    log: FrameLog,
+    image_ids: HashMap<u64, u32>,
+    start_ms: Option<f64>,
+}
+
+#[cfg(target_arch = "wasm32")]
+impl FrameRecorder {
+    pub(crate) fn add_image(&mut self, rgba: &[u8], width: u32, height: u32) -> u32 {
+        let key = fnv1a64(rgba) ^ (u64::from(width) << 32 | u64::from(height));
+        if let Some(&id) = self.image_ids.get(&key) {
+            if self.log.images[id as usize].rgba == rgba {
+                return id;
+            }
+        }
+        let id = self.log.images.len() as u32;
+        self.log.images.push(RecordedImage {
+            width,
+            height,
+            rgba: rgba.to_vec(),
+        });
+        self.image_ids.insert(key, id);
+        id
+    }
+
+    pub(crate) fn push_frame(
+        &mut self,
+        now_ms: f64,
+        width: u32,
+        height: u32,
+        commands: Vec<DrawCommand>,
+    ) {
+        let start = *self.start_ms.get_or_insert(now_ms);
+        self.log.frames.push(RecordedFrame {
+            time_ms: (now_ms - start).max(0.0).round() as u32,
+            width,
+            height,
+            commands,
+        });
+    }
+
+    pub(crate) fn finish(self) -> FrameLog {
+        self.log
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn fnv1a64(bytes: &[u8]) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325u64;
+    for &byte in bytes {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+// Level art is large flat areas, so runs of identical pixels keep images small without a codec.
+fn write_pixel_runs(out: &mut Vec<u8>, rgba: &[u8]) {
+    let mut pixels = rgba.chunks_exact(4).peekable();
+    while let Some(pixel) = pixels.next() {
+        let mut run = 1u64;
+        while pixels.peek() == Some(&pixel) {
+            pixels.next();
+            run += 1;
+        }
+        write_varint(out, run);
+        out.extend_from_slice(pixel);
+    }
+}
+
+fn write_f32s(out: &mut Vec<u8>, values: &[f32]) {
+    for value in values {
+        out.extend_from_slice(&value.to_le_bytes());
+    }
+}
+
+fn write_command(out: &mut Vec<u8>, command: &DrawCommand) {
+    match command {
+        DrawCommand::Clear(color) => {
+            out.push(TAG_CLEAR);
+            write_f32s(out, color);
+        }
+        DrawCommand::FullscreenImage { image } => {
+            out.push(TAG_FULLSCREEN_IMAGE);
+            write_varint(out, u64::from(*image));
+        }
+        DrawCommand::Sprites {
+            atlas,
+            frame_size,
+            world_size,
+            sprites,
+        } => {
+            out.push(TAG_SPRITES);
+            write_varint(out, u64::from(*atlas));
+            write_varint(out, u64::from(frame_size[0]));
+            write_varint(out, u64::from(frame_size[1]));
+            write_f32s(out, world_size);
+            write_varint(out, sprites.len() as u64);
+            for sprite in sprites {
+                write_f32s(out, &sprite.center);
+                write_f32s(out, &sprite.size);
+                write_varint(out, u64::from(sprite.frame));
+                write_f32s(out, &sprite.color);
+            }
+        }
+        DrawCommand::Lighting {
+            world_size,
+            lighting,
+        } => {
+            out.push(TAG_LIGHTING);
+            write_f32s(out, world_size);
+            write_f32s(out, &lighting.ambient);
+            write_varint(out, lighting.lights.len() as u64);
+            for light in &lighting.lights {
+                write_f32s(out, &light.position);
+                write_f32s(out, &[light.radius]);
+                write_f32s(out, &light.color);
+                write_f32s(out, &[light.intensity]);
+            }
+            write_varint(out, lighting.occluders.len() as u64);
+            for occluder in &lighting.occluders {
+                write_f32s(out, &occluder.center);
+                write_f32s(out, &occluder.size);
+            }
+        }
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    at: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .at
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| "frame log is truncated".to_string())?;
+        let slice = &self.bytes[self.at..end];
+        self.at = end;
+        Ok(slice)
+    }
+
+    fn byte(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn varint(&mut self) -> Result<u64, String> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err("varint is too long".to_string())
+    }
+
+    fn varint_u32(&mut self) -> Result<u32, String> {
+        u32::try_from(self.varint()?).map_err(|_| "value does not fit in u32".to_string())
+    }
+
+    fn varint_usize(&mut self) -> Result<usize, String> {
+        usize::try_from(self.varint()?).map_err(|_| "value does not fit in usize".to_string())
+    }
+
+    fn f32(&mut self) -> Result<f32, String> {
+        let mut raw = [0u8; 4];
+        raw.copy_from_slice(self.take(4)?);
+        Ok(f32::from_le_bytes(raw))
+    }
+
+    // World sizes scale every later coordinate and size the replayed light map, so they get the
+    // same bound as images.
+    fn world_size(&mut self) -> Result<[f32; 2], String> {
+        let size = [self.f32()?, self.f32()?];
+        let max = MAX_LEVEL_DIMENSION as f32;
+        if size.iter().any(|&edge| !(0.0..=max).contains(&edge)) {
+            return Err(format!(
+                "world size {}x{} is outside 0..={MAX_LEVEL_DIMENSION}",
+                size[0], size[1]
+            ));
+        }
+        Ok(size)
+    }
+
+    fn pixel_runs(&mut self, pixel_count: usize) -> Result<Vec<u8>, String> {
+        let mut rgba = Vec::with_capacity(pixel_count.min(1 << 22) * 4);
+        let mut filled = 0usize;
+        while filled < pixel_count {
+            let run = self.varint_usize()?;
+            if run == 0 || run > pixel_count - filled {
+                return Err("pixel run overflows image".to_string());
+            }
+            let pixel = self.take(4)?;
+            for _ in 0..run {
+                rgba.extend_from_slice(pixel);
+            }
+            filled += run;
+        }
+        Ok(rgba)
+    }
+
+    fn image_id(&mut self, image_count: usize) -> Result<u32, String> {
+        let image = self.varint_u32()?;
+        if image as usize >= image_count {
+            return Err(format!("draw command references missing image {image}"));
+        }
+        Ok(image)
+    }
+
+    fn command(&mut self, image_count: usize) -> Result<DrawCommand, String> {
+        match self.byte()? {
+            TAG_CLEAR => Ok(DrawCommand::Clear([
+                self.f32()?,
+                self.f32()?,
+                self.f32()?,
+                self.f32()?,
+            ])),
+            TAG_FULLSCREEN_IMAGE => Ok(DrawCommand::FullscreenImage {
+                image: self.image_id(image_count)?,
+            }),
+            TAG_SPRITES => {
+                let atlas = self.image_id(image_count)?;
+                let frame_size = [self.varint_u32()?, self.varint_u32()?];
+                let world_size = self.world_size()?;
+                let count = self.varint_usize()?;
+                let mut sprites = Vec::with_capacity(count.min(4096));
+                for _ in 0..count {
+                    sprites.push(SpriteInstance {
+                        center: [self.f32()?, self.f32()?],
+                        size: [self.f32()?, self.f32()?],
+                        frame: self.varint_u32()?,
+                        color: [self.f32()?, self.f32()?, self.f32()?, self.f32()?],
+                    });
+                }
+                Ok(DrawCommand::Sprites {
+                    atlas,
+                    frame_size,
+                    world_size,
+                    sprites,
+                })
+            }
+            TAG_LIGHTING => {
+                let world_size = self.world_size()?;
+                let ambient = [self.f32()?, self.f32()?, self.f32()?];
+                let light_count = self.varint_usize()?;
+                let mut lights = Vec::with_capacity(light_count.min(256));
+                for _ in 0..light_count {
+                    lights.push(PointLight {
+                        position: [self.f32()?, self.f32()?],
+                        radius: self.f32()?,
+                        color: [self.f32()?, self.f32()?, self.f32()?],
+                        intensity: self.f32()?,
+                    });
+                }
+                let occluder_count = self.varint_usize()?;
+                let mut occluders = Vec::with_capacity(occluder_count.min(256));
+                for _ in 0..occluder_count {
+                    occluders.push(Occluder {
+                        center: [self.f32()?, self.f32()?],
+                        size: [self.f32()?, self.f32()?],
+                    });
+                }
+                let lighting = Lighting {
+                    ambient,
+                    lights,
+                    occluders,
+                };
+                lighting.validate()?;
+                Ok(DrawCommand::Lighting {
+                    world_size,
+                    lighting,
+                })
+            }
+            tag => Err(format!("unknown draw command tag {tag}")),
+        }
+    }
+}
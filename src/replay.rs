@@ -0,0 +1,253 @@
+use crate::capture::FrameCapture;
+use crate::level::MAX_LEVEL_DIMENSION;
+use crate::lighting::Lighting;
+use crate::recorder::{check_dimensions, DrawCommand, FrameLog, RecordedImage};
+use crate::sprites::{AtlasLayout, SpriteInstance};
+
+// Used for the last frame of a replay, which has no successor to measure against.
+const FALLBACK_FRAME_MS: u32 = 16;
+
+impl FrameLog {
+    /// Rasterises one recorded frame on the CPU, without a GPU or browser.
+    ///
+    /// Output matches the WebGL2 path: nearest sampling and an sRGB target.
+    pub fn render_frame(
+        &self,
+        index: usize,
+        width: u32,
+        height: u32,
+    ) -> Result<FrameCapture, String> {
+        let frame = self
+            .frames
+            .get(index)
+            .ok_or_else(|| format!("frame {index} is out of range"))?;
+        if width == 0 || height == 0 {
+            return Err("replay target has invalid dimensions".to_string());
+        }
+        check_dimensions("replay target", width, height)?;
+
+        let mut rgba = vec![0u8; width as usize * height as usize * 4];
+        for command in &frame.commands {
+            match command {
+                DrawCommand::Clear(color) => {
+                    let px = [
+                        linear_to_srgb(color[0]),
+                        linear_to_srgb(color[1]),
+                        linear_to_srgb(color[2]),
+                        (color[3].clamp(0.0, 1.0) * 255.0).round() as u8,
+                    ];
+                    for dst in rgba.chunks_exact_mut(4) {
+                        dst.copy_from_slice(&px);
+                    }
+                }
+                DrawCommand::FullscreenImage { image } => {
+                    let image = self
+                        .images
+                        .get(*image as usize)
+                        .ok_or_else(|| format!("frame {index} references missing image {image}"))?;
+                    stretch_nearest(&mut rgba, width, height, image);
+                }
+                DrawCommand::Sprites {
+                    atlas,
+                    frame_size,
+                    world_size,
+                    sprites,
+                } => {
+                    let atlas = self
+                        .images
+                        .get(*atlas as usize)
+                        .ok_or_else(|| format!("frame {index} references missing atlas {atlas}"))?;
+                    let layout = AtlasLayout {
+                        width: atlas.width,
+                        height: atlas.height,
+                        frame_width: frame_size[0],
+                        frame_height: frame_size[1],
+                    };
+                    let scale = [
+                        width as f32 / world_size[0].max(1.0),
+                        height as f32 / world_size[1].max(1.0),
+                    ];
+                    for sprite in sprites {
+                        draw_sprite(&mut rgba, width, height, atlas, &layout, scale, sprite);
+                    }
+                }
+                DrawCommand::Lighting {
+                    world_size,
+                    lighting,
+                } => apply_lighting(&mut rgba, width, height, *world_size, lighting),
+            }
+        }
+
+        Ok(FrameCapture {
+            width,
+            height,
+            rgba,
+        })
+    }
+
+    /// Replays every frame at its recorded surface size, as an image sequence.
+    pub fn render_frames(&self) -> Result<Vec<FrameCapture>, String> {
+        (0..self.frames.len())
+            .map(|index| {
+                let frame = &self.frames[index];
+                self.render_frame(index, frame.width, frame.height)
+            })
+            .collect()
+    }
+
+    /// Replays the log into a looping animated PNG at the first frame's size.
+    ///
+    /// Consecutive identical frames are merged into one longer frame.
+    pub fn to_apng(&self) -> Result<Vec<u8>, String> {
+        let first = self
+            .frames
+            .first()
+            .ok_or_else(|| "frame log has no frames".to_string())?;
+        let (width, height) = (first.width, first.height);
+
+        let mut frames: Vec<Vec<u8>> = Vec::new();
+        let mut delays: Vec<u32> = Vec::new();
+        for index in 0..self.frames.len() {
+            let delay = match self.frames.get(index + 1) {
+                Some(next) => next.time_ms.saturating_sub(self.frames[index].time_ms),
+                None => FALLBACK_FRAME_MS,
+            };
+            let capture = self.render_frame(index, width, height)?;
+            match (frames.last(), delays.last_mut()) {
+                (Some(previous), Some(last_delay)) if *previous == capture.rgba => {
+                    *last_delay += delay;
+                }
+                _ => {
+                    frames.push(capture.rgba);
+                    delays.push(delay);
+                }
+            }
+        }
+
+        let frame_refs: Vec<&[u8]> = frames.iter().map(Vec::as_slice).collect();
+        bvb_raster::encode_apng(width, height, &frame_refs, &delays).map_err(|err| err.to_string())
+    }
+}
+
+fn stretch_nearest(dst: &mut [u8], width: u32, height: u32, image: &RecordedImage) {
+    let (tw, th) = (image.width as usize, image.height as usize);
+    if tw == 0 || th == 0 {
+        return;
+    }
+    let (width, height) = (width as usize, height as usize);
+    for y in 0..height {
+        // Sample at the pixel centre, like the fragment shader does.
+        let sy = (((2 * y + 1) * th) / (2 * height)).min(th - 1);
+        for x in 0..width {
+            let sx = (((2 * x + 1) * tw) / (2 * width)).min(tw - 1);
+            let si = (sy * tw + sx) * 4;
+            let di = (y * width + x) * 4;
+            dst[di..di + 4].copy_from_slice(&image.rgba[si..si + 4]);
+        }
+    }
+}
+
+// Covers every target pixel whose centre lies inside the quad, like GPU rasterisation, and blends in
+// linear space the way an sRGB render target does.
+fn draw_sprite(
+    dst: &mut [u8],
+    width: u32,
+    height: u32,
+    atlas: &RecordedImage,
+    layout: &AtlasLayout,
+    scale: [f32; 2],
+    sprite: &SpriteInstance,
+) {
+    let x0 = (sprite.center[0] - sprite.size[0] * 0.5) * scale[0];
+    let y0 = (sprite.center[1] - sprite.size[1] * 0.5) * scale[1];
+    let x1 = (sprite.center[0] + sprite.size[0] * 0.5) * scale[0];
+    let y1 = (sprite.center[1] + sprite.size[1] * 0.5) * scale[1];
+    if x1 <= x0 || y1 <= y0 || atlas.width == 0 || atlas.height == 0 {
+        return;
+    }
+
+    let (fx, fy, fw, fh) = layout.frame_rect(sprite.frame);
+    let px_start = (x0 - 0.5).ceil().max(0.0) as u32;
+    let py_start = (y0 - 0.5).ceil().max(0.0) as u32;
+    let px_end = ((x1 - 0.5).ceil().max(0.0) as u32).min(width);
+    let py_end = ((y1 - 0.5).ceil().max(0.0) as u32).min(height);
+
+    for py in py_start..py_end {
+        let v = (py as f32 + 0.5 - y0) / (y1 - y0);
+        let ty = (fy + ((v * fh as f32) as u32).min(fh.saturating_sub(1))).min(atlas.height - 1);
+        for px in px_start..px_end {
+            let u = (px as f32 + 0.5 - x0) / (x1 - x0);
+            let tx = (fx + ((u * fw as f32) as u32).min(fw.saturating_sub(1))).min(atlas.width - 1);
+            let si = ((ty * atlas.width + tx) * 4) as usize;
+            let texel = &atlas.rgba[si..si + 4];
+
+            let alpha = (f32::from(texel[3]) / 255.0 * sprite.color[3]).clamp(0.0, 1.0);
+            if alpha <= 0.0 {
+                continue;
+            }
+            let di = ((py * width + px) * 4) as usize;
+            for channel in 0..3 {
+                let src = srgb_to_linear(texel[channel]) * sprite.color[channel];
+                let dst_linear = srgb_to_linear(dst[di + channel]);
+                dst[di + channel] = linear_to_srgb(src * alpha + dst_linear * (1.0 - alpha));
+            }
+            let dst_alpha = f32::from(dst[di + 3]) / 255.0;
+            dst[di + 3] = ((alpha + dst_alpha * (1.0 - alpha)) * 255.0).round() as u8;
+        }
+    }
+}
+
+// Builds the light map at level resolution like the GPU light pass, then multiplies it in with
+// nearest sampling.
+fn apply_lighting(
+    dst: &mut [u8],
+    width: u32,
+    height: u32,
+    world_size: [f32; 2],
+    lighting: &Lighting,
+) {
+    // Decoded logs are bounded already; the clamp keeps hand-built ones from sizing a huge map.
+    let map_edge = |edge: f32| (edge.round() as usize).clamp(1, MAX_LEVEL_DIMENSION as usize);
+    let map_width = map_edge(world_size[0]);
+    let map_height = map_edge(world_size[1]);
+    let mut light_map = Vec::with_capacity(map_width * map_height);
+    for y in 0..map_height {
+        for x in 0..map_width {
+            let light = lighting.light_at([x as f32 + 0.5, y as f32 + 0.5]);
+            // The light target is 8-bit, so quantise the same way.
+            light_map.push(light.map(|channel| (channel * 255.0).round() / 255.0));
+        }
+    }
+
+    for y in 0..height {
+        let ly = ((y as f32 + 0.5) * map_height as f32 / height as f32) as usize;
+        for x in 0..width {
+            let lx = ((x as f32 + 0.5) * map_width as f32 / width as f32) as usize;
+            let light = light_map[ly.min(map_height - 1) * map_width + lx.min(map_width - 1)];
+            let di = ((y * width + x) * 4) as usize;
+            for channel in 0..3 {
+                dst[di + channel] =
+                    linear_to_srgb(srgb_to_linear(dst[di + channel]) * light[channel]);
+            }
+        }
+    }
+}
+
+fn srgb_to_linear(value: u8) -> f32 {
+    let v = f32::from(value) / 255.0;
+    if v <= 0.040_45 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(value: f32) -> u8 {
+    let v = value.clamp(0.0, 1.0);
+    let encoded = if v <= 0.003_130_8 {
+        v * 12.92
+    } else {
+        1.055 * v.powf(1.0 / 2.4) - 0.055
+    };
+    (encoded * 255.0).round() as u8
+}
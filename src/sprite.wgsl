@@ -0,0 +1,56 @@
+struct View {
+  world_size: vec2<f32>,
+  padding: vec2<f32>,
+}
+
+struct SpriteIn {
+  @location(0) center: vec2<f32>,
+  @location(1) size: vec2<f32>,
+  @location(2) uv_rect: vec4<f32>,
+  @location(3) color: vec4<f32>,
+}
+
+struct VertexOut {
+  @builtin(position) position: vec4<f32>,
+  @location(0) uv: vec2<f32>,
+  @location(1) color: vec4<f32>,
+}
+
+@group(0) @binding(0)
+var atlas_texture: texture_2d<f32>;
+
+@group(0) @binding(1)
+var atlas_sampler: sampler;
+
+@group(1) @binding(0)
+var<uniform> view: View;
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32, sprite: SpriteIn) -> VertexOut {
+  var corners = array<vec2<f32>, 6>(
+    vec2<f32>(0.0, 0.0),
+    vec2<f32>(1.0, 0.0),
+    vec2<f32>(0.0, 1.0),
+    vec2<f32>(0.0, 1.0),
+    vec2<f32>(1.0, 0.0),
+    vec2<f32>(1.0, 1.0),
+  );
+  let corner = corners[vertex_index];
+  let world = sprite.center + (corner - vec2<f32>(0.5, 0.5)) * sprite.size;
+
+  var output: VertexOut;
+  output.position = vec4<f32>(
+    world.x / view.world_size.x * 2.0 - 1.0,
+    1.0 - world.y / view.world_size.y * 2.0,
+    0.0,
+    1.0,
+  );
+  output.uv = mix(sprite.uv_rect.xy, sprite.uv_rect.zw, corner);
+  output.color = sprite.color;
+  return output;
+}
+
+@fragment
+fn fs_main(input: VertexOut) -> @location(0) vec4<f32> {
+  return textureSample(atlas_texture, atlas_sampler, input.uv) * input.color;
+}
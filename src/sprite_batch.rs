@@ -0,0 +1,230 @@
+use crate::sprites::{AtlasLayout, SpriteInstance};
+use crate::wasm_impl::create_texture_and_bind_group;
+
+// center (2) + size (2) + uv rect (4) + colour (4) floats.
+const INSTANCE_FLOATS: usize = 12;
+const INSTANCE_BYTES: u64 = (INSTANCE_FLOATS * 4) as u64;
+const WHITE: [u8; 4] = [255, 255, 255, 255];
+
+// Instanced textured quads drawn over the level image in a single draw call.
+pub(crate) struct SpriteBatch {
+    pipeline: wgpu::RenderPipeline,
+    view_buffer: wgpu::Buffer,
+    view_bind_group: wgpu::BindGroup,
+    atlas: wgpu::Texture,
+    atlas_bind_group: wgpu::BindGroup,
+    pub(crate) layout: AtlasLayout,
+    instance_buffer: wgpu::Buffer,
+    instance_capacity: u64,
+    instance_count: u32,
+}
+
+impl SpriteBatch {
+    pub(crate) fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        format: wgpu::TextureFormat,
+        texture_layout: &wgpu::BindGroupLayout,
+        sampler: &wgpu::Sampler,
+    ) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("blob2d-renderer-sprite-shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("sprite.wgsl").into()),
+        });
+
+        let view_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("blob2d-renderer-sprite-view-layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let view_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("blob2d-renderer-sprite-view"),
+            size: 16,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let view_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("blob2d-renderer-sprite-view-bind-group"),
+            layout: &view_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: view_buffer.as_entire_binding(),
+            }],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("blob2d-renderer-sprite-pipeline-layout"),
+            bind_group_layouts: &[texture_layout, &view_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("blob2d-renderer-sprite-pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: INSTANCE_BYTES,
+                    step_mode: wgpu::VertexStepMode::Instance,
+                    attributes: &wgpu::vertex_attr_array![
+                        0 => Float32x2,
+                        1 => Float32x2,
+                        2 => Float32x4,
+                        3 => Float32x4,
+                    ],
+                }],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        // A 1x1 white atlas turns every sprite into a flat quad of its tint colour.
+        let (atlas, atlas_bind_group) =
+            create_texture_and_bind_group(device, queue, texture_layout, sampler, &WHITE, 1, 1);
+        let instance_capacity = 64;
+        let instance_buffer = create_instance_buffer(device, instance_capacity);
+
+        Self {
+            pipeline,
+            view_buffer,
+            view_bind_group,
+            atlas,
+            atlas_bind_group,
+            layout: AtlasLayout {
+                width: 1,
+                height: 1,
+                frame_width: 1,
+                frame_height: 1,
+            },
+            instance_buffer,
+            instance_capacity,
+            instance_count: 0,
+        }
+    }
+
+    pub(crate) fn atlas_bytes(&self) -> u64 {
+        let size = self.atlas.size();
+        4 * u64::from(size.width) * u64::from(size.height)
+    }
+
+    pub(crate) fn upload_atlas(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        texture_layout: &wgpu::BindGroupLayout,
+        sampler: &wgpu::Sampler,
+        rgba: &[u8],
+        layout: AtlasLayout,
+    ) {
+        let (atlas, atlas_bind_group) = create_texture_and_bind_group(
+            device,
+            queue,
+            texture_layout,
+            sampler,
+            rgba,
+            layout.width,
+            layout.height,
+        );
+        self.atlas = atlas;
+        self.atlas_bind_group = atlas_bind_group;
+        self.layout = layout;
+    }
+
+    // Writes the instances and the world-to-clip scale; returns how many sprites will be drawn.
+    pub(crate) fn prepare(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        sprites: &[SpriteInstance],
+        world_size: [f32; 2],
+    ) -> u32 {
+        self.instance_count = sprites.len() as u32;
+        if sprites.is_empty() {
+            return 0;
+        }
+
+        let needed = sprites.len() as u64;
+        if needed > self.instance_capacity {
+            self.instance_capacity = needed.next_power_of_two();
+            self.instance_buffer = create_instance_buffer(device, self.instance_capacity);
+        }
+
+        let mut bytes = Vec::with_capacity(sprites.len() * INSTANCE_FLOATS * 4);
+        for sprite in sprites {
+            let uv = self.layout.frame_uv(sprite.frame);
+            let floats = [
+                sprite.center[0],
+                sprite.center[1],
+                sprite.size[0],
+                sprite.size[1],
+                uv[0],
+                uv[1],
+                uv[2],
+                uv[3],
+                sprite.color[0],
+                sprite.color[1],
+                sprite.color[2],
+                sprite.color[3],
+            ];
+            for value in floats {
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        queue.write_buffer(&self.instance_buffer, 0, &bytes);
+
+        let mut view = Vec::with_capacity(16);
+        for value in [world_size[0].max(1.0), world_size[1].max(1.0), 0.0, 0.0] {
+            view.extend_from_slice(&value.to_le_bytes());
+        }
+        queue.write_buffer(&self.view_buffer, 0, &view);
+
+        self.instance_count
+    }
+
+    pub(crate) fn draw<'pass>(&'pass self, render_pass: &mut wgpu::RenderPass<'pass>) -> u32 {
+        if self.instance_count == 0 {
+            return 0;
+        }
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.atlas_bind_group, &[]);
+        render_pass.set_bind_group(1, &self.view_bind_group, &[]);
+        render_pass.set_vertex_buffer(
+            0,
+            self.instance_buffer
+                .slice(..u64::from(self.instance_count) * INSTANCE_BYTES),
+        );
+        render_pass.draw(0..6, 0..self.instance_count);
+        1
+    }
+}
+
+fn create_instance_buffer(device: &wgpu::Device, capacity: u64) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("blob2d-renderer-sprite-instances"),
+        size: capacity * INSTANCE_BYTES,
+        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
@@ -0,0 +1,53 @@
+/// One textured quad, positioned in level pixel coordinates ((0, 0) is the top-left of the level image).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpriteInstance {
+    pub center: [f32; 2],
+    pub size: [f32; 2],
+    /// Frame index into the sprite atlas, row-major.
+    pub frame: u32,
+    /// Linear RGBA tint, multiplied with the atlas texel.
+    pub color: [f32; 4],
+}
+
+/// Grid layout of the sprite atlas texture.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AtlasLayout {
+    pub width: u32,
+    pub height: u32,
+    pub frame_width: u32,
+    pub frame_height: u32,
+}
+
+impl AtlasLayout {
+    pub fn columns(&self) -> u32 {
+        (self.width / self.frame_width.max(1)).max(1)
+    }
+
+    pub fn frame_count(&self) -> u32 {
+        self.columns() * (self.height / self.frame_height.max(1)).max(1)
+    }
+
+    /// Texel rectangle `(x, y, width, height)` of `frame`; out-of-range frames wrap.
+    pub fn frame_rect(&self, frame: u32) -> (u32, u32, u32, u32) {
+        let frame = frame % self.frame_count();
+        let columns = self.columns();
+        (
+            (frame % columns) * self.frame_width,
+            (frame / columns) * self.frame_height,
+            self.frame_width.min(self.width),
+            self.frame_height.min(self.height),
+        )
+    }
+
+    /// Normalised `(u0, v0, u1, v1)` of `frame`.
+    pub fn frame_uv(&self, frame: u32) -> [f32; 4] {
+        let (x, y, w, h) = self.frame_rect(frame);
+        let (tw, th) = (self.width as f32, self.height as f32);
+        [
+            x as f32 / tw,
+            y as f32 / th,
+            (x + w) as f32 / tw,
+            (y + h) as f32 / th,
+        ]
+    }
+}
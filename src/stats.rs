@@ -0,0 +1,131 @@
+use std::fmt;
+
+/// Snapshot of renderer counters, returned by `Renderer::stats()`.
+///
+/// Timings are in milliseconds. Counters such as `draw_calls` describe the most recent frame.
+#[derive(Clone, Debug, Default)]
+pub struct RendererStats {
+    pub frame_index: u64,
+    /// CPU time spent inside the last `render` call.
+    pub cpu_frame_ms: f64,
+    /// Exponential moving average of `cpu_frame_ms`.
+    pub cpu_frame_avg_ms: f64,
+    /// Time between the starts of the last two `render` calls.
+    pub frame_interval_ms: f64,
+    /// GPU time of the last resolved frame. `None` when timestamp queries are unavailable (WebGL2).
+    pub gpu_frame_ms: Option<f64>,
+    pub draw_calls: u32,
+    pub sprites_batched: u32,
+    /// Bytes held by textures the renderer allocated (surface images excluded).
+    pub texture_bytes: u64,
+    pub surface_reconfigures: u32,
+    pub surface_size: (u32, u32),
+    pub adapter: Option<wgpu::AdapterInfo>,
+    pub limits: Option<wgpu::Limits>,
+}
+
+impl RendererStats {
+    pub fn fps(&self) -> f64 {
+        if self.frame_interval_ms > 0.0 {
+            1000.0 / self.frame_interval_ms
+        } else {
+            0.0
+        }
+    }
+}
+
+impl fmt::Display for RendererStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.adapter {
+            Some(info) => {
+                writeln!(f, "backend: {}", backend_label(info.backend))?;
+                writeln!(f, "adapter: {} ({:?})", info.name, info.device_type)?;
+                if !info.driver.is_empty() || !info.driver_info.is_empty() {
+                    writeln!(f, "driver: {} {}", info.driver, info.driver_info)?;
+                }
+            }
+            None => writeln!(f, "backend: Unavailable")?,
+        }
+        writeln!(
+            f,
+            "surface: {}x{} (configured {}x)",
+            self.surface_size.0, self.surface_size.1, self.surface_reconfigures
+        )?;
+        writeln!(
+            f,
+            "frame: #{} cpu {:.2} ms (avg {:.2} ms), interval {:.1} ms ({:.0} fps)",
+            self.frame_index,
+            self.cpu_frame_ms,
+            self.cpu_frame_avg_ms,
+            self.frame_interval_ms,
+            self.fps()
+        )?;
+        match self.gpu_frame_ms {
+            Some(ms) => writeln!(f, "gpu: {ms:.2} ms")?,
+            None => writeln!(f, "gpu: n/a (no timestamp queries)")?,
+        }
+        writeln!(
+            f,
+            "draws: {}, sprites batched: {}",
+            self.draw_calls, self.sprites_batched
+        )?;
+        write!(f, "textures: {:.1} KiB", self.texture_bytes as f64 / 1024.0)?;
+        if let Some(limits) = &self.limits {
+            write!(
+                f,
+                "\nlimits: tex2d {}, bind groups {}, ubo {} B",
+                limits.max_texture_dimension_2d,
+                limits.max_bind_groups,
+                limits.max_uniform_buffer_binding_size
+            )?;
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn backend_label(backend: wgpu::Backend) -> &'static str {
+    match backend {
+        wgpu::Backend::Empty => "Empty",
+        wgpu::Backend::Vulkan => "Vulkan",
+        wgpu::Backend::Metal => "Metal",
+        wgpu::Backend::Dx12 => "DirectX12",
+        wgpu::Backend::Gl => "WebGL2",
+        wgpu::Backend::BrowserWebGpu => "BrowserWebGPU",
+    }
+}
+
+// Weight of the newest sample in the CPU frame-time moving average.
+#[cfg(target_arch = "wasm32")]
+const CPU_AVG_WEIGHT: f64 = 0.1;
+
+#[cfg(target_arch = "wasm32")]
+#[derive(Default)]
+pub(crate) struct FrameClock {
+    last_start_ms: Option<f64>,
+}
+
+#[cfg(target_arch = "wasm32")]
+impl FrameClock {
+    pub(crate) fn begin(&mut self, stats: &mut RendererStats, now_ms: f64) {
+        if let Some(last) = self.last_start_ms {
+            stats.frame_interval_ms = (now_ms - last).max(0.0);
+        }
+        self.last_start_ms = Some(now_ms);
+        stats.frame_index += 1;
+        stats.draw_calls = 0;
+        stats.sprites_batched = 0;
+    }
+
+    pub(crate) fn end(&mut self, stats: &mut RendererStats, now_ms: f64) {
+        let Some(start) = self.last_start_ms else {
+            return;
+        };
+        let cpu = (now_ms - start).max(0.0);
+        stats.cpu_frame_ms = cpu;
+        stats.cpu_frame_avg_ms = if stats.frame_index <= 1 {
+            cpu
+        } else {
+            stats.cpu_frame_avg_ms + (cpu - stats.cpu_frame_avg_ms) * CPU_AVG_WEIGHT
+        };
+    }
+}
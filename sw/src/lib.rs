@@ -6,15 +6,15 @@ use workbox_rs::strategies::{CacheFirst, HandlerFn};
 use workbox_rs::sw;
 use workbox_rs::types::{Request, Response};
 
-const CACHE_STATIC: &str = "bvb-static-v3";
-const OLD_CACHES: [&str; 2] = ["bvb-static-v1", "bvb-static-v2"];
+const CACHE_STATIC: &str = "bvb-static-v4";
+const OLD_CACHES: [&str; 3] = ["bvb-static-v1", "bvb-static-v2", "bvb-static-v3"];
 const PRECACHE_URLS: [&str; 10] = [
     "/",
     "/index.html",
     "/beyond_vs_below.js",
     "/beyond_vs_below_bg.wasm",
     "/assets/title_screen/title_screen.png",
-    "/assets/levels/mall_parking_lot.wasm?v=unit11",
+    "/assets/levels/mall_parking_lot.wasm?v=abi2",
     "/sw_bootstrap_sync.js",
     "/sw_bootstrap.js",
     "/assets/sw/bvb_sw.js",
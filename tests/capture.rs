@@ -0,0 +1,75 @@
+use blob2d_renderer::FrameCapture;
+
+fn checker(width: u32, height: u32) -> FrameCapture {
+    let mut rgba = Vec::with_capacity((width * height * 4) as usize);
+    for y in 0..height {
+        for x in 0..width {
+            let on = (x / 4 + y / 4) % 2 == 0;
+            rgba.extend_from_slice(if on {
+                &[244, 231, 208, 255]
+            } else {
+                &[14, 22, 30, 255]
+            });
+        }
+    }
+    FrameCapture {
+        width,
+        height,
+        rgba,
+    }
+}
+
+fn chunks(png: &[u8]) -> Vec<([u8; 4], &[u8])> {
+    let mut out = Vec::new();
+    let mut at = 8;
+    while at < png.len() {
+        let len = u32::from_be_bytes(png[at..at + 4].try_into().unwrap()) as usize;
+        let kind: [u8; 4] = png[at + 4..at + 8].try_into().unwrap();
+        out.push((kind, &png[at + 8..at + 8 + len]));
+        at += 12 + len;
+    }
+    out
+}
+
+#[test]
+fn png_has_signature_header_and_end_chunk() {
+    let png = checker(33, 17).to_png();
+
+    assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+    let chunks = chunks(&png);
+    let kinds: Vec<&[u8; 4]> = chunks.iter().map(|(kind, _)| kind).collect();
+    assert_eq!(kinds, [b"IHDR", b"IDAT", b"IEND"]);
+
+    let ihdr = chunks[0].1;
+    assert_eq!(u32::from_be_bytes(ihdr[0..4].try_into().unwrap()), 33);
+    assert_eq!(u32::from_be_bytes(ihdr[4..8].try_into().unwrap()), 17);
+    assert_eq!(&ihdr[8..10], &[8, 6]);
+}
+
+#[test]
+fn png_decodes_to_the_captured_pixels() {
+    let frame = checker(33, 17);
+    let decoded = bvb_raster::decode_png(&frame.to_png()).expect("decode capture");
+
+    assert_eq!((decoded.width, decoded.height), (33, 17));
+    assert_eq!(decoded.pixels, frame.rgba);
+}
+
+#[test]
+fn repetitive_frames_compress() {
+    let frame = checker(128, 128);
+    let png = frame.to_png();
+
+    assert!(png.len() < frame.rgba.len() / 8);
+}
+
+#[test]
+fn save_png_writes_file() {
+    let path = std::env::temp_dir().join("blob2d-renderer-capture-test.png");
+    let frame = checker(8, 8);
+
+    frame.save_png(&path).expect("write capture");
+
+    assert_eq!(std::fs::read(&path).expect("read capture"), frame.to_png());
+    let _ = std::fs::remove_file(path);
+}
@@ -0,0 +1,84 @@
+use blob2d_renderer::{Hazard, HazardInfo, HazardOverlay, HazardState, HAZARD_FRAME_SIZE};
+
+fn lava(state: HazardState) -> HazardInfo {
+    HazardInfo {
+        kind: Hazard::Lava,
+        state,
+        x: 32,
+        y: 48,
+        width: 32,
+        height: 16,
+    }
+}
+
+#[test]
+fn draws_one_quad_per_tile_of_each_visible_hazard() {
+    let overlay = HazardOverlay::new(
+        vec![
+            lava(HazardState::Active),
+            lava(HazardState::Dormant),
+            HazardInfo {
+                kind: Hazard::Collapse,
+                state: HazardState::Depleted,
+                x: 0,
+                y: 0,
+                width: 16,
+                height: 16,
+            },
+        ],
+        16,
+    );
+    let sprites = overlay.sprites(0);
+    assert_eq!(sprites.len(), 3);
+    assert_eq!(sprites[0].center, [40.0, 56.0]);
+    assert_eq!(sprites[1].center, [56.0, 56.0]);
+    assert_eq!(sprites[0].size, [16.0, 16.0]);
+    // Lava active is row 0, column 1; collapse depleted is row 2, column 2.
+    assert_eq!(sprites[0].frame, 1);
+    assert_eq!(sprites[2].frame, 8);
+    assert_eq!(sprites[2].color, [1.0; 4]);
+}
+
+#[test]
+fn set_state_changes_what_is_drawn() {
+    let mut overlay = HazardOverlay::new(vec![lava(HazardState::Dormant)], 16);
+    assert!(overlay.sprites(0).is_empty());
+
+    overlay.set_state(0, HazardState::Warning).unwrap();
+    assert_eq!(overlay.hazards()[0].state, HazardState::Warning);
+    assert_eq!(overlay.sprites(0)[0].frame, 0);
+    assert_eq!(
+        overlay.set_state(1, HazardState::Active).unwrap_err(),
+        "hazard 1 does not exist, the level has 1"
+    );
+}
+
+#[test]
+fn warnings_flash_and_others_stay_steady() {
+    let warning = HazardOverlay::new(vec![lava(HazardState::Warning)], 16);
+    let alpha = |ms| warning.sprites(ms)[0].color[3];
+    assert!((alpha(0) - 0.35).abs() < 1e-6);
+    assert!((alpha(300) - 1.0).abs() < 1e-6);
+    assert_eq!(alpha(150), alpha(450));
+    assert_eq!(alpha(0), alpha(600));
+
+    let active = HazardOverlay::new(vec![lava(HazardState::Active)], 16);
+    assert_eq!(active.sprites(150)[0].color[3], 1.0);
+}
+
+#[test]
+fn atlas_has_a_frame_per_kind_and_drawn_state() {
+    let (pixels, layout) = HazardOverlay::atlas();
+    assert_eq!((layout.width, layout.height), (48, 48));
+    assert_eq!(layout.frame_width, HAZARD_FRAME_SIZE);
+    assert_eq!(pixels.len(), 48 * 48 * 4);
+    // Every frame draws something.
+    for frame in 0..9 {
+        let (fx, fy) = ((frame % 3) * 16, (frame / 3) * 16);
+        let opaque = (0..16 * 16).any(|i| {
+            let (x, y) = (fx + i % 16, fy + i / 16);
+            pixels[(y * 48 + x) * 4 + 3] > 0
+        });
+        assert!(opaque, "frame {frame} is empty");
+    }
+}
@@ -0,0 +1,155 @@
+use blob2d_renderer::{
+    ExitSide, Hazard, LayerKind, LevelInstance, LevelModule, LevelOptions, LevelParams, Marker,
+};
+use bvb_level_kit::content_hash;
+
+const PARKING_LOT: &[u8] = include_bytes!("../assets/levels/mall_parking_lot.wasm");
+// The parking lot as first shipped, before the versioned ABI.
+const LEGACY: &[u8] = include_bytes!("fixtures/legacy_parking_lot.wasm");
+
+#[test]
+fn loads_legacy_level_module() {
+    let level = LevelModule::load(LEGACY).expect("load level");
+
+    assert_eq!((level.width, level.height), (288, 512));
+    assert_eq!(level.pixels.len(), 288 * 512 * 4);
+    assert_eq!(level.scale, 1);
+    assert!(level.pixels.chunks_exact(4).all(|px| px[3] == 255));
+    assert_eq!(level.metadata.abi_version, 0);
+    assert_eq!(level.name_or("Untitled"), "Untitled");
+    assert!(level.semantics.is_none());
+    assert!(level.layers.is_empty());
+    assert!(level.hazards.is_empty());
+}
+
+#[test]
+fn rejects_non_level_modules() {
+    assert!(LevelModule::load(b"not wasm").is_err());
+
+    // A valid, empty module: no memory and none of the level exports.
+    let empty = [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00];
+    let err = LevelModule::load(&empty).expect_err("empty module");
+    assert!(err.contains("memory"), "{err}");
+}
+
+#[test]
+fn legacy_module_rejects_generation_options() {
+    let seeded = LevelOptions {
+        seed: 7,
+        params: Some(LevelParams::default()),
+        ..LevelOptions::default()
+    };
+    let err = LevelModule::load_with(LEGACY, &seeded).expect_err("no init_with");
+    assert!(err.contains("bvb_level_init_with"), "{err}");
+
+    let scaled = LevelOptions {
+        scale: 2,
+        ..LevelOptions::default()
+    };
+    let err = LevelModule::load_with(LEGACY, &scaled).expect_err("no render");
+    assert!(err.contains("bvb_level_render"), "{err}");
+}
+
+#[test]
+fn instance_regenerates_without_reinstantiating() {
+    let mut instance = LevelInstance::new(LEGACY).expect("instantiate");
+    assert_eq!(instance.abi_version(), 0);
+    let first = instance
+        .generate(&LevelOptions::default())
+        .expect("generate");
+    // Version 0 modules have no `bvb_level_release`, so releasing leaves the level in place.
+    instance.release().expect("release");
+    let second = instance
+        .generate(&LevelOptions::default())
+        .expect("regenerate");
+    assert_eq!(first, second);
+    assert_eq!(first, LevelModule::load(LEGACY).unwrap());
+}
+
+#[test]
+fn shipped_module_exports_layers_and_semantics() {
+    let level = LevelModule::load(PARKING_LOT).expect("load level");
+    assert_eq!((level.width, level.height, level.scale), (288, 512, 1));
+    let metadata = &level.metadata;
+    assert_eq!(metadata.abi_version, 2);
+    assert_eq!(level.name_or("Untitled"), "Mall Parking Lot");
+    assert_eq!(metadata.author.as_deref(), Some("Beyond vs Below"));
+    assert_eq!(
+        (
+            metadata.tile_size,
+            metadata.grid_width,
+            metadata.grid_height
+        ),
+        (Some(16), Some(18), Some(32))
+    );
+    assert_eq!(
+        metadata.content_hash,
+        Some(content_hash(288, 512, &level.pixels))
+    );
+
+    let kinds: Vec<_> = level.layers.iter().map(|layer| layer.info.kind).collect();
+    assert_eq!(
+        kinds,
+        [
+            LayerKind::Ground,
+            LayerKind::Animated,
+            LayerKind::Animated,
+            LayerKind::Foreground
+        ]
+    );
+    assert_eq!(level.layers[0].pixels.len(), level.pixels.len());
+    for layer in &level.layers {
+        let info = layer.info;
+        assert!(info.x + info.width <= 288 && info.y + info.height <= 512);
+        assert_eq!(layer.pixels.len(), info.pixels_len(1));
+    }
+    // The spawn rings pulse; the trees are still.
+    assert_eq!(
+        (level.layers[1].info.frames, level.layers[3].info.frames),
+        (4, 1)
+    );
+
+    // The parking lot has no hazards, in the table or on the grid.
+    assert!(level.hazards.is_empty());
+    let grid = level.semantics.as_ref().expect("semantic grid");
+    assert_eq!((grid.width, grid.height, grid.tile_size), (18, 32, 16));
+    assert!(grid.tiles.iter().all(|tile| tile.hazard == Hazard::None));
+    assert_eq!(grid.find(Marker::FlagBase), [(9, 16)]);
+    assert_eq!(grid.find(Marker::HumanExit), [(0, 15), (0, 16)]);
+    assert_eq!(grid.find(Marker::AlienSpawnBase).len(), 12);
+    assert_eq!(grid.find(Marker::DemonSpawnBase).len(), 12);
+}
+
+#[test]
+fn shipped_module_renders_seeded_levels_at_scale() {
+    let options = LevelOptions {
+        seed: 42,
+        params: Some(LevelParams {
+            crossovers: 4,
+            exit_side: ExitSide::Right,
+            ..LevelParams::default()
+        }),
+        scale: 2,
+    };
+    let mut instance = LevelInstance::new(PARKING_LOT).expect("instantiate");
+    assert_eq!(instance.abi_version(), 2);
+    let level = instance.generate(&options).expect("generate");
+    assert_eq!((level.width, level.height, level.scale), (576, 1024, 2));
+    assert_eq!(level.pixels.len(), 576 * 1024 * 4);
+    for layer in &level.layers {
+        assert_eq!(layer.pixels.len(), layer.info.pixels_len(2));
+    }
+    // Layers and the grid stay in level pixels and tiles whatever the scale.
+    assert_eq!(level.layers[0].info.width, 288);
+    let grid = level.semantics.as_ref().expect("semantic grid");
+    assert_eq!((grid.width, grid.height), (18, 32));
+    assert_eq!(grid.find(Marker::HumanExit), [(17, 15), (17, 16)]);
+
+    // Releasing frees the module's buffers and the same options render the same level again.
+    instance.release().expect("release");
+    assert_eq!(instance.generate(&options).expect("regenerate"), level);
+    let default = instance
+        .generate(&LevelOptions::default())
+        .expect("default level");
+    assert_ne!(default.metadata.content_hash, level.metadata.content_hash);
+}
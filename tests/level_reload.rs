@@ -0,0 +1,22 @@
+use blob2d_renderer::LevelManifest;
+
+#[test]
+fn reads_shasum_output() {
+    let text = "\
+3F9A0C  ./mall_parking_lot.wasm
+07bd1e *assets/levels/food_court.wasm
+
+";
+    let manifest = LevelManifest::parse(text).expect("manifest");
+    assert_eq!(manifest.hash("mall_parking_lot.wasm"), Some("3f9a0c"));
+    assert_eq!(manifest.hash("food_court.wasm"), Some("07bd1e"));
+    assert_eq!(manifest.hash("rooftop.wasm"), None);
+}
+
+#[test]
+fn rejects_malformed_lines() {
+    let err = LevelManifest::parse("3f9a0c").unwrap_err();
+    assert!(err.contains("line 1 has no file name"), "{err}");
+    let err = LevelManifest::parse("\nnot-a-hash level.wasm").unwrap_err();
+    assert!(err.contains("line 2 has an invalid hash"), "{err}");
+}
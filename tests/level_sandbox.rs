@@ -0,0 +1,348 @@
+use blob2d_renderer::{LevelInstance, LevelLimits, LevelModule, LevelOptions};
+
+const PARKING_LOT: &[u8] = include_bytes!("../assets/levels/mall_parking_lot.wasm");
+
+// What the hand-assembled test module's exports do.
+#[derive(Clone, Copy)]
+struct Module {
+    // An env import ahead of everything else.
+    import: bool,
+    // Initial memory in 64 KiB pages.
+    pages: u32,
+    // Maximum memory in pages, declared when set.
+    max_pages: Option<u32>,
+    // A funcref table of this many elements, declared when set.
+    table: Option<u32>,
+    // `bvb_level_init` loops forever instead of returning.
+    spin: bool,
+    width: i32,
+    height: i32,
+    pixels_ptr: i32,
+    pixels_len: i32,
+    // Exports `bvb_level_scale` returning this when set.
+    scale: Option<i32>,
+}
+
+impl Default for Module {
+    fn default() -> Self {
+        Self {
+            import: false,
+            pages: 1,
+            max_pages: None,
+            table: None,
+            spin: false,
+            width: 4,
+            height: 4,
+            pixels_ptr: 1024,
+            pixels_len: 64,
+            scale: None,
+        }
+    }
+}
+
+fn leb_u32(mut value: u32, out: &mut Vec<u8>) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+fn leb_i32(mut value: i32, out: &mut Vec<u8>) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        let done = (value == 0 && byte & 0x40 == 0) || (value == -1 && byte & 0x40 != 0);
+        out.push(if done { byte } else { byte | 0x80 });
+        if done {
+            return;
+        }
+    }
+}
+
+fn section(id: u8, body: Vec<u8>, out: &mut Vec<u8>) {
+    out.push(id);
+    leb_u32(body.len() as u32, out);
+    out.extend(body);
+}
+
+fn name(text: &str, out: &mut Vec<u8>) {
+    leb_u32(text.len() as u32, out);
+    out.extend(text.as_bytes());
+}
+
+// A minimal module with the required level exports, assembled by hand.
+fn assemble(module: Module) -> Vec<u8> {
+    let mut wasm = vec![0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00];
+    // Types: 0 is () -> (), 1 is () -> i32.
+    section(
+        1,
+        vec![0x02, 0x60, 0x00, 0x00, 0x60, 0x00, 0x01, 0x7f],
+        &mut wasm,
+    );
+    let imported = u32::from(module.import);
+    if module.import {
+        let mut body = vec![0x01];
+        name("env", &mut body);
+        name("host_time", &mut body);
+        body.extend([0x00, 0x01]);
+        section(2, body, &mut wasm);
+    }
+    let mut consts = vec![
+        module.width,
+        module.height,
+        module.pixels_ptr,
+        module.pixels_len,
+    ];
+    consts.extend(module.scale);
+    let mut functions = vec![consts.len() as u8 + 1, 0x00];
+    functions.extend(consts.iter().map(|_| 0x01));
+    section(3, functions, &mut wasm);
+    if let Some(elements) = module.table {
+        let mut table = vec![0x01, 0x70, 0x00];
+        leb_u32(elements, &mut table);
+        section(4, table, &mut wasm);
+    }
+    let mut memory = vec![0x01, u8::from(module.max_pages.is_some())];
+    leb_u32(module.pages, &mut memory);
+    if let Some(max_pages) = module.max_pages {
+        leb_u32(max_pages, &mut memory);
+    }
+    section(5, memory, &mut wasm);
+
+    let exports = [
+        "bvb_level_init",
+        "bvb_level_width",
+        "bvb_level_height",
+        "bvb_level_pixels_ptr",
+        "bvb_level_pixels_len",
+        "bvb_level_scale",
+    ];
+    let exports = &exports[..consts.len() + 1];
+    let mut body = Vec::new();
+    leb_u32(exports.len() as u32 + 1, &mut body);
+    name("memory", &mut body);
+    body.extend([0x02, 0x00]);
+    for (index, export) in exports.iter().enumerate() {
+        name(export, &mut body);
+        body.push(0x00);
+        leb_u32(imported + index as u32, &mut body);
+    }
+    section(7, body, &mut wasm);
+
+    let mut code = vec![consts.len() as u8 + 1];
+    let init: &[u8] = if module.spin {
+        // loop br 0 end end
+        &[0x00, 0x03, 0x40, 0x0c, 0x00, 0x0b, 0x0b]
+    } else {
+        &[0x00, 0x0b]
+    };
+    leb_u32(init.len() as u32, &mut code);
+    code.extend(init);
+    for value in consts {
+        let mut func = vec![0x00, 0x41];
+        leb_i32(value, &mut func);
+        func.push(0x0b);
+        leb_u32(func.len() as u32, &mut code);
+        code.extend(func);
+    }
+    section(10, code, &mut wasm);
+    wasm
+}
+
+#[test]
+fn assembled_module_loads() {
+    let level = LevelModule::load(&assemble(Module::default())).expect("load");
+    assert_eq!((level.width, level.height), (4, 4));
+    assert_eq!(level.pixels, [0; 64]);
+}
+
+#[test]
+fn rejects_modules_with_imports() {
+    let wasm = assemble(Module {
+        import: true,
+        ..Module::default()
+    });
+    let err = LevelInstance::new(&wasm).err().expect("import");
+    assert_eq!(
+        err,
+        "level module imports `env.host_time`, level modules may not import anything"
+    );
+}
+
+#[test]
+fn stops_modules_that_run_out_of_fuel() {
+    let limits = LevelLimits {
+        fuel_per_call: 100_000,
+        ..LevelLimits::default()
+    };
+    let spin = assemble(Module {
+        spin: true,
+        ..Module::default()
+    });
+    let err = LevelModule::load_limited(&spin, &LevelOptions::default(), limits).unwrap_err();
+    assert_eq!(
+        err,
+        "level export `bvb_level_init` ran out of fuel (100000 units)"
+    );
+
+    // Fuel is per call, and the default covers the shipped level.
+    LevelModule::load(PARKING_LOT).expect("default limits");
+}
+
+#[test]
+fn enforces_the_memory_limit() {
+    let limits = LevelLimits {
+        memory_bytes: 4 << 16,
+        ..LevelLimits::default()
+    };
+    let large = assemble(Module {
+        pages: 5,
+        ..Module::default()
+    });
+    let err = LevelInstance::with_limits(&large, limits)
+        .err()
+        .expect("too large");
+    assert_eq!(
+        err,
+        "level module declares 5 pages (327680 bytes) of memory, more than the 262144 bytes allowed"
+    );
+    let small = assemble(Module {
+        pages: 4,
+        ..Module::default()
+    });
+    LevelInstance::with_limits(&small, limits).expect("fits");
+    // Only the initial size counts; growing towards a larger maximum traps at the limit instead.
+    let capped = assemble(Module {
+        pages: 2,
+        max_pages: Some(100),
+        ..Module::default()
+    });
+    LevelInstance::with_limits(&capped, limits).expect("fits");
+
+    // The parking lot starts smaller than this but grows past it while generating.
+    let limits = LevelLimits {
+        memory_bytes: 26 << 16,
+        ..LevelLimits::default()
+    };
+    let err = LevelModule::load_limited(PARKING_LOT, &LevelOptions::default(), limits).unwrap_err();
+    assert_eq!(
+        err,
+        "level export `bvb_level_init` tried to grow memory past 1703936 bytes"
+    );
+}
+
+#[test]
+fn checks_the_pixel_buffer_against_dimensions_and_memory() {
+    let cases = [
+        (
+            Module {
+                pixels_len: 60,
+                ..Module::default()
+            },
+            "level pixel buffer is 60 bytes, expected 64 for 4x4",
+        ),
+        (
+            // A negative i32 is a length above `i32::MAX` once read as a `usize` cast.
+            Module {
+                pixels_len: -64,
+                ..Module::default()
+            },
+            "level pixel buffer is 4294967232 bytes, expected 64 for 4x4",
+        ),
+        (
+            Module {
+                pixels_ptr: 65536 - 32,
+                ..Module::default()
+            },
+            "level pixel buffer 0xffe0..0x10020 lies outside linear memory (65536 bytes)",
+        ),
+        (
+            Module {
+                pixels_ptr: 0,
+                ..Module::default()
+            },
+            "level pixel buffer pointer is null",
+        ),
+        (
+            Module {
+                width: 0,
+                ..Module::default()
+            },
+            "level module has invalid dimensions 0x4",
+        ),
+        (
+            Module {
+                width: 4096,
+                ..Module::default()
+            },
+            "level module has invalid dimensions 4096x4",
+        ),
+    ];
+    for (module, expected) in cases {
+        assert_eq!(LevelModule::load(&assemble(module)).unwrap_err(), expected);
+    }
+}
+
+#[test]
+fn rejects_a_zero_scale() {
+    let mut instance = LevelInstance::new(&assemble(Module::default())).expect("instance");
+    let options = LevelOptions {
+        scale: 0,
+        ..LevelOptions::default()
+    };
+    assert_eq!(
+        instance.generate(&options).unwrap_err(),
+        "level scale must be at least 1"
+    );
+
+    let zero = assemble(Module {
+        scale: Some(0),
+        ..Module::default()
+    });
+    assert_eq!(
+        LevelModule::load(&zero).unwrap_err(),
+        "level module reports scale 0, scales start at 1"
+    );
+}
+
+#[test]
+fn rejects_truncated_modules_before_compiling() {
+    let wasm = assemble(Module::default());
+    let err = LevelInstance::new(&wasm[..wasm.len() - 3])
+        .err()
+        .expect("truncated");
+    assert_eq!(err, "invalid level module: unexpected end of the binary");
+    let err = LevelInstance::new(b"\0asm\x02\0\0\0")
+        .err()
+        .expect("version");
+    assert_eq!(err, "invalid level module: not a WebAssembly 1.0 binary");
+}
+
+#[test]
+fn enforces_the_table_limit() {
+    let limits = LevelLimits {
+        table_elements: 64,
+        ..LevelLimits::default()
+    };
+    let large = assemble(Module {
+        table: Some(1 << 28),
+        ..Module::default()
+    });
+    let err = LevelInstance::with_limits(&large, limits)
+        .err()
+        .expect("too large");
+    assert_eq!(
+        err,
+        "level module declares a table of 268435456 elements, more than the 64 allowed"
+    );
+    let small = assemble(Module {
+        table: Some(64),
+        ..Module::default()
+    });
+    LevelInstance::with_limits(&small, limits).expect("fits");
+}
@@ -0,0 +1,95 @@
+use blob2d_renderer::{
+    DrawCommand, FrameLog, Lighting, Occluder, PointLight, RecordedFrame, RecordedImage,
+    MAX_OCCLUDERS,
+};
+
+fn torch(position: [f32; 2]) -> PointLight {
+    PointLight {
+        position,
+        radius: 10.0,
+        color: [1.0, 0.5, 0.0],
+        intensity: 1.0,
+    }
+}
+
+#[test]
+fn point_lights_fall_off_to_ambient() {
+    let lighting = Lighting {
+        ambient: [0.1, 0.1, 0.2],
+        lights: vec![torch([0.0, 0.0])],
+        occluders: Vec::new(),
+    };
+
+    assert_eq!(lighting.light_at([0.0, 0.0]), [1.0, 0.6, 0.2]);
+    assert_eq!(lighting.light_at([5.0, 0.0]), [0.35, 0.225, 0.2]);
+    assert_eq!(lighting.light_at([20.0, 0.0]), [0.1, 0.1, 0.2]);
+}
+
+#[test]
+fn occluders_cast_shadows_but_stay_lit() {
+    let lighting = Lighting {
+        ambient: [0.0; 3],
+        lights: vec![torch([0.0, 0.0])],
+        occluders: vec![Occluder {
+            center: [4.0, 0.0],
+            size: [2.0, 2.0],
+        }],
+    };
+
+    assert_eq!(lighting.light_at([7.0, 0.0]), [0.0; 3]);
+    assert!(lighting.light_at([4.0, 0.0])[0] > 0.0);
+    assert!(lighting.light_at([0.0, 7.0])[0] > 0.0);
+}
+
+#[test]
+fn too_many_occluders_are_rejected() {
+    let lighting = Lighting {
+        occluders: vec![
+            Occluder {
+                center: [0.0, 0.0],
+                size: [1.0, 1.0],
+            };
+            MAX_OCCLUDERS + 1
+        ],
+        ..Lighting::default()
+    };
+
+    assert!(lighting.validate().is_err());
+}
+
+#[test]
+fn replay_multiplies_light_over_the_scene() {
+    let log = FrameLog {
+        images: vec![RecordedImage {
+            width: 2,
+            height: 1,
+            rgba: vec![255; 8],
+        }],
+        frames: vec![RecordedFrame {
+            time_ms: 0,
+            width: 2,
+            height: 1,
+            commands: vec![
+                DrawCommand::FullscreenImage { image: 0 },
+                DrawCommand::Lighting {
+                    world_size: [2.0, 1.0],
+                    lighting: Lighting {
+                        ambient: [0.0; 3],
+                        lights: vec![PointLight {
+                            position: [0.5, 0.5],
+                            radius: 1.0,
+                            color: [1.0; 3],
+                            intensity: 1.0,
+                        }],
+                        occluders: Vec::new(),
+                    },
+                },
+            ],
+        }],
+    };
+
+    assert_eq!(FrameLog::decode(&log.encode()).expect("decode log"), log);
+
+    let frame = log.render_frame(0, 2, 1).expect("replay");
+    assert_eq!(frame.rgba, [255, 255, 255, 255, 0, 0, 0, 255]);
+}
@@ -0,0 +1,100 @@
+use blob2d_renderer::{Curve, Emission, EmitterConfig, ParticleEmitter, ParticleSystem};
+
+const STEP: f32 = 1.0 / 60.0;
+
+fn run(seed: u64, steps: usize) -> Vec<blob2d_renderer::SpriteInstance> {
+    let mut system = ParticleSystem::new();
+    system
+        .spawn(EmitterConfig::push_impact(), [40.0, 60.0], seed)
+        .expect("push impact");
+    system
+        .spawn(
+            EmitterConfig::portal([0.4, 0.9, 0.5, 1.0]),
+            [100.0, 200.0],
+            seed + 1,
+        )
+        .expect("portal");
+    for _ in 0..steps {
+        system.update(STEP);
+    }
+    system.sprites()
+}
+
+#[test]
+fn same_seed_and_steps_give_identical_particles() {
+    assert_eq!(run(7, 12), run(7, 12));
+    assert_ne!(run(7, 12), run(8, 12));
+}
+
+#[test]
+fn burst_spawns_once_and_finishes() {
+    let mut system = ParticleSystem::new();
+    system
+        .spawn(EmitterConfig::push_impact(), [0.0, 0.0], 1)
+        .expect("push impact");
+
+    system.update(STEP);
+    assert_eq!(system.particle_count(), 18);
+
+    for _ in 0..60 {
+        system.update(STEP);
+    }
+    assert_eq!(system.emitter_count(), 0);
+    assert!(system.sprites().is_empty());
+}
+
+#[test]
+fn continuous_emission_follows_rate_and_duration() {
+    let mut config = EmitterConfig::portal([1.0; 4]);
+    config.emission = Emission::Continuous {
+        rate: 10.0,
+        duration: Some(1.0),
+    };
+    config.lifetime = (10.0, 10.0);
+    let mut emitter = ParticleEmitter::new(config, [0.0, 0.0], 3).expect("emitter");
+
+    for _ in 0..120 {
+        emitter.update(STEP);
+    }
+    assert_eq!(emitter.particle_count(), 10);
+    assert!(!emitter.is_finished());
+}
+
+#[test]
+fn continuous_emission_stays_within_the_cap() {
+    let mut config = EmitterConfig::portal([1.0; 4]);
+    config.emission = Emission::Continuous {
+        rate: f32::INFINITY,
+        duration: None,
+    };
+    let mut system = ParticleSystem::new();
+    assert_eq!(
+        system.spawn(config.clone(), [0.0, 0.0], 1).unwrap_err(),
+        "emitter rate inf is not finite"
+    );
+    assert_eq!(system.emitter_count(), 0);
+
+    // A huge finite rate fills the emitter once instead of spawning a billion particles a frame.
+    config.emission = Emission::Continuous {
+        rate: 1e12,
+        duration: None,
+    };
+    config.lifetime = (10.0, 10.0);
+    let mut emitter = ParticleEmitter::new(config, [0.0, 0.0], 3).expect("emitter");
+    emitter.update(STEP);
+    assert_eq!(emitter.particle_count(), 64);
+    emitter.update(STEP);
+    assert_eq!(emitter.particle_count(), 64);
+}
+
+#[test]
+fn curves_interpolate_and_clamp() {
+    let curve = Curve {
+        keys: vec![(0.0, 0.0), (0.5, 1.0), (1.0, 3.0)],
+    };
+
+    assert_eq!(curve.sample(-1.0), 0.0);
+    assert_eq!(curve.sample(0.25), 0.5);
+    assert_eq!(curve.sample(0.75), 2.0);
+    assert_eq!(curve.sample(2.0), 3.0);
+}
@@ -0,0 +1,199 @@
+use blob2d_renderer::{
+    DrawCommand, FrameLog, Lighting, RecordedFrame, RecordedImage, SpriteInstance,
+};
+
+const CLEAR: [f32; 4] = [0.95, 0.91, 0.84, 1.0];
+
+fn two_pixel_log(frames: usize) -> FrameLog {
+    let image = RecordedImage {
+        width: 2,
+        height: 1,
+        rgba: vec![255, 0, 0, 255, 0, 0, 255, 255],
+    };
+    let frames = (0..frames)
+        .map(|i| RecordedFrame {
+            time_ms: i as u32 * 16,
+            width: 4,
+            height: 2,
+            commands: vec![
+                DrawCommand::Clear(CLEAR),
+                DrawCommand::FullscreenImage { image: 0 },
+            ],
+        })
+        .collect();
+    FrameLog {
+        images: vec![image],
+        frames,
+    }
+}
+
+#[test]
+fn encode_decode_round_trips() {
+    let log = two_pixel_log(5);
+    let bytes = log.encode();
+
+    assert_eq!(FrameLog::decode(&bytes).expect("decode log"), log);
+}
+
+#[test]
+fn repeated_frames_encode_compactly() {
+    let short = two_pixel_log(2).encode();
+    let long = two_pixel_log(102).encode();
+
+    // Each repeated frame costs its time delta, size and a repeat marker.
+    assert!(long.len() - short.len() <= 100 * 4);
+}
+
+#[test]
+fn decode_rejects_truncated_and_foreign_data() {
+    let bytes = two_pixel_log(1).encode();
+
+    assert!(FrameLog::decode(&bytes[..bytes.len() - 1]).is_err());
+    assert!(FrameLog::decode(b"PNG?").is_err());
+}
+
+fn varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+// A log header followed by `images` images of `size` x `size`, each one run of a single pixel.
+fn flat_images(images: u64, size: u64) -> Vec<u8> {
+    let mut bytes = b"B2DR\x01".to_vec();
+    varint(&mut bytes, images);
+    for _ in 0..images {
+        varint(&mut bytes, size);
+        varint(&mut bytes, size);
+        varint(&mut bytes, size * size);
+        bytes.extend([1, 2, 3, 255]);
+    }
+    bytes
+}
+
+#[test]
+fn decode_rejects_oversized_images_and_frames() {
+    // About 20 bytes asking for a 65535 x 65535 image.
+    let huge = flat_images(1, 65535);
+    assert!(huge.len() < 24);
+    assert_eq!(
+        FrameLog::decode(&huge).unwrap_err(),
+        "image 0 is 65535x65535, at most 2048x2048 is allowed"
+    );
+
+    // Level-sized images are fine one at a time but not by the dozen.
+    let mut many = flat_images(9, 2048);
+    varint(&mut many, 0);
+    assert_eq!(
+        FrameLog::decode(&many).unwrap_err(),
+        "frame log images exceed 134217728 decoded bytes"
+    );
+
+    let mut wide = flat_images(0, 0);
+    for value in [1, 0, 4096, 1, 1] {
+        varint(&mut wide, value);
+    }
+    assert_eq!(
+        FrameLog::decode(&wide).unwrap_err(),
+        "frame 0 is 4096x1, at most 2048x2048 is allowed"
+    );
+    assert!(two_pixel_log(1).render_frame(0, 1, 65535).is_err());
+
+    // A world size scales the light map, so a huge one would mean a trillion light samples.
+    for command in [
+        DrawCommand::Lighting {
+            world_size: [1e6, 1e6],
+            lighting: Lighting::default(),
+        },
+        DrawCommand::Sprites {
+            atlas: 0,
+            frame_size: [1, 1],
+            world_size: [f32::NAN, 4.0],
+            sprites: Vec::new(),
+        },
+    ] {
+        let mut log = two_pixel_log(1);
+        log.frames[0].commands.push(command);
+        assert!(FrameLog::decode(&log.encode())
+            .unwrap_err()
+            .starts_with("world size "));
+    }
+}
+
+#[test]
+fn replay_stretches_image_with_nearest_sampling() {
+    let frame = two_pixel_log(1).render_frame(0, 4, 2).expect("replay");
+
+    let reds: Vec<u8> = frame.rgba.chunks_exact(4).map(|px| px[0]).collect();
+    assert_eq!(reds, [255, 255, 0, 0, 255, 255, 0, 0]);
+}
+
+#[test]
+fn replay_clear_is_srgb_encoded() {
+    let log = FrameLog {
+        images: Vec::new(),
+        frames: vec![RecordedFrame {
+            time_ms: 0,
+            width: 1,
+            height: 1,
+            commands: vec![DrawCommand::Clear([0.5, 0.0, 1.0, 1.0])],
+        }],
+    };
+
+    assert_eq!(
+        log.render_frame(0, 1, 1).expect("replay").rgba,
+        [188, 0, 255, 255]
+    );
+}
+
+#[test]
+fn apng_merges_identical_frames() {
+    let apng = two_pixel_log(3).to_apng().expect("apng");
+
+    let actl = apng
+        .windows(4)
+        .position(|w| w == b"acTL")
+        .expect("acTL chunk");
+    let frame_count = u32::from_be_bytes(apng[actl + 4..actl + 8].try_into().unwrap());
+    assert_eq!(frame_count, 1);
+    assert!(!apng.windows(4).any(|w| w == b"fdAT"));
+}
+
+#[test]
+fn empty_log_has_no_apng() {
+    assert_eq!(
+        FrameLog::default().to_apng().unwrap_err(),
+        "frame log has no frames"
+    );
+}
+
+#[test]
+fn replay_blends_tinted_sprites_over_the_image() {
+    let mut log = two_pixel_log(1);
+    log.images.push(RecordedImage {
+        width: 1,
+        height: 1,
+        rgba: vec![255, 255, 255, 255],
+    });
+    log.frames[0].commands.push(DrawCommand::Sprites {
+        atlas: 1,
+        frame_size: [1, 1],
+        world_size: [2.0, 1.0],
+        sprites: vec![SpriteInstance {
+            center: [1.5, 0.5],
+            size: [1.0, 1.0],
+            frame: 0,
+            color: [0.0, 1.0, 0.0, 1.0],
+        }],
+    });
+
+    assert_eq!(FrameLog::decode(&log.encode()).expect("decode log"), log);
+
+    let frame = log.render_frame(0, 4, 2).expect("replay");
+    let pixels: Vec<&[u8]> = frame.rgba.chunks_exact(4).collect();
+    assert_eq!(pixels[0], [255, 0, 0, 255]);
+    assert_eq!(pixels[3], [0, 255, 0, 255]);
+    assert_eq!(pixels[7], [0, 255, 0, 255]);
+}
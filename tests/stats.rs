@@ -0,0 +1,23 @@
+use blob2d_renderer::RendererStats;
+
+#[test]
+fn default_stats_report_unavailable_backend_and_no_gpu_timing() {
+    let text = RendererStats::default().to_string();
+
+    assert!(text.contains("backend: Unavailable"));
+    assert!(text.contains("gpu: n/a"));
+    assert!(text.contains("draws: 0, sprites batched: 0"));
+}
+
+#[test]
+fn fps_is_derived_from_frame_interval() {
+    let stats = RendererStats {
+        frame_interval_ms: 20.0,
+        gpu_frame_ms: Some(1.5),
+        ..Default::default()
+    };
+
+    assert!((stats.fps() - 50.0).abs() < f64::EPSILON);
+    assert!(stats.to_string().contains("gpu: 1.50 ms"));
+    assert_eq!(RendererStats::default().fps(), 0.0);
+}
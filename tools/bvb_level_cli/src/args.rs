@@ -0,0 +1,91 @@
+use std::path::PathBuf;
+
+use bvb_level_kit::{ExitSide, LevelParams};
+
+pub const USAGE: &str = "\
+Usage: bvb-level <LEVEL> [OPTIONS]
+
+Prints a level's metadata and checks that its spawn bases are connected.
+
+LEVEL is a level module (.wasm), a level description (.toml), or `parking-lot` to run the
+parking lot generator directly.
+
+Options:
+  --seed <N>                Seed for `bvb_level_init_with` (default 0)
+  --lane-width <PX>         Params: lane width in pixels (default 36)
+  --crossovers <N>          Params: staggered island openings (default 2)
+  --bay-density <N>         Params: parking stalls, 0 to 3 (default 2)
+  --exit-side <left|right>  Params: edge holding the human exit (default left)
+  --scale <N>               Image pixels per level pixel, 1 to 4 (default 1)
+  --png <FILE>              Write the level image
+  --overlay <FILE>          Write the image with walkable, blocked and marker tiles tinted
+  --check                   Exit with status 1 unless the alien and demon bases are connected
+  -h, --help                Print this help
+";
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Args {
+    pub level: String,
+    pub seed: u32,
+    /// `Some` once any params option is given.
+    pub params: Option<LevelParams>,
+    pub scale: u32,
+    pub png: Option<PathBuf>,
+    pub overlay: Option<PathBuf>,
+    pub check: bool,
+    pub help: bool,
+}
+
+impl Args {
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut parsed = Args {
+            scale: 1,
+            ..Args::default()
+        };
+        let mut level = None;
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or_else(|| format!("`{arg}` needs a value"));
+            match arg.as_str() {
+                "-h" | "--help" => parsed.help = true,
+                "--check" => parsed.check = true,
+                "--seed" => parsed.seed = number(&arg, &value()?)?,
+                "--scale" => parsed.scale = number(&arg, &value()?)?,
+                "--png" => parsed.png = Some(value()?.into()),
+                "--overlay" => parsed.overlay = Some(value()?.into()),
+                "--lane-width" => parsed.params().lane_width = number(&arg, &value()?)?,
+                "--crossovers" => parsed.params().crossovers = number(&arg, &value()?)?,
+                "--bay-density" => parsed.params().bay_density = number(&arg, &value()?)?,
+                "--exit-side" => {
+                    parsed.params().exit_side = match value()?.as_str() {
+                        "left" => ExitSide::Left,
+                        "right" => ExitSide::Right,
+                        other => {
+                            return Err(format!(
+                                "`--exit-side` must be left or right, not `{other}`"
+                            ));
+                        }
+                    }
+                }
+                _ if arg.starts_with('-') => return Err(format!("unknown option `{arg}`")),
+                _ if level.is_some() => return Err(format!("unexpected argument `{arg}`")),
+                _ => level = Some(arg),
+            }
+        }
+        if parsed.help {
+            return Ok(parsed);
+        }
+        parsed.level = level.ok_or("missing LEVEL")?;
+        Ok(parsed)
+    }
+
+    fn params(&mut self) -> &mut LevelParams {
+        self.params.get_or_insert_with(LevelParams::default)
+    }
+}
+
+fn number<T: std::str::FromStr>(option: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("`{option}` takes a number, not `{value}`"))
+}
@@ -0,0 +1,64 @@
+// `bvb-level`: previews and checks level modules without serving the app.
+//
+// Loads a level module with the same native loader the host uses, renders a description, or runs
+// the parking lot generator in-process, then prints the metadata and writes PNGs. See
+// `docs/levels.md`.
+
+mod args;
+mod report;
+mod source;
+
+use std::process::ExitCode;
+
+use blob2d_renderer::encode_png;
+
+use args::{Args, USAGE};
+use report::Connectivity;
+
+fn main() -> ExitCode {
+    let args = match Args::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("bvb-level: {err}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    if args.help {
+        print!("{USAGE}");
+        return ExitCode::SUCCESS;
+    }
+    match run(&args) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(err) => {
+            eprintln!("bvb-level: {err}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+// Returns whether the level passed `--check`.
+fn run(args: &Args) -> Result<bool, String> {
+    let level = source::load(args)?;
+    print!("{}", report::describe(&level));
+
+    if let Some(path) = &args.png {
+        let png = encode_png(level.width, level.height, &level.pixels);
+        std::fs::write(path, png)
+            .map_err(|err| format!("cannot write {}: {err}", path.display()))?;
+        println!("wrote {}", path.display());
+    }
+    if let Some(path) = &args.overlay {
+        let grid = level
+            .semantics
+            .as_ref()
+            .ok_or("the level has no semantic grid to overlay")?;
+        let pixels = report::overlay(&level, grid)?;
+        let png = encode_png(level.width, level.height, &pixels);
+        std::fs::write(path, png)
+            .map_err(|err| format!("cannot write {}: {err}", path.display()))?;
+        println!("wrote {}", path.display());
+    }
+
+    Ok(!args.check || report::connectivity(&level) == Connectivity::Connected)
+}
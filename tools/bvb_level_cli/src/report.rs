@@ -0,0 +1,172 @@
+use std::fmt::Write as _;
+
+use blob2d_renderer::LevelModule;
+use bvb_level_kit::{Hazard, LayerKind, Marker, NavGraph, SemanticGrid};
+use bvb_raster::{Canvas, Rgba};
+
+// Overlay tints, blended over the level image.
+const WALKABLE_TINT: Rgba = [40, 220, 90, 72];
+const BLOCKED_TINT: Rgba = [230, 40, 40, 120];
+const GRID_LINE: Rgba = [0, 0, 0, 64];
+
+fn marker_tint(marker: Marker) -> Option<Rgba> {
+    match marker {
+        Marker::None => None,
+        Marker::AlienSpawnBase => Some([40, 220, 255, 160]),
+        Marker::DemonSpawnBase => Some([255, 120, 30, 160]),
+        Marker::FlagBase => Some([255, 230, 40, 160]),
+        Marker::HumanExit => Some([255, 255, 255, 160]),
+        Marker::HumanSpawn => Some([230, 60, 230, 160]),
+    }
+}
+
+/// Whether the alien and demon bases share a walkable region.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Connectivity {
+    Connected,
+    Disconnected,
+    /// The module exports no semantic grid.
+    NoGrid,
+    /// The grid lacks an alien or a demon base.
+    MissingBase,
+}
+
+pub fn connectivity(level: &LevelModule) -> Connectivity {
+    let Some(grid) = &level.semantics else {
+        return Connectivity::NoGrid;
+    };
+    let (alien, demon) = (
+        grid.find(Marker::AlienSpawnBase),
+        grid.find(Marker::DemonSpawnBase),
+    );
+    let (Some(&alien), Some(&demon)) = (alien.first(), demon.first()) else {
+        return Connectivity::MissingBase;
+    };
+    if NavGraph::new(grid).regions().connected(&[alien, demon]) {
+        Connectivity::Connected
+    } else {
+        Connectivity::Disconnected
+    }
+}
+
+/// The metadata listing printed for every level.
+pub fn describe(level: &LevelModule) -> String {
+    let meta = &level.metadata;
+    let mut out = String::new();
+    let unknown = "-";
+    let _ = writeln!(
+        out,
+        "name:         {}",
+        meta.name.as_deref().unwrap_or(unknown)
+    );
+    let _ = writeln!(
+        out,
+        "author:       {}",
+        meta.author.as_deref().unwrap_or(unknown)
+    );
+    let _ = writeln!(out, "abi version:  {}", meta.abi_version);
+    let _ = writeln!(
+        out,
+        "image:        {}x{} at scale {}",
+        level.width, level.height, level.scale
+    );
+    match (meta.grid_width, meta.grid_height, meta.tile_size) {
+        (Some(width), Some(height), Some(tile)) => {
+            let _ = writeln!(out, "grid:         {width}x{height} tiles of {tile} px");
+        }
+        _ => {
+            let _ = writeln!(out, "grid:         {unknown}");
+        }
+    }
+    match meta.content_hash {
+        Some(hash) => {
+            let _ = writeln!(out, "content hash: {hash:016x}");
+        }
+        None => {
+            let _ = writeln!(out, "content hash: {unknown}");
+        }
+    }
+    if level.layers.is_empty() {
+        let _ = writeln!(out, "layers:       {unknown}");
+    } else {
+        let count = |kind| {
+            level
+                .layers
+                .iter()
+                .filter(|layer| layer.info.kind == kind)
+                .count()
+        };
+        let _ = writeln!(
+            out,
+            "layers:       ground, {} animated, {} foreground",
+            count(LayerKind::Animated),
+            count(LayerKind::Foreground)
+        );
+    }
+    let hazards: Vec<_> = [
+        (Hazard::Lava, "lava"),
+        (Hazard::Turret, "turret"),
+        (Hazard::Collapse, "collapse"),
+    ]
+    .into_iter()
+    .filter_map(|(kind, label)| {
+        let count = level.hazards.iter().filter(|h| h.kind == kind).count();
+        (count > 0).then(|| format!("{count} {label}"))
+    })
+    .collect();
+    let _ = writeln!(
+        out,
+        "hazards:      {}",
+        if hazards.is_empty() {
+            unknown.to_string()
+        } else {
+            hazards.join(", ")
+        }
+    );
+    if let Some(grid) = &level.semantics {
+        let walkable = grid.tiles.iter().filter(|tile| tile.is_walkable()).count();
+        let regions = NavGraph::new(grid).regions().count();
+        let _ = writeln!(
+            out,
+            "walkable:     {walkable} of {} tiles, {regions} region{}",
+            grid.tiles.len(),
+            if regions == 1 { "" } else { "s" }
+        );
+    }
+    let bases = match connectivity(level) {
+        Connectivity::Connected => "connected",
+        Connectivity::Disconnected => "NOT connected",
+        Connectivity::NoGrid => "unknown, no semantic grid",
+        Connectivity::MissingBase => "missing an alien or demon base",
+    };
+    let _ = writeln!(out, "bases:        {bases}");
+    out
+}
+
+/// The level image with every tile tinted by its classification: markers first, then walkable
+/// green or blocked red, with faint tile borders.
+pub fn overlay(level: &LevelModule, grid: &SemanticGrid) -> Result<Vec<u8>, String> {
+    let mut canvas = Canvas::from_rgba(level.width, level.height, level.pixels.clone())?;
+    let tile = (grid.tile_size * level.scale) as i32;
+    for ty in 0..grid.height {
+        for tx in 0..grid.width {
+            let semantic = grid.get(tx, ty).expect("tile inside the grid");
+            let tint = marker_tint(semantic.marker).unwrap_or(if semantic.is_walkable() {
+                WALKABLE_TINT
+            } else {
+                BLOCKED_TINT
+            });
+            let (x0, y0) = (tx as i32 * tile, ty as i32 * tile);
+            for y in y0..y0 + tile {
+                for x in x0..x0 + tile {
+                    let edge = x == x0 || y == y0;
+                    canvas.blend(x, y, tint);
+                    if edge {
+                        canvas.blend(x, y, GRID_LINE);
+                    }
+                }
+            }
+        }
+    }
+    Ok(canvas.pixels)
+}
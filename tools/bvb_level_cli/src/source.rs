@@ -0,0 +1,98 @@
+use blob2d_renderer::{LevelMetadata, LevelModule, LevelOptions};
+use bvb_level_builder::{LevelDesc, MAX_IMAGE_DIMENSION};
+use bvb_level_kit::{ABI_VERSION, HazardInfo, LevelLayer, SemanticGrid, content_hash};
+
+use crate::args::Args;
+
+/// `LEVEL` value that runs the parking lot generator in-process.
+pub const PARKING_LOT: &str = "parking-lot";
+
+/// Loads or generates the level `args` names. Every source ends up as a `LevelModule`, so the
+/// rest of the tool does not care where the pixels came from.
+pub fn load(args: &Args) -> Result<LevelModule, String> {
+    if !(1..=4).contains(&args.scale) {
+        return Err(format!("`--scale` must be 1 to 4, not {}", args.scale));
+    }
+    if args.level == PARKING_LOT {
+        let params = args.params.unwrap_or_default();
+        let (layers, grid) = bvb_level_parking_lot::generate_layers(args.seed, &params, args.scale);
+        let pixels = bvb_level_parking_lot::flatten_layers(&layers, args.scale);
+        return Ok(from_generator(
+            bvb_level_parking_lot::LEVEL_NAME,
+            bvb_level_parking_lot::LEVEL_AUTHOR,
+            args.scale,
+            pixels,
+            grid,
+            layers,
+            Vec::new(),
+        ));
+    }
+
+    let bytes =
+        std::fs::read(&args.level).map_err(|err| format!("cannot read {}: {err}", args.level))?;
+    if args.level.ends_with(".toml") {
+        if args.seed != 0 || args.params.is_some() {
+            return Err("level descriptions take no seed or params".to_string());
+        }
+        let text =
+            String::from_utf8(bytes).map_err(|_| format!("{} is not valid UTF-8", args.level))?;
+        let desc = LevelDesc::parse(&text).map_err(|err| format!("{}: {err}", args.level))?;
+        // The same limit a module built from the description applies in `bvb_level_render`.
+        let (width, height) = desc.pixel_size();
+        if width.max(height) * args.scale > MAX_IMAGE_DIMENSION {
+            return Err(format!(
+                "{}: {width}x{height} at scale {} exceeds {MAX_IMAGE_DIMENSION} pixels",
+                args.level, args.scale
+            ));
+        }
+        let (pixels, grid) = desc.render(args.scale);
+        return Ok(from_generator(
+            &desc.name,
+            &desc.author,
+            args.scale,
+            pixels,
+            grid,
+            Vec::new(),
+            desc.hazard_infos(),
+        ));
+    }
+
+    let options = LevelOptions {
+        seed: args.seed,
+        params: args.params,
+        scale: args.scale,
+    };
+    LevelModule::load_with(&bytes, &options).map_err(|err| format!("{}: {err}", args.level))
+}
+
+// What a module built from the same generator would report.
+fn from_generator(
+    name: &str,
+    author: &str,
+    scale: u32,
+    pixels: Vec<u8>,
+    grid: SemanticGrid,
+    layers: Vec<LevelLayer>,
+    hazards: Vec<HazardInfo>,
+) -> LevelModule {
+    let width = grid.width * grid.tile_size * scale;
+    let height = grid.height * grid.tile_size * scale;
+    LevelModule {
+        width,
+        height,
+        scale,
+        metadata: LevelMetadata {
+            abi_version: ABI_VERSION,
+            name: Some(name.to_string()),
+            author: Some(author.to_string()),
+            tile_size: Some(grid.tile_size),
+            grid_width: Some(grid.width),
+            grid_height: Some(grid.height),
+            content_hash: Some(content_hash(width, height, &pixels)),
+        },
+        pixels,
+        semantics: Some(grid),
+        layers,
+        hazards,
+    }
+}
@@ -0,0 +1,102 @@
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+use bvb_raster::decode_png;
+
+fn bvb_level(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_bvb-level"))
+        .args(args)
+        .output()
+        .expect("run bvb-level")
+}
+
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("bvb-level-{name}-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn generator_writes_image_and_overlay() {
+    let dir = scratch_dir("generator");
+    let (png, overlay) = (dir.join("level.png"), dir.join("overlay.png"));
+    let out = bvb_level(&[
+        "parking-lot",
+        "--scale",
+        "2",
+        "--crossovers",
+        "4",
+        "--png",
+        png.to_str().unwrap(),
+        "--overlay",
+        overlay.to_str().unwrap(),
+        "--check",
+    ]);
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(out.status.success(), "{stdout}");
+    assert!(
+        stdout.contains("name:         Mall Parking Lot"),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains("image:        576x1024 at scale 2"),
+        "{stdout}"
+    );
+    assert!(stdout.contains("bases:        connected"), "{stdout}");
+
+    let image = decode_png(&std::fs::read(&png).unwrap()).unwrap();
+    let tinted = decode_png(&std::fs::read(&overlay).unwrap()).unwrap();
+    assert_eq!((image.width, image.height), (576, 1024));
+    assert_eq!((tinted.width, tinted.height), (576, 1024));
+    assert_ne!(image.pixels, tinted.pixels);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn check_fails_when_bases_are_walled_off() {
+    let dir = scratch_dir("walled");
+    let path = dir.join("walled.toml");
+    let shipped = include_str!("../../../levels/bvb_level_demon_lair/demon_lair.toml");
+    // A wall across the whole map between the bases.
+    let walled =
+        format!("{shipped}\n[[islands]]\nx = 0\ny = 176\nw = 288\nh = 32\nfill = \"wall\"\n");
+    std::fs::write(&path, walled).unwrap();
+
+    let out = bvb_level(&[path.to_str().unwrap(), "--check"]);
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert_eq!(out.status.code(), Some(1), "{stdout}");
+    assert!(stdout.contains("bases:        NOT connected"), "{stdout}");
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn descriptions_stay_within_the_image_limit() {
+    let dir = scratch_dir("tall");
+    let path = dir.join("tall.toml");
+    let shipped = include_str!("../../../levels/bvb_level_demon_lair/demon_lair.toml");
+    std::fs::write(&path, shipped.replace("height = 32", "height = 64")).unwrap();
+    let path = path.to_str().unwrap();
+
+    assert!(bvb_level(&[path, "--scale", "2"]).status.success());
+    let out = bvb_level(&[path, "--scale", "3"]);
+    assert_eq!(out.status.code(), Some(2));
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(
+        stderr.contains("288x1024 at scale 3 exceeds 2048 pixels"),
+        "{stderr}"
+    );
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn rejects_bad_arguments() {
+    for args in [
+        &["parking-lot", "--scale", "5"][..],
+        &["parking-lot", "--exit-side", "up"],
+        &["--seed", "1"],
+    ] {
+        let out = bvb_level(args);
+        assert_eq!(out.status.code(), Some(2), "{args:?}");
+        assert!(String::from_utf8_lossy(&out.stderr).starts_with("bvb-level: "));
+    }
+}